@@ -1,5 +1,6 @@
 use std::env;
 use hellodb::columns::header::ColumnHeader;
+use hellodb::columns::low_cardinality::downcast_low_cardinality_mut;
 use hellodb::io::column::*;
 use hellodb::io::db::*;
 use hellodb::io::serialize::*;
@@ -26,9 +27,9 @@ fn main()
                 [
                     ColumnHeader::new("id", TypeName::DBInt),
                     ColumnHeader::new("age", TypeName::DBInt),
-                    ColumnHeader::new("platform", TypeName::DBString),
-                    ColumnHeader::new("country", TypeName::DBString),
-                    ColumnHeader::new("gender", TypeName::DBString),
+                    ColumnHeader::new("platform", TypeName::DBLowCardinality),
+                    ColumnHeader::new("country", TypeName::DBLowCardinality),
+                    ColumnHeader::new("gender", TypeName::DBLowCardinality),
                     ColumnHeader::new("value", TypeName::DBFloat),
                 ]
             )
@@ -66,19 +67,19 @@ fn main()
         ).unwrap()
     );
     let mut platform_writer = make_col_writer(
-        TypeName::DBString,
+        TypeName::DBLowCardinality,
         File::create(
             table.col_path("platform").unwrap()
         ).unwrap()
     );
     let mut country_writer = make_col_writer(
-        TypeName::DBString,
+        TypeName::DBLowCardinality,
         File::create(
             table.col_path("country").unwrap()
         ).unwrap()
     );
     let mut gender_writer = make_col_writer(
-        TypeName::DBString,
+        TypeName::DBLowCardinality,
         File::create(table.col_path("gender").unwrap()
         ).unwrap()
     );
@@ -111,23 +112,26 @@ fn main()
         age_writer.write_col(age_col.data_ref()).unwrap();
 
         platform_col.resize(bsize);
-        for v in platform_col.downcast_data_iter_mut::<DBString>().unwrap()
+        let platform_lc = downcast_low_cardinality_mut(platform_col.data_mut().as_mut()).unwrap();
+        for row in 0..bsize
         {
-            *v = platforms[rng.gen_range(0..platforms.len())].to_string();
+            platform_lc.set_value(row, platforms[rng.gen_range(0..platforms.len())]);
         }
         platform_writer.write_col(platform_col.data_ref()).unwrap();
 
         country_col.resize(bsize);
-        for v in country_col.downcast_data_iter_mut::<DBString>().unwrap()
+        let country_lc = downcast_low_cardinality_mut(country_col.data_mut().as_mut()).unwrap();
+        for row in 0..bsize
         {
-            *v = countries[rng.gen_range(0..countries.len())].to_string();
+            country_lc.set_value(row, countries[rng.gen_range(0..countries.len())]);
         }
         country_writer.write_col(country_col.data_ref()).unwrap();
 
         gender_col.resize(bsize);
-        for v in gender_col.downcast_data_iter_mut::<DBString>().unwrap()
+        let gender_lc = downcast_low_cardinality_mut(gender_col.data_mut().as_mut()).unwrap();
+        for row in 0..bsize
         {
-            *v = genders[rng.gen_range(0..genders.len())].to_string();
+            gender_lc.set_value(row, genders[rng.gen_range(0..genders.len())]);
         }
         gender_writer.write_col(gender_col.data_ref()).unwrap();
 
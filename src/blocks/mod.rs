@@ -1,6 +1,7 @@
 pub mod source;
 use crate::DBResult;
 use crate::columns::Column;
+use crate::columns::header::ColumnHeader;
 use std::collections::HashMap;
 use std::cmp::min;
 use cli_table::{Cell, Table, TableStruct, CellStruct, format::{Justify }, Style };
@@ -14,6 +15,7 @@ pub struct ColumnBlock
     columns:HashMap<String, Column>,
     sources:HashMap<String, ColumnSourceRef>,
     col_order:Vec<String>,
+    fill_order:Vec<String>,
 }
 
 pub type BlockRef = Rc<RefCell<ColumnBlock>>;
@@ -22,7 +24,7 @@ impl ColumnBlock
 {
     pub fn new() -> ColumnBlock
     {
-        ColumnBlock { columns: HashMap::new(), sources: HashMap::new(), col_order: Vec::new() }
+        ColumnBlock { columns: HashMap::new(), sources: HashMap::new(), col_order: Vec::new(), fill_order: Vec::new() }
     }
 
     pub fn add(&mut self, column:Column, source:ColumnSourceRef) -> &mut ColumnBlock
@@ -38,6 +40,19 @@ impl ColumnBlock
         let col_name = column.name().to_string();
         self.columns.insert(col_name.clone(), column);
         self.sources.insert(col_name.clone(), source);
+        self.fill_order.push(col_name);
+    }
+
+    //Makes a column added by add_invisible part of the visible result
+    pub fn show(&mut self, name:&str)
+    {
+        self.col_order.push(name.to_string());
+    }
+
+    //Headers of the visible columns in their order
+    pub fn headers(&self) -> Vec<ColumnHeader>
+    {
+        self.col_order.iter().map(|name| self.columns[name].header().clone()).collect()
     }
 
     pub fn is_empty(&self) -> bool
@@ -117,9 +132,10 @@ impl ColumnBlock
     pub fn process(&mut self, rows:usize) -> DBResult<()>
     {
         self.resize(rows);
-        for name in self.col_order.iter()
+        //Invisible columns are filled too, visible ones may be calculated from them
+        for name in self.fill_order.iter()
         {
-            self.sources.get_mut(name).unwrap().fill_column(&mut self.columns, &name)?;
+            self.sources.get_mut(name).unwrap().fill_column(&mut self.columns, name)?;
         }
         Ok(())
     }
@@ -289,7 +305,7 @@ mod test {
                 vec!["a".to_string(), "b".to_string()], PlusBuilder::new().build(args.clone()).unwrap()
             )
         ).add(
-            Column::new(ColumnHeader::new("a + b == c", TypeName::DBInt)),
+            Column::new(ColumnHeader::new("a + b == c", TypeName::DBBool)),
             FunctionSource::new_ref(
                 vec!["c".to_string(), "a + b".to_string()], EqualBuilder::new().build(args.clone()).unwrap()
             )
@@ -298,11 +314,11 @@ mod test {
 
 
         block.process(4).unwrap();
-        let res:Vec<i64> = block.col_at("a + b == c").downcast_data_iter::<DBInt>().unwrap().copied().collect();
-        assert_eq!(res, vec![1, 0, 1, 0]);
+        let res:Vec<bool> = block.col_at("a + b == c").downcast_data_iter::<DBBool>().unwrap().copied().collect();
+        assert_eq!(res, vec![true, false, true, false]);
         block.process(4).unwrap();
-        let res:Vec<i64> = block.col_at("a + b == c").downcast_data_iter::<DBInt>().unwrap().copied().collect();
-        assert_eq!(res, vec![0, 1, 0, 1]);
+        let res:Vec<bool> = block.col_at("a + b == c").downcast_data_iter::<DBBool>().unwrap().copied().collect();
+        assert_eq!(res, vec![false, true, false, true]);
 
         if test_path.exists()
         {
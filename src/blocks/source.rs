@@ -4,6 +4,10 @@ use crate::io::column::{ColReaderPtr, make_col_reader};
 use crate::types::{TypeName, DBType};
 use crate::functions::regular::RegFunctionRef;
 use crate::columns::Column;
+use crate::columns::array::downcast_array_ref;
+use crate::types::types::DBBool;
+use std::cell::Cell;
+use std::collections::HashSet;
 pub trait ColumnSource
 {
     fn fill_column(&mut self, columns:&mut HashMap<String,Column>, col_name:&str) -> DBResult<()>;
@@ -86,6 +90,27 @@ impl<T:DBType> ColumnSource for ConstValueSource<T>
     }
 }
 
+//NULL literal
+pub struct NullSource {}
+
+impl NullSource
+{
+    pub fn new_ref() -> ColumnSourceRef
+    {
+        Box::new(Self{})
+    }
+}
+
+impl ColumnSource for NullSource
+{
+    fn fill_column(&mut self, columns:&mut HashMap<String, Column>, col_name:&str) -> DBResult<()>
+    {
+        columns.get_mut(col_name).unwrap().nulls_mut().unwrap().fill(true);
+
+        Ok(())
+    }
+}
+
 
 pub struct FunctionSource
 {
@@ -120,4 +145,218 @@ impl ColumnSource for FunctionSource
         }
         self.func.apply(args, columns.get_mut(col_name).unwrap())
     }
-}
\ No newline at end of file
+}
+//Column that repeats another column of the block under a different name, e.g. for `expr AS alias`
+pub struct AliasSource
+{
+    src :String,
+}
+
+impl AliasSource
+{
+    pub fn new(src:&str) -> AliasSource
+    {
+        AliasSource{src:src.to_string()}
+    }
+    pub fn new_ref(src:&str) -> ColumnSourceRef
+    {
+        Box::new(AliasSource::new(src))
+    }
+}
+
+impl ColumnSource for AliasSource
+{
+    fn fill_column(&mut self, columns:&mut HashMap<String, Column>, col_name:&str) -> DBResult<()>
+    {
+        let mut dest = columns.remove(col_name).unwrap();
+        columns[&self.src].copy_to(&mut dest, 0);
+        columns.insert(col_name.to_string(), dest);
+        Ok(())
+    }
+}
+
+//Column gathered by row numbers from a column of another block, e.g. one side of a join
+pub struct IndexedSource
+{
+    block :BlockRef,
+    src :String,
+    rows :Rc<RefCell<Vec<usize>>>,
+}
+
+impl IndexedSource
+{
+    pub fn new(block:BlockRef, src:&str, rows:Rc<RefCell<Vec<usize>>>) -> IndexedSource
+    {
+        IndexedSource{block, src:src.to_string(), rows}
+    }
+    pub fn new_ref(block:BlockRef, src:&str, rows:Rc<RefCell<Vec<usize>>>) -> ColumnSourceRef
+    {
+        Box::new(IndexedSource::new(block, src, rows))
+    }
+}
+
+impl ColumnSource for IndexedSource
+{
+    fn fill_column(&mut self, columns:&mut HashMap<String, Column>, col_name:&str) -> DBResult<()>
+    {
+        let block = self.block.borrow();
+        block.col_at(&self.src).copy_indexed_to(
+            columns.get_mut(col_name).unwrap(),
+            &self.rows.borrow()
+        );
+        Ok(())
+    }
+}
+
+//Elements of the array column of another block at the given positions of its values, see ArrayJoinProcessor
+pub struct ArrayElementsSource
+{
+    block :BlockRef,
+    src :String,
+    elems :Rc<RefCell<Vec<usize>>>,
+}
+
+impl ArrayElementsSource
+{
+    pub fn new(block:BlockRef, src:&str, elems:Rc<RefCell<Vec<usize>>>) -> ArrayElementsSource
+    {
+        ArrayElementsSource{block, src:src.to_string(), elems}
+    }
+    pub fn new_ref(block:BlockRef, src:&str, elems:Rc<RefCell<Vec<usize>>>) -> ColumnSourceRef
+    {
+        Box::new(ArrayElementsSource::new(block, src, elems))
+    }
+}
+
+impl ColumnSource for ArrayElementsSource
+{
+    fn fill_column(&mut self, columns:&mut HashMap<String, Column>, col_name:&str) -> DBResult<()>
+    {
+        let block = self.block.borrow();
+        let arr = downcast_array_ref(block.col_at(&self.src).data_ref().as_ref()).unwrap();
+        arr.values().copy_indexed_to(columns.get_mut(col_name).unwrap().data_mut(), &self.elems.borrow());
+        Ok(())
+    }
+}
+
+//Column copied entirely from a column of another block, e.g. the output of the previous step
+pub struct BlockSource
+{
+    block :BlockRef,
+    src :String,
+}
+
+impl BlockSource
+{
+    pub fn new(block:BlockRef, src:&str) -> BlockSource
+    {
+        BlockSource{block, src:src.to_string()}
+    }
+    pub fn new_ref(block:BlockRef, src:&str) -> ColumnSourceRef
+    {
+        Box::new(BlockSource::new(block, src))
+    }
+}
+
+impl ColumnSource for BlockSource
+{
+    fn fill_column(&mut self, columns:&mut HashMap<String, Column>, col_name:&str) -> DBResult<()>
+    {
+        self.block.borrow().col_at(&self.src).copy_to(columns.get_mut(col_name).unwrap(), 0);
+        Ok(())
+    }
+}
+
+//Column taken from one of several blocks, current is switched by the processor feeding them one by one
+pub struct ConcatSource
+{
+    blocks :Vec<BlockRef>,
+    srcs :Vec<String>,
+    current :Rc<Cell<usize>>,
+}
+
+impl ConcatSource
+{
+    pub fn new(blocks:Vec<BlockRef>, srcs:Vec<String>, current:Rc<Cell<usize>>) -> ConcatSource
+    {
+        ConcatSource{blocks, srcs, current}
+    }
+    pub fn new_ref(blocks:Vec<BlockRef>, srcs:Vec<String>, current:Rc<Cell<usize>>) -> ColumnSourceRef
+    {
+        Box::new(ConcatSource::new(blocks, srcs, current))
+    }
+}
+
+impl ColumnSource for ConcatSource
+{
+    fn fill_column(&mut self, columns:&mut HashMap<String, Column>, col_name:&str) -> DBResult<()>
+    {
+        let current = self.current.get();
+        self.blocks[current].borrow().col_at(&self.srcs[current]).copy_to(columns.get_mut(col_name).unwrap(), 0);
+        Ok(())
+    }
+}
+
+//Int 1 if the row of the args columns is among the rows of the block, 0 otherwise (or the opposite if negate).
+//The block is hashed on the first fill, when it is already calculated
+pub struct RowInBlockSource
+{
+    block :BlockRef,
+    args :Vec<String>,
+    negate :bool,
+    keys :Option<HashSet<Vec<u8>>>,
+}
+
+impl RowInBlockSource
+{
+    pub fn new(block:BlockRef, args:Vec<String>, negate:bool) -> RowInBlockSource
+    {
+        RowInBlockSource{block, args, negate, keys:None}
+    }
+    pub fn new_ref(block:BlockRef, args:Vec<String>, negate:bool) -> ColumnSourceRef
+    {
+        Box::new(RowInBlockSource::new(block, args, negate))
+    }
+
+    fn build_keys(&self) -> HashSet<Vec<u8>>
+    {
+        let block = self.block.borrow();
+        let headers = block.headers();
+        let mut keys = HashSet::<Vec<u8>>::new();
+        for row in 0..block.rows_len()
+        {
+            let mut key = Vec::<u8>::new();
+            for h in headers.iter()
+            {
+                block.col_at(h.name()).pack_value_to(row, &mut key);
+            }
+            keys.insert(key);
+        }
+        keys
+    }
+}
+
+impl ColumnSource for RowInBlockSource
+{
+    fn fill_column(&mut self, columns:&mut HashMap<String, Column>, col_name:&str) -> DBResult<()>
+    {
+        if self.keys.is_none()
+        {
+            self.keys = Some(self.build_keys());
+        }
+        let keys = self.keys.as_ref().unwrap();
+        let mut dest = columns.remove(col_name).unwrap();
+        let mut key = Vec::<u8>::new();
+        for (row, v) in dest.downcast_data_iter_mut::<DBBool>().unwrap().enumerate()
+        {
+            key.clear();
+            for name in self.args.iter()
+            {
+                columns[name].pack_value_to(row, &mut key);
+            }
+            *v = keys.contains(&key) != self.negate;
+        }
+        columns.insert(col_name.to_string(), dest);
+        Ok(())
+    }
+}
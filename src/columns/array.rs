@@ -0,0 +1,304 @@
+use super::data::*;
+use crate::io::serialize::ByteSerialize;
+use crate::types::TypeName;
+use crate::types::types::DBBool;
+use std::any::Any;
+use std::cmp::Ordering;
+use std::ops::Range;
+use itertools::izip;
+
+//Values of Array(T): the elements of all rows flattened into one storage of T and the end offsets of the rows in it
+pub struct ArrayStorage
+{
+    offsets :Vec<u64>,
+    values :StoragePtr,
+    elem :TypeName,
+}
+
+impl ArrayStorage
+{
+    pub fn new(elem:TypeName) -> Self
+    {
+        Self{offsets:Vec::new(), values:make_storage(elem.clone()), elem}
+    }
+    pub fn elem_type(&self) -> &TypeName
+    {
+        &self.elem
+    }
+    pub fn offsets(&self) -> &Vec<u64>
+    {
+        &self.offsets
+    }
+    pub fn values(&self) -> &StoragePtr
+    {
+        &self.values
+    }
+    //The caller keeps the offsets and the values consistent, e.g. the reader of the column
+    pub fn offsets_mut(&mut self) -> &mut Vec<u64>
+    {
+        &mut self.offsets
+    }
+    pub fn values_mut(&mut self) -> &mut StoragePtr
+    {
+        &mut self.values
+    }
+    fn start(&self, row:usize) -> usize
+    {
+        if row == 0 {0} else {self.offsets[row - 1] as usize}
+    }
+    //Positions of the elements of the row in the values
+    pub fn row_range(&self, row:usize) -> Range<usize>
+    {
+        self.start(row)..self.offsets[row] as usize
+    }
+    pub fn row_len(&self, row:usize) -> usize
+    {
+        self.row_range(row).len()
+    }
+
+    //Replaces the rows from at by the rows of the given lengths, their elements are the values one after another
+    pub fn replace_rows(&mut self, at:usize, lens:&[usize], values:&StoragePtr)
+    {
+        let rows = lens.len().min(self.len().saturating_sub(at));
+        let (start, end) = (self.start(at), self.start(at + rows));
+        let new_len:usize = lens[..rows].iter().sum();
+        //The elements of the rows after the replaced ones are moved
+        let tail_len = self.values.len() - end;
+        let mut tail = make_storage(self.elem.clone());
+        if tail_len > 0
+        {
+            tail.resize(tail_len);
+            self.values.copy_indexed_to(&mut tail, &(end..self.values.len()).collect::<Vec<usize>>());
+        }
+        self.values.resize(start + new_len + tail_len);
+        values.copy_to(&mut self.values, start);
+        if tail_len > 0
+        {
+            tail.copy_to(&mut self.values, start + new_len);
+        }
+        let mut pos = start as u64;
+        for (o, l) in izip!(self.offsets[at..at + rows].iter_mut(), lens.iter())
+        {
+            pos += *l as u64;
+            *o = pos;
+        }
+        for o in self.offsets[at + rows..].iter_mut()
+        {
+            *o = *o - end as u64 + pos;
+        }
+    }
+
+    //Copies the rows to dest from at
+    fn copy_rows_to(&self, rows:impl Iterator<Item = usize>, dest:&mut Box<dyn ColumnStorage>, at:usize)
+    {
+        let mut lens = Vec::<usize>::new();
+        let mut indices = Vec::<usize>::new();
+        for row in rows
+        {
+            lens.push(self.row_len(row));
+            indices.extend(self.row_range(row));
+        }
+        let mut values = make_storage(self.elem.clone());
+        values.resize(indices.len());
+        self.values.copy_indexed_to(&mut values, &indices);
+        downcast_array_mut(dest.as_mut()).unwrap().replace_rows(at, &lens, &values);
+        clear_nulls(dest, at, lens.len());
+    }
+
+    //Strings and dates are quoted, as in the array literals
+    fn elem_to_string(&self, i:usize) -> String
+    {
+        let s = self.values.to_string_at(i);
+        let quoted = matches!(
+            self.elem.base(), TypeName::DBString | TypeName::DBLowCardinality | TypeName::DBDate | TypeName::DBDateTime
+        );
+        if quoted && !self.values.nulls().is_some_and(|nulls| nulls[i]) {format!("'{}'", s)} else {s}
+    }
+}
+
+//Array storage of the column, possibly wrapped into Nullable
+pub fn downcast_array_ref(col:&dyn ColumnStorage) -> Option<&ArrayStorage>
+{
+    match col.as_any().downcast_ref::<ArrayStorage>() {
+        Some(arr) => Some(arr),
+        None => col.inner().and_then(|inner| downcast_array_ref(inner.as_ref()))
+    }
+}
+
+pub fn downcast_array_mut(col:&mut dyn ColumnStorage) -> Option<&mut ArrayStorage>
+{
+    if col.as_any().is::<ArrayStorage>()
+    {
+        return col.as_mut_any().downcast_mut::<ArrayStorage>();
+    }
+    col.inner_mut().and_then(|inner| downcast_array_mut(inner.as_mut()))
+}
+
+impl ColumnStorage for ArrayStorage
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+    fn as_mut_any(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn len(&self) -> usize
+    {
+        self.offsets.len()
+    }
+    //New rows are empty arrays
+    fn resize(&mut self, size:usize)
+    {
+        let last = if size > 0 && size <= self.len() {self.offsets[size - 1]} else {self.offsets.last().copied().unwrap_or(0)};
+        self.offsets.resize(size, last);
+        self.values.resize(last as usize);
+    }
+    fn fit_offset_limit(&mut self, offset:usize, limit:Option<usize>)
+    {
+        let rows = limit.map_or(self.len() - offset, |l| l.min(self.len() - offset));
+        let (start, end) = (self.start(offset), self.start(offset + rows));
+        self.values.fit_offset_limit(start, Some(end - start));
+        self.offsets = self.offsets[offset..offset + rows].iter().map(|o| o - start as u64).collect();
+    }
+
+    fn copy_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize)
+    {
+        let dest_arr = downcast_array_mut(dest.as_mut()).unwrap();
+        let lens:Vec<usize> = (0..self.len()).map(|row| self.row_len(row)).collect();
+        dest_arr.replace_rows(offset, &lens, &self.values);
+        clear_nulls(dest, offset, self.len());
+    }
+    fn copy_filtered_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize, filter:&Box<dyn ColumnStorage>)
+    {
+        let flt = downcast_storage_ref::<DBBool>(filter.as_ref()).unwrap();
+        self.copy_rows_to(flt.iter().enumerate().filter(|(_, f)| **f).map(|(row, _)| row), dest, offset);
+    }
+    fn copy_indexed_to(&self, dest:&mut Box<dyn ColumnStorage>, indices:&[usize])
+    {
+        self.copy_rows_to(indices.iter().copied(), dest, 0);
+    }
+
+    //The number of elements followed by the packed elements
+    fn pack_value_to(&self, at:usize, dest: &mut Vec<u8>)
+    {
+        (self.row_len(at) as u64).to_byte(dest).unwrap();
+        for i in self.row_range(at)
+        {
+            self.values.pack_value_to(i, dest);
+        }
+    }
+    fn unpack_value_from(&mut self, at:usize, src: &mut &[u8])
+    {
+        let mut len:u64 = 0;
+        len.from_byte(src).unwrap();
+        let mut values = make_storage(self.elem.clone());
+        values.resize(len as usize);
+        for i in 0..len as usize
+        {
+            values.unpack_value_from(i, src);
+        }
+        self.replace_rows(at, &[len as usize], &values);
+    }
+
+    //Arrays are compared element by element, a prefix goes first
+    fn elems_cmp(&self, a_index:usize, b_index:usize) -> Ordering
+    {
+        let (a, b) = (self.row_range(a_index), self.row_range(b_index));
+        for (i, j) in izip!(a.clone(), b.clone())
+        {
+            match self.values.elems_cmp(i, j) {
+                Ordering::Equal => {},
+                other => {return other;}
+            }
+        }
+        a.len().cmp(&b.len())
+    }
+    fn permute(&mut self, perms: &[usize])
+    {
+        let mut indices = Vec::<usize>::with_capacity(self.values.len());
+        let mut offsets = Vec::<u64>::with_capacity(perms.len());
+        for row in perms.iter()
+        {
+            indices.extend(self.row_range(*row));
+            offsets.push(indices.len() as u64);
+        }
+        let mut values = make_storage(self.elem.clone());
+        values.resize(indices.len());
+        self.values.copy_indexed_to(&mut values, &indices);
+        self.values = values;
+        self.offsets = offsets;
+    }
+
+    fn to_string_at(&self, n:usize) -> String
+    {
+        let elems:Vec<String> = self.row_range(n).map(|i| self.elem_to_string(i)).collect();
+        format!("[{}]", elems.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::types::*;
+
+    fn strings(col:&StoragePtr) -> Vec<String>
+    {
+        (0..col.len()).map(|i| col.to_string_at(i)).collect()
+    }
+
+    //Int arrays of the given rows
+    fn int_arrays(rows:&[&[i64]]) -> StoragePtr
+    {
+        let mut res = make_storage(TypeName::DBArray(Box::new(TypeName::DBInt)));
+        res.resize(rows.len());
+        for (row, elems) in rows.iter().enumerate()
+        {
+            let mut values = make_storage(TypeName::DBInt);
+            values.resize(elems.len());
+            downcast_storage_mut::<DBInt>(values.as_mut()).unwrap().data_mut().clone_from_slice(elems);
+            downcast_array_mut(res.as_mut()).unwrap().replace_rows(row, &[elems.len()], &values);
+        }
+        res
+    }
+
+    #[test]
+    fn array_storage()
+    {
+        let mut c = int_arrays(&[&[1, 2], &[], &[3, 4, 5], &[1]]);
+        assert_eq!(strings(&c), vec!["[1, 2]", "[]", "[3, 4, 5]", "[1]"]);
+        assert_eq!(*downcast_array_ref(c.as_ref()).unwrap().offsets(), vec![2, 2, 5, 6]);
+        assert_eq!(c.elems_cmp(3, 0), Ordering::Less);
+        assert_eq!(c.elems_cmp(1, 3), Ordering::Less);
+
+        let mut dest = make_storage(TypeName::DBArray(Box::new(TypeName::DBInt)).to_nullable());
+        dest.resize(3);
+        dest.nulls_mut().unwrap()[0] = true;
+        c.copy_indexed_to(&mut dest, &[2, 0]);
+        assert_eq!(strings(&dest), vec!["[3, 4, 5]", "[1, 2]", "[]"]);
+
+        let mut packed = Vec::<u8>::new();
+        c.pack_value_to(2, &mut packed);
+        c.unpack_value_from(0, &mut packed.as_slice());
+        assert_eq!(strings(&c), vec!["[3, 4, 5]", "[]", "[3, 4, 5]", "[1]"]);
+
+        c.permute(&[3, 0, 1, 2]);
+        c.fit_offset_limit(1, Some(2));
+        assert_eq!(strings(&c), vec!["[3, 4, 5]", "[]"]);
+        c.resize(3);
+        assert_eq!(strings(&c), vec!["[3, 4, 5]", "[]", "[]"]);
+        c.resize(1);
+        assert_eq!(downcast_array_ref(c.as_ref()).unwrap().values().len(), 3);
+
+        let mut strs = make_storage(TypeName::DBArray(Box::new(TypeName::DBString.to_nullable())));
+        strs.resize(1);
+        let mut values = make_storage(TypeName::DBString.to_nullable());
+        values.resize(2);
+        downcast_storage_mut::<DBString>(values.as_mut()).unwrap()[0] = "a".to_string();
+        values.nulls_mut().unwrap()[1] = true;
+        downcast_array_mut(strs.as_mut()).unwrap().replace_rows(0, &[2], &values);
+        assert_eq!(strs.to_string_at(0), "['a', NULL]");
+    }
+}
@@ -1,10 +1,15 @@
 use std::ops::{DerefMut, Deref};
 use crate::types::{DBType, TypeName};
 use crate::types::types::*;
+use crate::types::decimal::MAX_PRECISION_64;
 use std::any::Any;
 use itertools::izip;
 use crate::io::serialize::*;
 use std::cmp::Ordering;
+use super::nullable::NullableStorage;
+use super::decimal::DecimalStorage;
+use super::low_cardinality::LowCardinalityStorage;
+use super::array::ArrayStorage;
 
 #[derive(Debug, Clone)]
 pub struct ColumnDataStorage<T:DBType> {
@@ -54,8 +59,16 @@ pub trait ColumnStorage {
 
     fn fit_offset_limit(&mut self, offset:usize, limit:Option<usize>);
 
+    //Null map of Nullable column, true for NULL
+    fn nulls(&self) -> Option<&Vec<bool>> {None}
+    fn nulls_mut(&mut self) -> Option<&mut Vec<bool>> {None}
+    //Storage of the values wrapped by Nullable or Decimal storage
+    fn inner(&self) -> Option<&StoragePtr> {None}
+    fn inner_mut(&mut self) -> Option<&mut StoragePtr> {None}
+
     fn copy_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize);
     fn copy_filtered_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize, filter:&Box<dyn ColumnStorage>);
+    fn copy_indexed_to(&self, dest:&mut Box<dyn ColumnStorage>, indices:&[usize]);
 
     fn pack_value_to(&self, at:usize, dest: &mut Vec<u8>);
     fn unpack_value_from(&mut self, at:usize, src: &mut &[u8]);
@@ -129,22 +142,35 @@ impl<T:DBType>  ColumnStorage for ColumnDataStorage<T>
         {
             *d = s.clone();
         }
-
+        clear_nulls(dest, offset, self.data.len());
     }
 
     fn copy_filtered_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize, filter:&Box<dyn ColumnStorage>)
     {
         let mut dest_itr = downcast_storage_mut::<T>(dest).unwrap().iter_mut().skip(offset);
-        let mut filter_itr = downcast_storage_ref::<DBInt>(filter).unwrap().iter();
+        let mut filter_itr = downcast_storage_ref::<DBBool>(filter).unwrap().iter();
         let mut self_itr = self.data.iter();
+        let mut copied = 0;
         while let Some(flt) = filter_itr.next()
         {
             let src = self_itr.next().unwrap();
-            if *flt == 1
+            if *flt
             {
                 *dest_itr.next().unwrap() = src.clone();
+                copied += 1;
             }
         }
+        clear_nulls(dest, offset, copied);
+    }
+
+    fn copy_indexed_to(&self, dest:&mut Box<dyn ColumnStorage>, indices:&[usize])
+    {
+        let dest_itr = downcast_storage_mut::<T>(dest).unwrap().iter_mut();
+        for (i, d) in izip!(indices.iter(), dest_itr)
+        {
+            *d = self.data[*i].clone();
+        }
+        clear_nulls(dest, 0, indices.len());
     }
 
     fn pack_value_to(&self, at:usize, dest: &mut Vec<u8>)
@@ -195,6 +221,22 @@ impl ColumnStorage for StoragePtr {
     {
         self.as_mut().fit_offset_limit(offset, limit);
     }
+    fn nulls(&self) -> Option<&Vec<bool>>
+    {
+        self.as_ref().nulls()
+    }
+    fn nulls_mut(&mut self) -> Option<&mut Vec<bool>>
+    {
+        self.as_mut().nulls_mut()
+    }
+    fn inner(&self) -> Option<&StoragePtr>
+    {
+        self.as_ref().inner()
+    }
+    fn inner_mut(&mut self) -> Option<&mut StoragePtr>
+    {
+        self.as_mut().inner_mut()
+    }
     fn to_string_at(&self, n:usize) -> String
     {
         self.as_ref().to_string_at(n)
@@ -207,6 +249,10 @@ impl ColumnStorage for StoragePtr {
     {
         self.as_ref().copy_filtered_to(dest, offset, filter)
     }
+    fn copy_indexed_to(&self, dest:&mut Box<dyn ColumnStorage>, indices:&[usize])
+    {
+        self.as_ref().copy_indexed_to(dest, indices)
+    }
 
     fn pack_value_to(&self, at:usize, dest: &mut Vec<u8>)
     {
@@ -233,21 +279,61 @@ pub fn is_storage_of<T:DBType>(col:&dyn ColumnStorage) -> bool
     col.as_any().is::<ColumnDataStorage<T>>()
 }
 
+//Values of Nullable(T) are got as T, values of Decimal as its integer storage
 pub fn downcast_storage_ref<T:DBType>(col:&dyn ColumnStorage) -> Option<&ColumnDataStorage<T>>
 {
-    col.as_any().downcast_ref::<ColumnDataStorage<T>>()
+    match col.inner() {
+        Some(inner) => downcast_storage_ref::<T>(inner.as_ref()),
+        None => col.as_any().downcast_ref::<ColumnDataStorage<T>>()
+    }
 }
 
 pub fn downcast_storage_mut<T:DBType>(col:&mut dyn ColumnStorage) -> Option<&mut ColumnDataStorage<T>>
 {
+    if col.inner().is_some()
+    {
+        return downcast_storage_mut::<T>(col.inner_mut().unwrap().as_mut());
+    }
     col.as_mut_any().downcast_mut::<ColumnDataStorage<T>>()
 }
 
+//Rows written by the copy of non Nullable values aren't NULL anymore
+pub(super) fn clear_nulls(dest:&mut Box<dyn ColumnStorage>, offset:usize, len:usize)
+{
+    if let Some(nulls) = dest.nulls_mut()
+    {
+        for n in nulls.iter_mut().skip(offset).take(len)
+        {
+            *n = false;
+        }
+    }
+}
+
 pub fn make_storage(name:TypeName) -> StoragePtr {
         match name {
             TypeName::DBInt => Box::new(ColumnDataStorage::<DBInt>::new()) as StoragePtr,
+            TypeName::DBInt8 => Box::new(ColumnDataStorage::<DBInt8>::new()) as StoragePtr,
+            TypeName::DBInt16 => Box::new(ColumnDataStorage::<DBInt16>::new()) as StoragePtr,
+            TypeName::DBInt32 => Box::new(ColumnDataStorage::<DBInt32>::new()) as StoragePtr,
+            TypeName::DBUInt8 => Box::new(ColumnDataStorage::<DBUInt8>::new()) as StoragePtr,
+            TypeName::DBUInt16 => Box::new(ColumnDataStorage::<DBUInt16>::new()) as StoragePtr,
+            TypeName::DBUInt32 => Box::new(ColumnDataStorage::<DBUInt32>::new()) as StoragePtr,
+            TypeName::DBUInt64 => Box::new(ColumnDataStorage::<DBUInt64>::new()) as StoragePtr,
             TypeName::DBFloat => Box::new(ColumnDataStorage::<DBFloat>::new()) as StoragePtr,
-            TypeName::DBString => Box::new(ColumnDataStorage::<DBString>::new()) as StoragePtr
+            TypeName::DBString => Box::new(ColumnDataStorage::<DBString>::new()) as StoragePtr,
+            TypeName::DBBool => Box::new(ColumnDataStorage::<DBBool>::new()) as StoragePtr,
+            TypeName::DBDate => Box::new(ColumnDataStorage::<DBDate>::new()) as StoragePtr,
+            TypeName::DBDateTime => Box::new(ColumnDataStorage::<DBDateTime>::new()) as StoragePtr,
+            TypeName::DBNothing => Box::new(ColumnDataStorage::<DBNothing>::new()) as StoragePtr,
+            TypeName::DBLowCardinality => Box::new(LowCardinalityStorage::new()) as StoragePtr,
+            TypeName::DBDecimal(precision, scale) if precision <= MAX_PRECISION_64 => Box::new(
+                DecimalStorage::new(Box::new(ColumnDataStorage::<DBDecimal64>::new()), scale)
+            ) as StoragePtr,
+            TypeName::DBDecimal(_, scale) => Box::new(
+                DecimalStorage::new(Box::new(ColumnDataStorage::<DBDecimal128>::new()), scale)
+            ) as StoragePtr,
+            TypeName::DBNullable(inner) => Box::new(NullableStorage::new(make_storage(*inner))) as StoragePtr,
+            TypeName::DBArray(elem) => Box::new(ArrayStorage::new(*elem)) as StoragePtr
         }
 }
 
@@ -393,4 +479,20 @@ mod test {
         assert_eq!(cb[0], cb[1]);
         assert_eq!(cc[0], cc[1]);
     }
+
+    #[test]
+    fn copy_indexed()
+    {
+        let mut c = ColumnDataStorage::<DBString>::new();
+        c.push("a".to_string());
+        c.push("b".to_string());
+        c.push("c".to_string());
+        let mut dest = make_storage(TypeName::DBString);
+        dest.resize(4);
+        c.copy_indexed_to(&mut dest, &[2, 0, 0, 1]);
+        assert_eq!(
+            *downcast_storage_ref::<DBString>(&dest).unwrap().data_ref(),
+            vec!["c".to_string(), "a".to_string(), "a".to_string(), "b".to_string()]
+        );
+    }
 }
\ No newline at end of file
@@ -0,0 +1,118 @@
+use super::data::*;
+use crate::types::decimal::format_decimal;
+use crate::types::types::{DBDecimal64, DBDecimal128};
+use std::any::Any;
+use std::cmp::Ordering;
+
+//Values of Decimal(P, S) with the scale for display, the values are i64 or i128 scaled by 10^S
+pub struct DecimalStorage
+{
+    values :StoragePtr,
+    scale :u8,
+}
+
+impl DecimalStorage
+{
+    pub fn new(values:StoragePtr, scale:u8) -> Self
+    {
+        Self{values, scale}
+    }
+}
+
+impl ColumnStorage for DecimalStorage
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+    fn as_mut_any(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+    fn inner(&self) -> Option<&StoragePtr>
+    {
+        Some(&self.values)
+    }
+    fn inner_mut(&mut self) -> Option<&mut StoragePtr>
+    {
+        Some(&mut self.values)
+    }
+
+    fn len(&self) -> usize
+    {
+        self.values.len()
+    }
+    fn resize(&mut self, size:usize)
+    {
+        self.values.resize(size);
+    }
+    fn fit_offset_limit(&mut self, offset:usize, limit:Option<usize>)
+    {
+        self.values.fit_offset_limit(offset, limit);
+    }
+
+    fn copy_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize)
+    {
+        self.values.copy_to(dest, offset);
+    }
+    fn copy_filtered_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize, filter:&Box<dyn ColumnStorage>)
+    {
+        self.values.copy_filtered_to(dest, offset, filter);
+    }
+    fn copy_indexed_to(&self, dest:&mut Box<dyn ColumnStorage>, indices:&[usize])
+    {
+        self.values.copy_indexed_to(dest, indices);
+    }
+
+    fn pack_value_to(&self, at:usize, dest: &mut Vec<u8>)
+    {
+        self.values.pack_value_to(at, dest);
+    }
+    fn unpack_value_from(&mut self, at:usize, src: &mut &[u8])
+    {
+        self.values.unpack_value_from(at, src);
+    }
+
+    fn elems_cmp(&self, a_index:usize, b_index:usize) -> Ordering
+    {
+        self.values.elems_cmp(a_index, b_index)
+    }
+    fn permute(&mut self, perms: &[usize])
+    {
+        self.values.permute(perms);
+    }
+
+    fn to_string_at(&self, n:usize) -> String
+    {
+        let v = match downcast_storage_ref::<DBDecimal64>(self.values.as_ref()) {
+            Some(d) => d[n] as i128,
+            None => downcast_storage_ref::<DBDecimal128>(self.values.as_ref()).unwrap()[n]
+        };
+        format_decimal(v, self.scale)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::TypeName;
+    use crate::types::types::*;
+
+    #[test]
+    fn decimal_storage()
+    {
+        let mut c = make_storage(TypeName::DBDecimal(10, 2).to_nullable());
+        c.resize(3);
+        downcast_storage_mut::<DBDecimal64>(c.as_mut()).unwrap().data_mut().clone_from_slice(&[1250, -5, 0]);
+        c.nulls_mut().unwrap()[2] = true;
+        assert_eq!(c.to_string_at(0), "12.50");
+        assert_eq!(c.to_string_at(1), "-0.05");
+        assert_eq!(c.to_string_at(2), "NULL");
+        c.permute(&[1, 0, 2]);
+        assert_eq!(c.to_string_at(0), "-0.05");
+
+        let wide = make_storage(TypeName::DBDecimal(38, 4));
+        assert!(downcast_storage_ref::<DBDecimal128>(wide.as_ref()).is_some());
+        assert!(downcast_storage_ref::<DBDecimal64>(wide.as_ref()).is_none());
+    }
+}
@@ -17,7 +17,7 @@ impl ColumnHeader {
     }
     pub fn type_name(&self) -> TypeName
     {
-        self.type_name
+        self.type_name.clone()
     }
 }
 
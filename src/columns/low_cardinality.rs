@@ -0,0 +1,240 @@
+use super::data::*;
+use crate::io::serialize::ByteSerialize;
+use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use itertools::izip;
+
+//Values of LowCardinality(String): the codes of the rows in the dictionary of the distinct strings.
+//The first string of the dictionary is always empty, so the default code 0 is the default value
+pub struct LowCardinalityStorage
+{
+    codes :Vec<u32>,
+    dict :Vec<String>,
+    index :HashMap<String, u32>,
+}
+
+impl LowCardinalityStorage
+{
+    pub fn new() -> Self
+    {
+        let mut res = Self{codes:Vec::new(), dict:Vec::new(), index:HashMap::new()};
+        res.code_of("");
+        res
+    }
+    pub fn codes(&self) -> &Vec<u32>
+    {
+        &self.codes
+    }
+    pub fn codes_mut(&mut self) -> &mut Vec<u32>
+    {
+        &mut self.codes
+    }
+    pub fn dict(&self) -> &Vec<String>
+    {
+        &self.dict
+    }
+    pub fn value(&self, row:usize) -> &str
+    {
+        &self.dict[self.codes[row] as usize]
+    }
+    pub fn set_value(&mut self, row:usize, s:&str)
+    {
+        self.codes[row] = self.code_of(s);
+    }
+    pub fn find_code(&self, s:&str) -> Option<u32>
+    {
+        self.index.get(s).copied()
+    }
+    //Code of the string, it's added to the dictionary if missing
+    pub fn code_of(&mut self, s:&str) -> u32
+    {
+        if let Some(code) = self.index.get(s)
+        {
+            return *code;
+        }
+        let code = self.dict.len() as u32;
+        self.dict.push(s.to_string());
+        self.index.insert(s.to_string(), code);
+        code
+    }
+    //Replaces the dictionary, the codes must be remapped by the caller
+    pub fn set_dict(&mut self, dict:&[String])
+    {
+        self.dict.clear();
+        self.index.clear();
+        self.code_of("");
+        for s in dict.iter()
+        {
+            self.code_of(s);
+        }
+    }
+    //Codes of the dictionary entries in the dictionary of dest
+    pub fn translation_to(&self, dest:&mut LowCardinalityStorage) -> Vec<u32>
+    {
+        self.dict.iter().map(|s| dest.code_of(s)).collect()
+    }
+}
+
+impl Default for LowCardinalityStorage
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+//LowCardinality storage of the column, possibly wrapped into Nullable
+pub fn downcast_low_cardinality_ref(col:&dyn ColumnStorage) -> Option<&LowCardinalityStorage>
+{
+    match col.as_any().downcast_ref::<LowCardinalityStorage>() {
+        Some(lc) => Some(lc),
+        None => col.inner().and_then(|inner| downcast_low_cardinality_ref(inner.as_ref()))
+    }
+}
+
+pub fn downcast_low_cardinality_mut(col:&mut dyn ColumnStorage) -> Option<&mut LowCardinalityStorage>
+{
+    if col.as_any().is::<LowCardinalityStorage>()
+    {
+        return col.as_mut_any().downcast_mut::<LowCardinalityStorage>();
+    }
+    col.inner_mut().and_then(|inner| downcast_low_cardinality_mut(inner.as_mut()))
+}
+
+impl ColumnStorage for LowCardinalityStorage
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+    fn as_mut_any(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn len(&self) -> usize
+    {
+        self.codes.len()
+    }
+    fn resize(&mut self, size:usize)
+    {
+        self.codes.resize(size, 0);
+    }
+    fn fit_offset_limit(&mut self, offset:usize, limit:Option<usize>)
+    {
+        self.codes.drain(..offset);
+        if let Some(l) = limit
+        {
+            self.codes.truncate(l);
+        }
+    }
+
+    //The codes are translated to the dictionary of dest
+    fn copy_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize)
+    {
+        let dest_lc = downcast_low_cardinality_mut(dest.as_mut()).unwrap();
+        let codes = self.translation_to(dest_lc);
+        for (s, d) in izip!(self.codes.iter(), dest_lc.codes.iter_mut().skip(offset))
+        {
+            *d = codes[*s as usize];
+        }
+        clear_nulls(dest, offset, self.codes.len());
+    }
+    fn copy_filtered_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize, filter:&Box<dyn ColumnStorage>)
+    {
+        let dest_lc = downcast_low_cardinality_mut(dest.as_mut()).unwrap();
+        let codes = self.translation_to(dest_lc);
+        let flt = downcast_storage_ref::<crate::types::types::DBBool>(filter.as_ref()).unwrap();
+        let filtered = izip!(self.codes.iter(), flt.iter()).filter(|(_, f)| **f).map(|(c, _)| c);
+        let mut copied = 0;
+        for (s, d) in izip!(filtered, dest_lc.codes.iter_mut().skip(offset))
+        {
+            *d = codes[*s as usize];
+            copied += 1;
+        }
+        clear_nulls(dest, offset, copied);
+    }
+    fn copy_indexed_to(&self, dest:&mut Box<dyn ColumnStorage>, indices:&[usize])
+    {
+        let dest_lc = downcast_low_cardinality_mut(dest.as_mut()).unwrap();
+        let codes = self.translation_to(dest_lc);
+        for (i, d) in izip!(indices.iter(), dest_lc.codes.iter_mut())
+        {
+            *d = codes[self.codes[*i] as usize];
+        }
+        clear_nulls(dest, 0, indices.len());
+    }
+
+    //Packed as the string, since the codes of different storages don't match
+    fn pack_value_to(&self, at:usize, dest: &mut Vec<u8>)
+    {
+        self.dict[self.codes[at] as usize].to_byte(dest).unwrap();
+    }
+    fn unpack_value_from(&mut self, at:usize, src: &mut &[u8])
+    {
+        let mut s = String::new();
+        s.from_byte(src).unwrap();
+        self.set_value(at, &s);
+    }
+
+    fn elems_cmp(&self, a_index:usize, b_index:usize) -> Ordering
+    {
+        if self.codes[a_index] == self.codes[b_index]
+        {
+            return Ordering::Equal;
+        }
+        self.value(a_index).cmp(self.value(b_index))
+    }
+    fn permute(&mut self, perms: &[usize])
+    {
+        self.codes = perms.iter().map(|pos| self.codes[*pos]).collect();
+    }
+
+    fn to_string_at(&self, n:usize) -> String
+    {
+        self.value(n).to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::TypeName;
+
+    fn strings(col:&StoragePtr) -> Vec<String>
+    {
+        (0..col.len()).map(|i| col.to_string_at(i)).collect()
+    }
+
+    #[test]
+    fn low_cardinality_storage()
+    {
+        let mut c = make_storage(TypeName::DBLowCardinality);
+        c.resize(4);
+        let lc = downcast_low_cardinality_mut(c.as_mut()).unwrap();
+        for (row, s) in ["ios", "web", "ios", "android"].iter().enumerate()
+        {
+            lc.set_value(row, s);
+        }
+        assert_eq!(*lc.codes(), vec![1, 2, 1, 3]);
+        assert_eq!(c.elems_cmp(3, 0), Ordering::Less);
+
+        //The dictionary of dest differs, so the codes are translated
+        let mut dest = make_storage(TypeName::DBLowCardinality.to_nullable());
+        dest.resize(3);
+        downcast_low_cardinality_mut(dest.as_mut()).unwrap().set_value(0, "web");
+        dest.nulls_mut().unwrap()[2] = true;
+        c.copy_indexed_to(&mut dest, &[3, 1, 0]);
+        assert_eq!(strings(&dest), vec!["android", "web", "ios"]);
+        assert_eq!(downcast_low_cardinality_ref(dest.as_ref()).unwrap().codes()[1], 1);
+
+        let mut packed = Vec::<u8>::new();
+        c.pack_value_to(3, &mut packed);
+        c.unpack_value_from(0, &mut packed.as_slice());
+        c.fit_offset_limit(1, Some(2));
+        assert_eq!(strings(&c), vec!["web", "ios"]);
+        c.permute(&[1, 0]);
+        assert_eq!(strings(&c), vec!["ios", "web"]);
+    }
+}
@@ -1,5 +1,9 @@
 pub mod header;
 pub mod data;
+pub mod nullable;
+pub mod decimal;
+pub mod low_cardinality;
+pub mod array;
 
 use header::ColumnHeader;
 use crate::types::{DBType, TypeName};
@@ -82,6 +86,18 @@ impl Column {
     {
         self.data.permute(perms);
     }
+    pub fn nulls(&self) -> Option<&Vec<bool>>
+    {
+        self.data.nulls()
+    }
+    pub fn nulls_mut(&mut self) -> Option<&mut Vec<bool>>
+    {
+        self.data.nulls_mut()
+    }
+    pub fn is_null(&self, at:usize) -> bool
+    {
+        self.nulls().is_some_and(|nulls| nulls[at])
+    }
     pub fn copy_to(&self, dest:&mut Column, offset:usize)
     {
         self.data.copy_to(dest.data_mut(), offset);
@@ -90,6 +106,10 @@ impl Column {
     {
         self.data.copy_filtered_to(dest.data_mut(), offset, filter.data_ref());
     }
+    pub fn copy_indexed_to(&self, dest:&mut Column, indices:&[usize])
+    {
+        self.data.copy_indexed_to(dest.data_mut(), indices);
+    }
 
     pub fn downcast_data_ref<T:DBType>(&self) -> Option<&ColumnDataStorage<T>>
     {
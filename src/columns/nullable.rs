@@ -0,0 +1,201 @@
+use super::data::*;
+use std::any::Any;
+use std::cmp::Ordering;
+use itertools::izip;
+
+//Values of Nullable(T) with the null map. Values under NULL are kept default,
+//so the code reading only the values (e.g. filters) sees NULL as the default value
+//The null map is kept a flag per row, since the functions go through it together with the values.
+//It's written to disk as a bitmap (see io::column::NullableWriter)
+pub struct NullableStorage
+{
+    values :StoragePtr,
+    nulls :Vec<bool>,
+}
+
+impl NullableStorage
+{
+    pub fn new(values:StoragePtr) -> Self
+    {
+        Self{values, nulls:Vec::new()}
+    }
+}
+
+impl ColumnStorage for NullableStorage
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+    fn as_mut_any(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+    fn inner(&self) -> Option<&StoragePtr>
+    {
+        Some(&self.values)
+    }
+    fn inner_mut(&mut self) -> Option<&mut StoragePtr>
+    {
+        Some(&mut self.values)
+    }
+
+    fn len(&self) -> usize
+    {
+        self.values.len()
+    }
+
+    fn resize(&mut self, size:usize)
+    {
+        self.values.resize(size);
+        self.nulls.resize(size, false);
+    }
+
+    fn fit_offset_limit(&mut self, offset:usize, limit:Option<usize>)
+    {
+        self.values.fit_offset_limit(offset, limit);
+        self.nulls.drain(..offset);
+        self.nulls.truncate(self.values.len());
+    }
+
+    fn nulls(&self) -> Option<&Vec<bool>>
+    {
+        Some(&self.nulls)
+    }
+    fn nulls_mut(&mut self) -> Option<&mut Vec<bool>>
+    {
+        Some(&mut self.nulls)
+    }
+
+    //The null map is copied only to Nullable dest, the values are copied to any
+    fn copy_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize)
+    {
+        self.values.copy_to(dest, offset);
+        if let Some(dest_nulls) = dest.nulls_mut()
+        {
+            for (s, d) in izip!(self.nulls.iter(), dest_nulls.iter_mut().skip(offset))
+            {
+                *d = *s;
+            }
+        }
+    }
+
+    fn copy_filtered_to(&self, dest:&mut Box<dyn ColumnStorage>, offset:usize, filter:&Box<dyn ColumnStorage>)
+    {
+        self.values.copy_filtered_to(dest, offset, filter);
+        if let Some(dest_nulls) = dest.nulls_mut()
+        {
+            let flt = downcast_storage_ref::<crate::types::types::DBBool>(filter).unwrap();
+            let filtered = izip!(self.nulls.iter(), flt.iter()).filter(|(_, f)| **f).map(|(n, _)| n);
+            for (s, d) in izip!(filtered, dest_nulls.iter_mut().skip(offset))
+            {
+                *d = *s;
+            }
+        }
+    }
+
+    fn copy_indexed_to(&self, dest:&mut Box<dyn ColumnStorage>, indices:&[usize])
+    {
+        self.values.copy_indexed_to(dest, indices);
+        if let Some(dest_nulls) = dest.nulls_mut()
+        {
+            for (i, d) in izip!(indices.iter(), dest_nulls.iter_mut())
+            {
+                *d = self.nulls[*i];
+            }
+        }
+    }
+
+    //The null flag goes before the value, NULL has no value
+    fn pack_value_to(&self, at:usize, dest: &mut Vec<u8>)
+    {
+        dest.push(self.nulls[at] as u8);
+        if !self.nulls[at]
+        {
+            self.values.pack_value_to(at, dest);
+        }
+    }
+    fn unpack_value_from(&mut self, at:usize, src: &mut &[u8])
+    {
+        self.nulls[at] = src[0] != 0;
+        *src = &src[1..];
+        if !self.nulls[at]
+        {
+            self.values.unpack_value_from(at, src);
+        }
+    }
+
+    //NULLs go last
+    fn elems_cmp(&self, a_index:usize, b_index:usize) -> Ordering
+    {
+        match (self.nulls[a_index], self.nulls[b_index]) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => self.values.elems_cmp(a_index, b_index)
+        }
+    }
+
+    fn permute(&mut self, perms: &[usize])
+    {
+        self.values.permute(perms);
+        self.nulls = perms.iter().map(|pos| self.nulls[*pos]).collect();
+    }
+
+    fn to_string_at(&self, n:usize) -> String
+    {
+        if self.nulls[n] {"NULL".to_string()} else {self.values.to_string_at(n)}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::TypeName;
+    use crate::types::types::*;
+
+    fn nullable_ints(values:&[Option<i64>]) -> StoragePtr
+    {
+        let mut res = make_storage(TypeName::DBInt.to_nullable());
+        res.resize(values.len());
+        for (v, d) in izip!(values.iter(), downcast_storage_mut::<DBInt>(res.as_mut()).unwrap().iter_mut())
+        {
+            *d = v.unwrap_or_default();
+        }
+        for (v, n) in izip!(values.iter(), res.nulls_mut().unwrap().iter_mut())
+        {
+            *n = v.is_none();
+        }
+        res
+    }
+
+    #[test]
+    fn nullable_storage()
+    {
+        let mut c = nullable_ints(&[Some(3), None, Some(1)]);
+        assert_eq!(c.to_string_at(1), "NULL");
+        c.permute(&[1, 2, 0]);
+        assert_eq!(*c.nulls().unwrap(), vec![true, false, false]);
+        assert_eq!(*downcast_storage_ref::<DBInt>(c.as_ref()).unwrap().data_ref(), vec![0, 1, 3]);
+        assert_eq!(c.elems_cmp(0, 1), Ordering::Greater);
+
+        let mut packed = Vec::<u8>::new();
+        c.pack_value_to(0, &mut packed);
+        c.pack_value_to(2, &mut packed);
+        let mut dest = make_storage(TypeName::DBInt.to_nullable());
+        dest.resize(2);
+        let mut src = packed.as_slice();
+        dest.unpack_value_from(1, &mut src);
+        dest.unpack_value_from(0, &mut src);
+        assert_eq!(src.len(), 0);
+        assert_eq!(*dest.nulls().unwrap(), vec![false, true]);
+        assert_eq!(dest.to_string_at(0), "3");
+
+        let mut plain = make_storage(TypeName::DBInt);
+        plain.resize(3);
+        c.copy_to(&mut plain, 0);
+        assert_eq!(*downcast_storage_ref::<DBInt>(plain.as_ref()).unwrap().data_ref(), vec![0, 1, 3]);
+        plain.copy_to(&mut dest, 0);
+        assert_eq!(*dest.nulls().unwrap(), vec![false, false]);
+    }
+}
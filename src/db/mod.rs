@@ -3,10 +3,12 @@ use table::Table;
 use std::collections::HashMap;
 use std::path::{PathBuf, Path};
 use crate::io::db::open_database;
+use crate::functions::registry::FunctionRegistry;
 pub struct DB
 {
     _path:PathBuf,
-    tables:HashMap<String,Table>
+    tables:HashMap<String,Table>,
+    functions:FunctionRegistry
 }
 
 impl DB
@@ -18,7 +20,7 @@ impl DB
         {
             tables.insert(t.name().to_string(), t);
         }
-        Self{_path:PathBuf::from(path.as_ref()), tables}
+        Self{_path:PathBuf::from(path.as_ref()), tables, functions:FunctionRegistry::new()}
     }
 
     pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self>
@@ -30,4 +32,15 @@ impl DB
     {
         self.tables.get(name.as_ref())
     }
+
+    pub fn functions(&self) -> &FunctionRegistry
+    {
+        &self.functions
+    }
+
+    //Registration of user defined functions
+    pub fn functions_mut(&mut self) -> &mut FunctionRegistry
+    {
+        &mut self.functions
+    }
 }
@@ -52,7 +52,7 @@ impl From<Vec<ColumnHeader>> for Schema {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Table
 {
     path:PathBuf,
@@ -0,0 +1,275 @@
+use super::*;
+use crate::execute::steps::aggr::AggrProcessor;
+use crate::functions::aggregate::null::NullableAggrColumn;
+use crate::columns::Column;
+use crate::columns::header::ColumnHeader;
+use crate::types::TypeName;
+use crate::functions::regular::RegFunctionBuilder;
+use super::expr::{binary_op_builder, unary_op_builder, function_column, value_column, function_builder, function_args, case_args, cast_type, string_syntax_call, typed_string_cast, decimal_value_column, is_null_function, registered_function, subscript_args, is_array_join, decimal_literal, low_cardinality_literal};
+use crate::functions::registry::FunctionRegistry;
+use crate::functions::regular::cast::CastBuilder;
+use crate::functions::regular::conditional::MultiIfBuilder;
+use crate::functions::regular::cmp::BetweenBuilder;
+
+pub fn contains_aggregate(functions:&FunctionRegistry, expr:&Expr) -> bool
+{
+    let nested = |e:&Expr| contains_aggregate(functions, e);
+    match expr {
+        Expr::Function(f) => {
+            functions.is_aggregate_function(&f.name.to_string()) || f.args.iter().any(
+                |arg| match arg {
+                    FunctionArg::Named{arg, ..} | FunctionArg::Unnamed(arg) => nested(arg)
+                }
+            )
+        },
+        Expr::BinaryOp{left, right, ..} => nested(left) || nested(right),
+        Expr::UnaryOp{expr, ..} | Expr::Nested(expr) | Expr::Cast{expr, ..} => nested(expr),
+        Expr::IsNull(expr) | Expr::IsNotNull(expr) => nested(expr),
+        Expr::Between{expr, low, high, ..} => nested(expr) || nested(low) || nested(high),
+        Expr::Substring{..} | Expr::Trim{..} => string_syntax_call(expr).is_ok_and(|(_, args)| args.iter().any(nested)),
+        Expr::Case{operand, conditions, results, else_result} => {
+            operand.iter().chain(else_result.iter()).any(|e| nested(e))
+                || conditions.iter().chain(results.iter()).any(nested)
+        },
+        _ => false
+    }
+}
+
+//Builds the aggregated block: group keys and aggregate results, both are filled by AggrProcessor
+pub struct AggrConstructor<'a, 'b>
+{
+    expr_constr :&'b mut ExprConstructor<'a>,
+    processor :AggrProcessor,
+    output :ColumnBlock,
+    //No GROUP BY, so a single group which may get no rows
+    global :bool,
+}
+
+impl<'a, 'b> AggrConstructor<'a, 'b>
+{
+    pub fn new(expr_constr:&'b mut ExprConstructor<'a>, filter_col_name:Option<String>, group_cols:Vec<String>) -> Self
+    {
+        let mut output = ColumnBlock::new();
+        for name in group_cols.iter()
+        {
+            if !output.has_col(name)
+            {
+                output.add_invisible(
+                    expr_constr.input().col_at(name).clone_empty(),
+                    DontTouchSource::new_ref()
+                );
+            }
+        }
+        Self{
+            expr_constr,
+            global:group_cols.is_empty(),
+            processor:AggrProcessor::new(filter_col_name, group_cols),
+            output
+        }
+    }
+
+    pub fn parse(&mut self, expr:&Expr) -> DBResult<String>
+    {
+        let col_name = format!("{}", expr);
+
+        if self.output.has_col(&col_name) {return Ok(col_name);}
+
+        match expr {
+            Expr::Function(f) if self.expr_constr.functions().is_aggregate_function(&f.name.to_string()) => {
+                self.parse_aggr_function(&col_name, f)?;
+            },
+            Expr::BinaryOp{left, op, right} => {
+                let op_builder = binary_op_builder(self.expr_constr.functions(), op)?;
+                if let Some((expr, builder)) = low_cardinality_literal(op, left, right)
+                {
+                    let name = self.parse(expr)?;
+                    if *self.output.col_at(&name).type_name().base() == TypeName::DBLowCardinality
+                    {
+                        self.add_function_column(&col_name, &builder, vec![name])?;
+                        return Ok(col_name);
+                    }
+                }
+                let left_name = self.parse(left)?;
+                let right_name = self.parse(right)?;
+                let left_name = self.exact_operand(left, left_name, &right_name)?;
+                let right_name = self.exact_operand(right, right_name, &left_name)?;
+                self.add_function_column(&col_name, op_builder, vec![left_name, right_name])?;
+            },
+            Expr::UnaryOp{op:UnaryOperator::Plus, expr} => {
+                return self.parse(expr);
+            },
+            Expr::UnaryOp{op, expr} => {
+                let op_builder = unary_op_builder(self.expr_constr.functions(), op)?;
+                let expr_name = self.parse(expr)?;
+                self.add_function_column(&col_name, op_builder, vec![expr_name])?;
+            },
+            Expr::Value(v) => {
+                let (column, source) = value_column(&col_name, v)?;
+                self.output.add_invisible(column, source);
+            },
+            Expr::Nested(v) => {
+                return self.parse(v);
+            },
+            Expr::TypedString{data_type:DataType::Decimal(precision, scale), value} => {
+                let (column, source) = decimal_value_column(&col_name, precision, scale, value)?;
+                self.output.add_invisible(column, source);
+            },
+            Expr::TypedString{..} => {
+                return self.parse(&typed_string_cast(expr)?);
+            },
+            Expr::Function(f) if is_array_join(f) => {
+                return Err(format!("{} must appear in GROUP BY", expr));
+            },
+            Expr::MapAccess{column, keys} => {
+                let (arr, index) = subscript_args(column, keys)?;
+                let builder = registered_function(self.expr_constr.functions(), "arrayElement")?;
+                self.parse_function(&col_name, builder, &[&arr, &index])?;
+            },
+            Expr::Function(f) => {
+                let builder = function_builder(self.expr_constr.functions(), f)?;
+                self.parse_function(&col_name, builder, &function_args(f)?)?;
+            },
+            Expr::Between{expr, negated, low, high} => {
+                let builder = BetweenBuilder::new_ref(*negated);
+                self.parse_function(&col_name, builder.as_ref(), &[expr, low, high])?;
+            },
+            Expr::Case{operand, conditions, results, else_result} => {
+                let args = case_args(operand, conditions, results, else_result);
+                let builder = MultiIfBuilder::new();
+                self.parse_function(&col_name, &builder, &args.iter().collect::<Vec<&Expr>>())?;
+            },
+            Expr::Cast{expr, data_type} => {
+                let builder = CastBuilder::new(cast_type(data_type)?);
+                self.parse_function(&col_name, &builder, &[expr])?;
+            },
+            Expr::Substring{..} | Expr::Trim{..} => {
+                let (name, args) = string_syntax_call(expr)?;
+                let builder = registered_function(self.expr_constr.functions(), name)?;
+                self.parse_function(&col_name, builder, &args.iter().collect::<Vec<&Expr>>())?;
+            },
+            Expr::IsNull(e) | Expr::IsNotNull(e) => {
+                let builder = registered_function(self.expr_constr.functions(), is_null_function(expr)?)?;
+                self.parse_function(&col_name, builder, &[e])?;
+            },
+            other => {return Err(format!("{} must be an aggregate function or appear in GROUP BY", other));}
+        };
+
+        Ok(col_name)
+    }
+
+    pub fn add_alias(&mut self, alias:&Ident, col_name:&str) -> DBResult<String>
+    {
+        if alias.value == col_name {return Ok(alias.value.clone());}
+        if self.output.has_col(&alias.value)
+        {
+            return Err(format!("Alias {} conflicts with an existing column", alias.value));
+        }
+        let column = Column::new(
+            ColumnHeader::new(&alias.value, self.output.col_at(col_name).type_name())
+        );
+        self.output.add_invisible(column, AliasSource::new_ref(col_name));
+        Ok(alias.value.clone())
+    }
+
+    pub fn output(&self) -> &ColumnBlock
+    {
+        &self.output
+    }
+
+    pub fn show(&mut self, name:&str)
+    {
+        self.output.show(name);
+    }
+
+    pub fn finish(self) -> (AggrProcessor, ColumnBlock)
+    {
+        (self.processor, self.output)
+    }
+
+    fn parse_function(&mut self, col_name:&str, builder:&dyn RegFunctionBuilder, args:&[&Expr]) -> DBResult<()>
+    {
+        let mut arg_names = Vec::<String>::new();
+        for arg in args.iter()
+        {
+            arg_names.push(self.parse(arg)?);
+        }
+        self.add_function_column(col_name, builder, arg_names)
+    }
+
+    fn add_function_column(&mut self, col_name:&str, builder:&dyn RegFunctionBuilder, args:Vec<String>) -> DBResult<()>
+    {
+        let (column, source) = function_column(&self.output, col_name, builder, args)?;
+        self.output.add_invisible(column, source);
+        Ok(())
+    }
+
+    //See ExprConstructor::exact_operand
+    fn exact_operand(&mut self, expr:&Expr, name:String, other_name:&str) -> DBResult<String>
+    {
+        match decimal_literal(expr) {
+            Some(e) if matches!(self.output.col_at(other_name).type_name().base(), TypeName::DBDecimal(..)) => self.parse(&e),
+            _ => Ok(name)
+        }
+    }
+
+    fn parse_aggr_function(&mut self, col_name:&str, func:&Function) -> DBResult<()>
+    {
+        if func.over.is_some()
+        {
+            return Err(format!("Window functions unsupported yet: {}", col_name));
+        }
+        let func_name = func.name.to_string();
+        let functions = self.expr_constr.functions();
+        //count(DISTINCT x) is the same as uniq(x)
+        let builder = match (func.distinct, func_name.to_lowercase().as_ref()) {
+            (false, _) => functions.aggregate_function(&func_name).unwrap(),
+            (true, "count") | (true, "uniq") => functions.aggregate_function("uniq").ok_or("Function uniq not found")?,
+            (true, _) => {return Err(format!("DISTINCT is not supported for {}", func_name));}
+        };
+
+        let mut srcs = Vec::<String>::new();
+        for arg in func.args.iter()
+        {
+            match arg {
+                //count(*) doesn't need any source columns
+                FunctionArg::Unnamed(Expr::Wildcard) if !func.distinct => {},
+                FunctionArg::Unnamed(e) => {
+                    let name = self.expr_constr.parse(e)?;
+                    //Aggregate functions get LowCardinality as String
+                    if *self.expr_constr.input().col_at(&name).type_name().base() == TypeName::DBLowCardinality
+                    {
+                        srcs.push(self.expr_constr.parse(&Expr::Cast{expr:Box::new(e.clone()), data_type:DataType::String})?);
+                    }
+                    else
+                    {
+                        srcs.push(name);
+                    }
+                },
+                other => {return Err(format!("{} is not supported yet", other));}
+            }
+        }
+        //Rows with NULL in any of the arguments are skipped by AggrProcessor
+        let arg_types:Vec<TypeName> = srcs.iter().map(
+            |name| self.expr_constr.input().col_at(name).type_name().base().clone()
+        ).collect();
+
+        let nullable = builder.nullable_result() && (
+            (self.global && builder.null_on_empty())
+            || srcs.iter().any(|name| self.expr_constr.input().col_at(name).type_name().is_nullable())
+        );
+
+        let mut type_name = builder.result_type(arg_types.clone())?;
+        let mut aggr_col = builder.build(arg_types)?;
+        if nullable
+        {
+            aggr_col = NullableAggrColumn::new_ref(aggr_col, type_name.clone());
+            type_name = type_name.to_nullable();
+        }
+        self.processor.add_item(aggr_col, srcs, col_name.to_string());
+        self.output.add_invisible(
+            Column::new(ColumnHeader::new(col_name, type_name)),
+            DontTouchSource::new_ref()
+        );
+        Ok(())
+    }
+}
@@ -1,40 +1,87 @@
 use super::*;
 use crate::blocks::source::*;
-use std::fs::File;
-use crate::functions::regular::arithmetic::*;
-use crate::functions::regular::cmp::*;
-use crate::functions::regular::boolean::*;
+use super::relation::{Relation, column_ref};
+use crate::functions::regular::cmp::BetweenBuilder;
+use crate::functions::regular::set::InBuilder;
+use crate::functions::regular::conditional::MultiIfBuilder;
+use crate::functions::regular::cast::{CastBuilder, cast_column};
+use crate::functions::regular::conditional::unify_types;
+use crate::functions::regular::low_cardinality::LowCardinalityEqualsBuilder;
+use crate::functions::regular::RegFunctionBuilder;
+use crate::functions::regular::null::build_function;
+use crate::functions::registry::FunctionRegistry;
 use crate::columns::Column;
 use crate::columns::header::ColumnHeader;
 use crate::types::TypeName;
 use crate::types::types::*;
+use crate::types::decimal::{parse_decimal, fits_precision, MAX_PRECISION_64};
+use crate::blocks::BlockRef;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashSet;
 
 pub struct ExprConstructor<'a>
 {
-   table:&'a Table,
-   input:&'a mut ColumnBlock
+   constr:&'a Constructor<'a>,
+   relation:&'a dyn Relation,
+   input:&'a mut ColumnBlock,
+   //Columns of the relation named as aliases of the projection, they are referred to by their full names
+   shadowed:HashSet<String>
 }
 
 impl<'a> ExprConstructor<'a>
 {
-    pub fn new(table:&'a Table, input:&'a mut ColumnBlock) -> Self
+    pub fn new(constr:&'a Constructor<'a>, relation:&'a dyn Relation, input:&'a mut ColumnBlock) -> Self
     {
-        Self{table, input}
+        Self{constr, relation, input, shadowed:HashSet::new()}
+    }
+
+    //The aliases take the names of the columns they shadow, so e.g. id + 1 AS id is id in the output and in ORDER BY
+    pub fn shadow_columns<'i>(&mut self, aliases:impl Iterator<Item = &'i Ident>)
+    {
+        for alias in aliases
+        {
+            if let Ok(Some(_)) = self.relation.find_col(None, &alias.value)
+            {
+                self.shadowed.insert(alias.value.clone());
+            }
+        }
+    }
+
+    pub fn input(&self) -> &ColumnBlock
+    {
+        self.input
+    }
+
+    pub fn functions(&self) -> &'a FunctionRegistry
+    {
+        self.constr.db.functions()
     }
 
     pub fn parse(&mut self, expr:&Expr) -> DBResult<String>
     {
         let mut col_name = format!("{}", expr);
+        if let Expr::Identifier(ident) = expr
+        {
+            if self.shadowed.contains(&ident.value)
+            {
+                col_name = self.relation.find_col(None, &ident.value)?.unwrap().0;
+            }
+        }
 
         if self.input.has_col(&col_name) {return Ok(col_name);}
 
         match expr {
-            Expr::Identifier(v) => {
-                self.parse_ident(&v)?;
+            Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+                let (qualifier, name) = column_ref(expr)?;
+                self.parse_column(&col_name, qualifier, name)?;
             },
             Expr::BinaryOp{left, op, right} => {
                 self.parse_binary_op(&col_name, &op, &left, &right)?;
             },
+            Expr::UnaryOp{op:UnaryOperator::Plus, expr} => {
+                col_name = self.parse(expr)?;
+            },
             Expr::UnaryOp{op, expr} => {
                 self.parse_unary_op(&col_name, &op, &expr)?;
             },
@@ -45,128 +92,582 @@ impl<'a> ExprConstructor<'a>
                 col_name = format!("{}", v);
                 self.parse(v)?;
             },
+            Expr::InList{expr, list, negated} => {
+                let builder = InBuilder::new_ref(literal_block(list)?, "values", *negated);
+                self.parse_function(&col_name, builder.as_ref(), &[expr])?;
+            },
+            Expr::InSubquery{expr, subquery, negated} => {
+                let block = self.constr.parse_subquery(subquery)?;
+                let headers = block.borrow().headers();
+                if headers.len() != 1
+                {
+                    return Err(format!("Subquery of IN must return one column, got {}", headers.len()));
+                }
+                let builder = InBuilder::new_ref(block, headers[0].name(), *negated);
+                self.parse_function(&col_name, builder.as_ref(), &[expr])?;
+            },
+            Expr::Function(f) if is_array_join(f) => {
+                let name = array_join_column(f)?;
+                if self.relation.find_col(None, &name)?.is_none()
+                {
+                    return Err(format!("{} is supported in SELECT, WHERE and GROUP BY only", expr));
+                }
+                self.parse_column(&col_name, None, &name)?;
+            },
+            Expr::MapAccess{column, keys} => {
+                let (arr, index) = subscript_args(column, keys)?;
+                let builder = registered_function(self.functions(), "arrayElement")?;
+                self.parse_function(&col_name, builder, &[&arr, &index])?;
+            },
+            Expr::Function(f) if self.functions().is_aggregate_function(&f.name.to_string()) => {
+                return Err(format!("Aggregate function {} is not allowed here", expr));
+            },
+            Expr::Function(f) => {
+                let builder = function_builder(self.functions(), f)?;
+                self.parse_function(&col_name, builder, &function_args(f)?)?;
+            },
+            Expr::Between{expr, negated, low, high} => {
+                let builder = BetweenBuilder::new_ref(*negated);
+                self.parse_function(&col_name, builder.as_ref(), &[expr, low, high])?;
+            },
+            Expr::Case{operand, conditions, results, else_result} => {
+                let args = case_args(operand, conditions, results, else_result);
+                let builder = MultiIfBuilder::new();
+                self.parse_function(&col_name, &builder, &args.iter().collect::<Vec<&Expr>>())?;
+            },
+            Expr::Cast{expr, data_type} => {
+                let builder = CastBuilder::new(cast_type(data_type)?);
+                self.parse_function(&col_name, &builder, &[expr])?;
+            },
+            Expr::TypedString{data_type:DataType::Decimal(precision, scale), value} => {
+                let (column, source) = decimal_value_column(&col_name, precision, scale, value)?;
+                self.input.add(column, source);
+            },
+            Expr::TypedString{..} => {
+                col_name = self.parse(&typed_string_cast(expr)?)?;
+            },
+            Expr::Substring{..} | Expr::Trim{..} => {
+                let (name, args) = string_syntax_call(expr)?;
+                let builder = registered_function(self.functions(), name)?;
+                self.parse_function(&col_name, builder, &args.iter().collect::<Vec<&Expr>>())?;
+            },
+            Expr::IsNull(e) | Expr::IsNotNull(e) => {
+                let builder = registered_function(self.functions(), is_null_function(expr)?)?;
+                self.parse_function(&col_name, builder, &[e])?;
+            },
             other => {return Err(format!("{} is not supported yet", other));}
         };
 
         Ok(col_name)
     }
 
-    pub fn process_wild(&mut self) -> Vec<String>
+    pub fn add_alias(&mut self, alias:&Ident, col_name:&str) -> DBResult<String>
     {
+        if alias.value == col_name {return Ok(alias.value.clone());}
+        if self.input.has_col(&alias.value)
+        {
+            return Err(format!("Alias {} conflicts with an existing column", alias.value));
+        }
+        let column = Column::new(
+            ColumnHeader::new(&alias.value, self.input.col_at(col_name).type_name())
+        );
+        self.input.add(column, AliasSource::new_ref(col_name));
+        Ok(alias.value.clone())
+    }
+
+    //Columns which names exist in several tables of the join are qualified
+    pub fn process_wild(&mut self, qualifier:Option<&str>) -> DBResult<Vec<String>>
+    {
+        let columns = self.relation.columns();
+        if let Some(q) = qualifier
+        {
+            if !columns.iter().any(|(alias, _)| alias == q)
+            {
+                return Err(format!("Table {} not found in FROM", q));
+            }
+        }
         let mut res = Vec::<String>::new();
-        for head in self.table.schema().headers_ref().iter()
+        for (alias, head) in columns.iter()
         {
-            self.parse_ident(&Ident{value:head.name().to_string(), quote_style:None}).unwrap();
-            res.push(head.name().to_string());
+            if qualifier.is_some() && qualifier != Some(alias.as_ref()) {continue;}
+            let ambiguous = columns.iter().filter(|(_, h)| h.name() == head.name()).count() > 1;
+            let expr = if ambiguous {
+                Expr::CompoundIdentifier(vec![Ident::new(alias), Ident::new(head.name())])
+            } else {
+                Expr::Identifier(Ident::new(head.name()))
+            };
+            res.push(self.parse(&expr)?);
         }
 
-        res
+        Ok(res)
     }
-    fn parse_ident(&mut self, ident:&Ident) -> DBResult<()>
+    //An empty block can't tell the number of rows (e.g. for count(*)), so some column is read anyway
+    pub fn ensure_rows(&mut self) -> DBResult<()>
     {
-        match self.table.make_column(&ident.value) {
-            Some(c) => {
-                let type_name =c.type_name();
+        if !self.input.is_empty() {return Ok(());}
+        match self.relation.columns().first() {
+            Some((alias, head)) => {
+                self.parse(&Expr::CompoundIdentifier(vec![Ident::new(alias), Ident::new(head.name())]))?;
+                Ok(())
+            },
+            None => Err("Relation without columns".to_string())
+        }
+    }
+    fn parse_column(&mut self, col_name:&str, qualifier:Option<&str>, name:&str) -> DBResult<()>
+    {
+        match self.relation.find_col(qualifier, name)? {
+            Some((_, header)) => {
                 self.input.add(
-                    c,
-                    ExternalSource::new_ref(
-                        File::open(self.table.col_path(&ident.value).unwrap()).unwrap(),
-                        type_name
-                    )
+                    Column::new(ColumnHeader::new(col_name, header.type_name())),
+                    self.relation.make_source(qualifier, name)?
                 );
                 Ok(())
             },
             None => {
                 Err(
-                    format!("Filed {} not found in {}", &ident.value, &self.table.name())
+                    format!("Filed {} not found", col_name)
                 )
             }
         }
     }
-    fn parse_binary_op(&mut self, col_name:&String, op:&BinaryOperator, left:&Expr, right:&Expr) -> DBResult<()>
-    {
-        let op_builder = match op {
-            BinaryOperator::Plus => PlusBuilder::new_ref(),
-            BinaryOperator::Minus => MinusBuilder::new_ref(),
-            BinaryOperator::Multiply => MultiplyBuilder::new_ref(),
-            BinaryOperator::Divide => DivideBuilder::new_ref(),
-            BinaryOperator::Eq => EqualBuilder::new_ref(),
-            BinaryOperator::NotEq => NotEqualBuilder::new_ref(),
-            BinaryOperator::Lt => LessBuilder::new_ref(),
-            BinaryOperator::LtEq => LessEqualBuilder::new_ref(),
-            BinaryOperator::Gt => GreaterBuilder::new_ref(),
-            BinaryOperator::GtEq => GreaterEqualBuilder::new_ref(),
-            BinaryOperator::And => AndBuilder::new_ref(),
-            BinaryOperator::Or => OrBuilder::new_ref(),
-            _ =>  return Err(format!("Operation {} not supported yet", op))
-        };
+    fn parse_binary_op(&mut self, col_name:&str, op:&BinaryOperator, left:&Expr, right:&Expr) -> DBResult<()>
+    {
+        let op_builder = binary_op_builder(self.functions(), op)?;
+        if let Some((expr, builder)) = low_cardinality_literal(op, left, right)
+        {
+            let name = self.parse(expr)?;
+            if *self.input.col_at(&name).type_name().base() == TypeName::DBLowCardinality
+            {
+                let (column, source) = function_column(self.input, col_name, &builder, vec![name])?;
+                self.input.add(column, source);
+                return Ok(());
+            }
+        }
 
-        let left_name = &self.parse(left)?;
-        let right_name = &self.parse(right)?;
-        let arg_types = vec![
-            self.input.col_at(left_name).type_name(),
-            self.input.col_at(right_name).type_name(),
-        ];
-        let type_name = op_builder.result_type(arg_types.clone())?;
-        self.input.add(
-            Column::new(ColumnHeader::new(col_name, type_name)),
-            FunctionSource::new_ref(
-                vec![left_name.clone(), right_name.clone()],
-                op_builder.build(arg_types)?
-            )
-        );
+        let left_name = self.parse(left)?;
+        let right_name = self.parse(right)?;
+        let left_name = self.exact_operand(left, left_name, &right_name)?;
+        let right_name = self.exact_operand(right, right_name, &left_name)?;
+        let (column, source) = function_column(self.input, col_name, op_builder, vec![left_name, right_name])?;
+        self.input.add(column, source);
 
         Ok(())
     }
-    fn parse_unary_op(&mut self, col_name:&String, op:&UnaryOperator, expr:&Expr) -> DBResult<()>
+    //Fractional literal next to Decimal is taken as exact decimal, so e.g. price * 1.1 doesn't go to Float
+    fn exact_operand(&mut self, expr:&Expr, name:String, other_name:&str) -> DBResult<String>
     {
-        let op_builder = match op {
-            UnaryOperator::Not => NotBuilder::new_ref(),
-            _ =>  return Err(format!("Operation {} not supported yet", op))
-        };
+        match decimal_literal(expr) {
+            Some(e) if matches!(self.input.col_at(other_name).type_name().base(), TypeName::DBDecimal(..)) => self.parse(&e),
+            _ => Ok(name)
+        }
+    }
+    fn parse_unary_op(&mut self, col_name:&str, op:&UnaryOperator, expr:&Expr) -> DBResult<()>
+    {
+        let op_builder = unary_op_builder(self.functions(), op)?;
 
-        let expr_name = &self.parse(expr)?;
-        let arg_types = vec![
-            self.input.col_at(expr_name).type_name(),
-        ];
-        let type_name = op_builder.result_type(arg_types.clone())?;
-        self.input.add(
-            Column::new(ColumnHeader::new(col_name, type_name)),
-            FunctionSource::new_ref(
-                vec![expr_name.clone()],
-                op_builder.build(arg_types)?
-            )
-        );
+        let expr_name = self.parse(expr)?;
+        let (column, source) = function_column(self.input, col_name, op_builder, vec![expr_name])?;
+        self.input.add(column, source);
+        Ok(())
+    }
+    fn parse_function(&mut self, col_name:&str, builder:&dyn RegFunctionBuilder, args:&[&Expr]) -> DBResult<()>
+    {
+        let mut arg_names = Vec::<String>::new();
+        for arg in args.iter()
+        {
+            arg_names.push(self.parse(arg)?);
+        }
+        let (column, source) = function_column(self.input, col_name, builder, arg_names)?;
+        self.input.add(column, source);
         Ok(())
     }
-    fn parse_value(&mut self, col_name:&String, val:&Value) -> DBResult<()>
+    fn parse_value(&mut self, col_name:&str, val:&Value) -> DBResult<()>
     {
-        let (source, type_name) = match val {
-            Value::Number(v, _) => {
+        let (column, source) = value_column(col_name, val)?;
+        self.input.add(column, source);
+        Ok(())
+    }
 
-                match v.parse::<i64>()
-                {
-                    Ok(value) => (ConstValueSource::<DBInt>::new_ref(value), TypeName::DBInt),
-                    Err(_) => {match v.parse::<f64>() {
-                            Ok(value) => (ConstValueSource::<DBFloat>::new_ref(value), TypeName::DBFloat),
-                            Err(_) => return Err("number parse error".to_string())
-                        }
-                    }
-                }
+}
 
+//Operators are the regular functions of the registry
+pub fn binary_op_builder<'r>(functions:&'r FunctionRegistry, op:&BinaryOperator) -> DBResult<&'r dyn RegFunctionBuilder>
+{
+    let name = match op {
+        BinaryOperator::Plus => "plus",
+        BinaryOperator::Minus => "minus",
+        BinaryOperator::Multiply => "multiply",
+        BinaryOperator::Divide => "divide",
+        BinaryOperator::Modulo => "modulo",
+        BinaryOperator::Eq => "equals",
+        BinaryOperator::NotEq => "notEquals",
+        BinaryOperator::Lt => "less",
+        BinaryOperator::LtEq => "lessOrEquals",
+        BinaryOperator::Gt => "greater",
+        BinaryOperator::GtEq => "greaterOrEquals",
+        BinaryOperator::And => "and",
+        BinaryOperator::Or => "or",
+        BinaryOperator::Like => "like",
+        BinaryOperator::NotLike => "notLike",
+        BinaryOperator::ILike => "ilike",
+        BinaryOperator::NotILike => "notILike",
+        BinaryOperator::BitwiseAnd => "bitAnd",
+        BinaryOperator::BitwiseOr => "bitOr",
+        BinaryOperator::BitwiseXor => "bitXor",
+        _ =>  return Err(format!("Operation {} not supported yet", op))
+    };
+    registered_function(functions, name)
+}
+
+pub fn unary_op_builder<'r>(functions:&'r FunctionRegistry, op:&UnaryOperator) -> DBResult<&'r dyn RegFunctionBuilder>
+{
+    let name = match op {
+        UnaryOperator::Not => "not",
+        UnaryOperator::Minus => "negate",
+        _ =>  return Err(format!("Operation {} not supported yet", op))
+    };
+    registered_function(functions, name)
+}
+
+pub fn registered_function<'r>(functions:&'r FunctionRegistry, name:&str) -> DBResult<&'r dyn RegFunctionBuilder>
+{
+    functions.function(name).ok_or(
+        format!("Function {} not found", name)
+    )
+}
+
+pub fn function_builder<'r>(functions:&'r FunctionRegistry, func:&Function) -> DBResult<&'r dyn RegFunctionBuilder>
+{
+    if func.distinct || func.over.is_some()
+    {
+        return Err(format!("{} is not supported yet", func));
+    }
+    registered_function(functions, &func.name.to_string())
+}
+
+pub fn function_args(func:&Function) -> DBResult<Vec<&Expr>>
+{
+    func.args.iter().map(
+        |arg| match arg {
+            FunctionArg::Unnamed(e) => Ok(e),
+            other => Err(format!("{} is not supported yet", other))
+        }
+    ).collect()
+}
+
+//Type of CAST(x AS type), both SQL and own type names are accepted
+pub fn cast_type(data_type:&DataType) -> DBResult<TypeName>
+{
+    match data_type {
+        DataType::TinyInt(_) | DataType::SmallInt(_) | DataType::Int(_) | DataType::BigInt(_) => Ok(TypeName::DBInt),
+        DataType::Float(_) | DataType::Real | DataType::Double => Ok(TypeName::DBFloat),
+        DataType::Char(_) | DataType::Varchar(_) | DataType::Text | DataType::String => Ok(TypeName::DBString),
+        DataType::Boolean => Ok(TypeName::DBBool),
+        DataType::Date => Ok(TypeName::DBDate),
+        DataType::Timestamp => Ok(TypeName::DBDateTime),
+        DataType::Decimal(precision, scale) => decimal_type(precision.unwrap_or(DEFAULT_DECIMAL_PRECISION), scale.unwrap_or(0)),
+        DataType::Custom(name) => TypeName::try_from(name.to_string()).map_err(
+            |_| format!("Type {} not found", name)
+        ),
+        other => Err(format!("CAST to {} is not supported yet", other))
+    }
+}
+
+//SUBSTRING(s FROM a FOR b) and TRIM([BOTH | LEADING | TRAILING] chars FROM s) are calls of the string functions
+pub fn string_syntax_call(expr:&Expr) -> DBResult<(&'static str, Vec<Expr>)>
+{
+    match expr {
+        Expr::Substring{expr, substring_from, substring_for} => {
+            let from = substring_from.as_ref().map_or(
+                Expr::Value(Value::Number("1".to_string(), false)),
+                |e| *e.clone()
+            );
+            let mut args = vec![*expr.clone(), from];
+            args.extend(substring_for.iter().map(|e| *e.clone()));
+            Ok(("substring", args))
+        },
+        Expr::Trim{expr, trim_where} => match trim_where {
+            Some((field, chars)) => {
+                let name = match field {
+                    TrimWhereField::Both => "trim",
+                    TrimWhereField::Leading => "ltrim",
+                    TrimWhereField::Trailing => "rtrim",
+                };
+                Ok((name, vec![*expr.clone(), *chars.clone()]))
             },
-            Value::SingleQuotedString(v) | Value::DoubleQuotedString(v) => {
-                (ConstValueSource::<DBString>::new_ref(v.clone()), TypeName::DBString)
-            },
-            _ =>return Err(format!("Value {} not supported yet", col_name))
+            None => Ok(("trim", vec![*expr.clone()]))
+        },
+        other => Err(format!("{} is not a string function call", other))
+    }
+}
+
+//DATE '2024-01-01' is CAST('2024-01-01' AS DATE)
+pub fn typed_string_cast(expr:&Expr) -> DBResult<Expr>
+{
+    match expr {
+        Expr::TypedString{data_type, value} => Ok(Expr::Cast{
+            expr:Box::new(Expr::Value(Value::SingleQuotedString(value.clone()))),
+            data_type:data_type.clone()
+        }),
+        other => Err(format!("{} is not a typed string", other))
+    }
+}
+
+//x IS NULL and x IS NOT NULL are calls of isNull and isNotNull
+pub fn is_null_function(expr:&Expr) -> DBResult<&'static str>
+{
+    match expr {
+        Expr::IsNull(_) => Ok("isNull"),
+        Expr::IsNotNull(_) => Ok("isNotNull"),
+        other => Err(format!("{} is not a NULL check", other))
+    }
+}
+
+//Arguments of multiIf for CASE: condition and result pairs and the else result.
+//CASE x WHEN v THEN ... is the same as CASE WHEN x = v THEN ...
+pub fn case_args(operand:&Option<Box<Expr>>, conditions:&[Expr], results:&[Expr], else_result:&Option<Box<Expr>>) -> Vec<Expr>
+{
+    let mut args = Vec::<Expr>::new();
+    for (cond, res) in conditions.iter().zip(results.iter())
+    {
+        let cond = match operand {
+            Some(op) => Expr::BinaryOp{left:op.clone(), op:BinaryOperator::Eq, right:Box::new(cond.clone())},
+            None => cond.clone()
         };
+        args.push(cond);
+        args.push(res.clone());
+    }
+    if let Some(e) = else_result
+    {
+        args.push(*e.clone());
+    }
+    args
+}
 
-        self.input.add(
-            Column::new(ColumnHeader::new(col_name, type_name)),
-            source
-        );
-        Ok(())
+//arr[i][j] is arrayElement(arrayElement(arr, i), j)
+pub fn subscript_args(column:&Expr, keys:&[Value]) -> DBResult<(Expr, Expr)>
+{
+    let (last, rest) = keys.split_last().unwrap();
+    let index = match last {
+        Value::Number(..) => Expr::Value(last.clone()),
+        other => {return Err(format!("Index of array must be a number, got {}", other));}
+    };
+    let arr = if rest.is_empty() {column.clone()} else {Expr::MapAccess{column:Box::new(column.clone()), keys:rest.to_vec()}};
+    Ok((arr, index))
+}
 
+//arrayJoin(arr) isn't a function: the rows of the relation are repeated for each element of arr (see ArrayJoinRelation)
+pub fn is_array_join(func:&Function) -> bool
+{
+    func.name.to_string().eq_ignore_ascii_case("arrayJoin")
+}
+
+//Column of ArrayJoinRelation for the call, the same for any case of the name
+pub fn array_join_column(func:&Function) -> DBResult<String>
+{
+    match function_args(func)?.as_slice() {
+        [arg] => Ok(format!("arrayJoin({})", arg)),
+        args => Err(format!("arrayJoin expects 1 argument, got {}", args.len()))
     }
+}
+
+//Arguments of the arrayJoin calls in the expression
+pub fn array_join_args(expr:&Expr, res:&mut Vec<Expr>)
+{
+    let mut nested = |e:&Expr| array_join_args(e, res);
+    match expr {
+        Expr::Function(f) if is_array_join(f) => {
+            if let Ok([arg]) = function_args(f).as_deref()
+            {
+                res.push((*arg).clone());
+            }
+        },
+        Expr::Function(f) => {
+            for arg in f.args.iter()
+            {
+                match arg {
+                    FunctionArg::Named{arg, ..} | FunctionArg::Unnamed(arg) => nested(arg)
+                }
+            }
+        },
+        Expr::BinaryOp{left, right, ..} => {
+            nested(left);
+            nested(right);
+        },
+        Expr::UnaryOp{expr, ..} | Expr::Nested(expr) | Expr::Cast{expr, ..} => nested(expr),
+        Expr::IsNull(expr) | Expr::IsNotNull(expr) => nested(expr),
+        Expr::MapAccess{column, ..} => nested(column),
+        Expr::InList{expr, ..} | Expr::InSubquery{expr, ..} => nested(expr),
+        Expr::Between{expr, low, high, ..} => {
+            nested(expr);
+            nested(low);
+            nested(high);
+        },
+        Expr::Substring{..} | Expr::Trim{..} => string_syntax_call(expr).iter().for_each(|(_, args)| args.iter().for_each(&mut nested)),
+        Expr::Case{operand, conditions, results, else_result} => {
+            operand.iter().chain(else_result.iter()).for_each(|e| nested(e));
+            conditions.iter().chain(results.iter()).for_each(nested);
+        },
+        _ => {}
+    }
+}
 
-}
\ No newline at end of file
+//Makes the column calculated by the function from the args columns, which must already be in the block
+pub fn function_column(block:&ColumnBlock, col_name:&str, builder:&dyn RegFunctionBuilder, args:Vec<String>) -> DBResult<(Column, ColumnSourceRef)>
+{
+    let arg_types:Vec<TypeName> = args.iter().map(|name| block.col_at(name).type_name()).collect();
+    let (type_name, func) = build_function(builder, arg_types)?;
+    Ok((
+        Column::new(ColumnHeader::new(col_name, type_name)),
+        FunctionSource::new_ref(args, func)
+    ))
+}
+
+//DECIMAL(P, S) without the precision
+const DEFAULT_DECIMAL_PRECISION :u64 = 18;
+
+fn decimal_type(precision:u64, scale:u64) -> DBResult<TypeName>
+{
+    match (u8::try_from(precision), u8::try_from(scale)) {
+        (Ok(precision), Ok(scale)) => TypeName::decimal(precision, scale),
+        _ => Err(format!("Wrong Decimal({}, {})", precision, scale))
+    }
+}
+
+//== or != with a string literal, it's the code comparison if the other side is LowCardinality
+pub fn low_cardinality_literal<'e>(op:&BinaryOperator, left:&'e Expr, right:&'e Expr) -> Option<(&'e Expr, LowCardinalityEqualsBuilder)>
+{
+    let negate = match op {
+        BinaryOperator::Eq => false,
+        BinaryOperator::NotEq => true,
+        _ => {return None;}
+    };
+    match (left, right) {
+        (expr, Expr::Value(Value::SingleQuotedString(v))) | (Expr::Value(Value::SingleQuotedString(v)), expr) =>
+            Some((expr, LowCardinalityEqualsBuilder::with_value(negate, v.clone()))),
+        _ => None
+    }
+}
+
+//Fractional number literal, possibly negative, as DECIMAL '...'
+pub fn decimal_literal(expr:&Expr) -> Option<Expr>
+{
+    let value = match expr {
+        Expr::Value(Value::Number(v, _)) => v.clone(),
+        Expr::UnaryOp{op:UnaryOperator::Minus, expr} => match expr.as_ref() {
+            Expr::Value(Value::Number(v, _)) => format!("-{}", v),
+            _ => {return None;}
+        },
+        _ => {return None;}
+    };
+    let digits = value.trim_start_matches('-');
+    if !digits.contains('.') || !digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+    {
+        return None;
+    }
+    Some(Expr::TypedString{data_type:DataType::Decimal(None, None), value})
+}
+
+//DECIMAL '12.50' is Decimal(4, 2) of the digits of the literal, DECIMAL(P, S) '12.50' is of the given type
+pub fn decimal_value_column(col_name:&str, precision:&Option<u64>, scale:&Option<u64>, val:&str) -> DBResult<(Column, ColumnSourceRef)>
+{
+    let type_name = match (precision, scale) {
+        (None, None) => {
+            let digits = val.trim().trim_start_matches(['-', '+']);
+            let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+            let int_digits = int_part.trim_start_matches('0').len() as u64;
+            let scale = frac_part.len() as u64;
+            decimal_type((int_digits + scale).max(1), scale)?
+        },
+        (precision, scale) => decimal_type(precision.unwrap_or(DEFAULT_DECIMAL_PRECISION), scale.unwrap_or(0))?
+    };
+    let TypeName::DBDecimal(precision, scale) = type_name else {unreachable!()};
+    let value = parse_decimal(val, scale).filter(|v| fits_precision(*v, precision)).ok_or_else(
+        || format!("Can't parse '{}' as {}", val, type_name)
+    )?;
+    let source = if precision <= MAX_PRECISION_64
+    {
+        ConstValueSource::<DBDecimal64>::new_ref(value as i64)
+    }
+    else
+    {
+        ConstValueSource::<DBDecimal128>::new_ref(value)
+    };
+    Ok((Column::new(ColumnHeader::new(col_name, type_name)), source))
+}
+
+pub fn value_column(col_name:&str, val:&Value) -> DBResult<(Column, ColumnSourceRef)>
+{
+    let (source, type_name) = match val {
+        Value::Number(v, _) => {
+
+            //Integers above the range of Int are UInt64
+            match (v.parse::<i64>(), v.parse::<u64>())
+            {
+                (Ok(value), _) => (ConstValueSource::<DBInt>::new_ref(value), TypeName::DBInt),
+                (_, Ok(value)) => (ConstValueSource::<DBUInt64>::new_ref(value), TypeName::DBUInt64),
+                _ => {match v.parse::<f64>() {
+                        Ok(value) => (ConstValueSource::<DBFloat>::new_ref(value), TypeName::DBFloat),
+                        Err(_) => return Err("number parse error".to_string())
+                    }
+                }
+            }
+
+        },
+        Value::SingleQuotedString(v) | Value::DoubleQuotedString(v) => {
+            (ConstValueSource::<DBString>::new_ref(v.clone()), TypeName::DBString)
+        },
+        Value::Boolean(v) => (ConstValueSource::<DBBool>::new_ref(*v), TypeName::DBBool),
+        Value::Null => (NullSource::new_ref(), TypeName::DBNothing.to_nullable()),
+        _ =>return Err(format!("Value {} not supported yet", col_name))
+    };
+
+    Ok((Column::new(ColumnHeader::new(col_name, type_name)), source))
+}
+
+//Literal of the list: a value or a negative number
+fn list_literal(expr:&Expr) -> Option<Value>
+{
+    match expr {
+        Expr::Value(v) => Some(v.clone()),
+        Expr::UnaryOp{op:UnaryOperator::Minus, expr} => match expr.as_ref() {
+            Expr::Value(Value::Number(v, long)) => Some(Value::Number(format!("-{}", v), *long)),
+            _ => None
+        },
+        Expr::Nested(e) => list_literal(e),
+        _ => None
+    }
+}
+
+//Block with the single column "values" made of the literals, e.g. for IN list.
+//The values are cast to their common type, e.g. (20, 30.5) is Float
+pub fn literal_block(list:&[Expr]) -> DBResult<BlockRef>
+{
+    let mut literals = Vec::<ColumnBlock>::with_capacity(list.len());
+    for e in list.iter()
+    {
+        let val = list_literal(e).ok_or_else(|| format!("Only literals are supported in the list, got {}", e))?;
+        let mut tmp = ColumnBlock::new();
+        let (column, source) = value_column("values", &val)?;
+        tmp.add(column, source);
+        tmp.process(1)?;
+        literals.push(tmp);
+    }
+    if literals.is_empty()
+    {
+        return Err("Empty list of values".to_string());
+    }
+    let types:Vec<TypeName> = literals.iter().map(|b| b.col_at("values").type_name()).collect();
+    let common = unify_types(&types).map_err(
+        |_| format!("Values of the list have incompatible types: {}", types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", "))
+    )?;
+    let mut values = Column::new(ColumnHeader::new("values", common.clone()));
+    values.resize(literals.len());
+    for (i, b) in literals.iter().enumerate()
+    {
+        cast_column(b.col_at("values"), common.clone())?.copy_to(&mut values, i);
+    }
+    let mut block = ColumnBlock::new();
+    block.add(values, DontTouchSource::new_ref());
+    Ok(Rc::new(RefCell::new(block)))
+}
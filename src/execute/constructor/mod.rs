@@ -1,19 +1,40 @@
 use super::*;
 use sqlparser::dialect::GenericDialect;
-use sqlparser::parser::Parser;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
+use sqlparser::keywords::Keyword;
 use sqlparser::ast::*;
 use crate::DBResult;
 use crate::db::DB;
 use crate::db::table::Table;
 mod expr;
-use expr::ExprConstructor;
+mod aggr;
+mod relation;
+use expr::{ExprConstructor, array_join_args};
+use aggr::{AggrConstructor, contains_aggregate};
+use relation::*;
 use crate::execute::steps::processor::*;
+use crate::execute::steps::distinct::*;
+use crate::execute::steps::insert::InsertProcessor;
+use crate::functions::regular::cast::cast_column;
+use crate::functions::regular::conditional::unify_types;
 use crate::blocks::source::*;
 use crate::io::db::table_size_iterator;
+use crate::blocks::BlockRef;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::columns::Column;
+use crate::columns::header::ColumnHeader;
+use crate::types::TypeName;
 
 pub struct Constructor<'a>
 {
-   db:&'a DB
+   db:&'a DB,
+   //Materialized CTEs visible from the query being parsed, with their column names if given
+   ctes:RefCell<HashMap<String, (BlockRef, Vec<Ident>)>>,
+   //Steps of subqueries in expressions, they are executed before the query using them
+   subquery_steps:RefCell<Vec<ExecuteStep>>,
 }
 
 impl<'a>  Constructor<'a>
@@ -21,7 +42,7 @@ impl<'a>  Constructor<'a>
 
     pub fn new(db:&'a DB) -> Self
     {
-        Self{db}
+        Self{db, ctes:RefCell::new(HashMap::new()), subquery_steps:RefCell::new(Vec::new())}
     }
 
     pub fn make_plan(&self, sql:&str) -> DBResult<Plan>
@@ -29,6 +50,9 @@ impl<'a>  Constructor<'a>
         let st = &Self::parse_sql(sql)?[0];
         match &st {
             Statement::Query(v) => self.parse_query(&v),
+            Statement::Insert{table_name, columns, source, overwrite:false, partitioned:None, ..} => {
+                self.parse_insert(table_name, columns, source)
+            },
             other => Err(format!("{} unsupported yet", other))
         }
     }
@@ -38,11 +62,249 @@ impl<'a>  Constructor<'a>
         let limit = Self::parse_limit(&query.limit);
         let offset = Self::parse_offset(&query.offset);
 
-        match &query.body {
-                SetExpr::Select(s) => self.parse_select(&s.as_ref(), offset, limit, &query.order_by),
-                other => Err(format!("{} unsupported yet", other))
+        //CTEs are visible only inside the query, outer ones with the same names are restored after
+        let outer_ctes = self.ctes.borrow().clone();
+        let outer_subquery_steps = self.subquery_steps.take();
+        let mut steps = Vec::<ExecuteStep>::new();
+        if let Some(with) = &query.with
+        {
+            if with.recursive
+            {
+                return Err("Recursive CTEs unsupported yet".to_string());
+            }
+            for cte in with.cte_tables.iter()
+            {
+                let plan = self.parse_query(&cte.query)?;
+                let output = plan.output();
+                steps.append(&mut plan.into_steps());
+                self.ctes.borrow_mut().insert(cte.alias.name.value.clone(), (output, cte.alias.columns.clone()));
+            }
+        }
+
+        let plan = self.parse_set_expr(&query.body, offset, limit, &query.order_by);
+        self.ctes.replace(outer_ctes);
+        steps.append(&mut self.subquery_steps.replace(outer_subquery_steps));
+
+        steps.append(&mut plan?.into_steps());
+        Ok(Plan::from_steps(steps))
+    }
+
+    //The subquery is executed before the query using it, the result is its output block
+    fn parse_subquery(&self, query:&Query) -> DBResult<BlockRef>
+    {
+        let plan = self.parse_query(query)?;
+        let output = plan.output();
+        self.subquery_steps.borrow_mut().append(&mut plan.into_steps());
+        Ok(output)
+    }
+
+    fn parse_set_expr(&self, body:&SetExpr, offset:Option<usize>, limit:Option<usize>, order:&Vec<OrderByExpr>) -> DBResult<Plan>
+    {
+        match body {
+            SetExpr::Select(s) => self.parse_select(s.as_ref(), offset, limit, order),
+            SetExpr::Query(q) if offset.is_none() && limit.is_none() && order.is_empty() => self.parse_query(q),
+            SetExpr::SetOperation{..} => self.parse_set_operation(body, offset, limit, order),
+            SetExpr::Values(values) => self.parse_values(values, offset, limit, order),
+            other => Err(format!("{} unsupported yet", other))
+        }
+    }
+
+    //UNION ALL concatenates the results of both sides, UNION dedupes them.
+    //INTERSECT and EXCEPT read unique rows of the left side filtered by the packed rows of the right one
+    fn parse_set_operation(&self, body:&SetExpr, offset:Option<usize>, limit:Option<usize>, order:&Vec<OrderByExpr>) -> DBResult<Plan>
+    {
+        let (op, all, left, right) = match body {
+            SetExpr::SetOperation{op, all, left, right} => (op, *all, left, right),
+            other => {return Err(format!("{} is not a set operation", other));}
+        };
+        if all && *op != SetOperator::Union
+        {
+            return Err(format!("{} ALL unsupported yet", op));
+        }
+        let left_plan = self.parse_set_expr(left, None, None, &vec![])?;
+        let right_plan = self.parse_set_expr(right, None, None, &vec![])?;
+        let left_out = left_plan.output();
+        let right_out = right_plan.output();
+        check_set_columns(&left_out.borrow().headers(), &right_out.borrow().headers())?;
+
+        //The last steps of the queries are driven by the relation, so their rows are streamed.
+        //The right side of INTERSECT and EXCEPT is read at once
+        let mut steps = left_plan.into_steps();
+        let mut branches = vec![steps.pop().unwrap()];
+        let mut right_steps = right_plan.into_steps();
+        if *op == SetOperator::Union
+        {
+            branches.push(right_steps.pop().unwrap());
+        }
+        steps.append(&mut right_steps);
+        let relation = ConcatRelation::new(branches)?;
+        let mut input = ColumnBlock::new();
+        let mut expr_constr = ExprConstructor::new(self, &relation, &mut input);
+        let res_cols = expr_constr.process_wild(None)?;
+
+        let mut order_fields = Vec::<(String, bool)>::new();
+        for order_expr in order
+        {
+            let col_name = expr_constr.parse(&order_expr.expr)?;
+            if !all && !res_cols.contains(&col_name)
+            {
+                return Err(format!("For {}, ORDER BY expression {} must appear in select list", op, col_name));
+            }
+            order_fields.push((col_name, order_expr.asc.unwrap_or(true)));
+        }
+
+        let filter_col_name = match op {
+            SetOperator::Union => None,
+            _ => {
+                let name = format!("{} filter", op);
+                input.add_invisible(
+                    Column::new(ColumnHeader::new(&name, TypeName::DBBool)),
+                    RowInBlockSource::new_ref(right_out, res_cols.clone(), *op == SetOperator::Except)
+                );
+                Some(name)
+            }
+        };
+
+        let output = Self::select_output(&input, &res_cols, &order_fields);
+        let mut step = ExecuteStep::new(input, output);
+        step.add_proc(relation.make_driver()?);
+        Self::add_select_procs(&mut step, filter_col_name, res_cols, order_fields, !all, offset, limit);
+        steps.push(step);
+        Ok(Plan::from_steps(steps))
+    }
+
+    //The rows are evaluated while the plan is made, as the IN lists. Columns are named column1, column2, ...
+    //and get the common type of their values
+    fn parse_values(&self, values:&Values, offset:Option<usize>, limit:Option<usize>, order:&Vec<OrderByExpr>) -> DBResult<Plan>
+    {
+        let relation = BlockRelation::new(self.values_block(&values.0)?, "values", &[])?;
+        let mut input = ColumnBlock::new();
+        let mut expr_constr = ExprConstructor::new(self, &relation, &mut input);
+        let res_cols = expr_constr.process_wild(None)?;
+
+        let mut order_fields = Vec::<(String, bool)>::new();
+        for order_expr in order
+        {
+            order_fields.push((expr_constr.parse(&order_expr.expr)?, order_expr.asc.unwrap_or(true)));
+        }
+
+        let output = Self::select_output(&input, &res_cols, &order_fields);
+        let mut step = ExecuteStep::new(input, output);
+        step.add_proc(relation.make_driver()?);
+        Self::add_select_procs(&mut step, None, res_cols, order_fields, false, offset, limit);
+        Ok(Plan::new(step))
+    }
+
+    fn values_block(&self, rows:&[Vec<Expr>]) -> DBResult<BlockRef>
+    {
+        let width = rows[0].len();
+        let no_columns = BlockRelation::new(Rc::new(RefCell::new(ColumnBlock::new())), "values", &[])?;
+        let subqueries = self.subquery_steps.borrow().len();
+        let mut row_blocks = Vec::<(ColumnBlock, Vec<String>)>::new();
+        for row in rows
+        {
+            if row.len() != width
+            {
+                return Err(format!("VALUES rows have different numbers of values: {} and {}", width, row.len()));
             }
+            let mut input = ColumnBlock::new();
+            let mut expr_constr = ExprConstructor::new(self, &no_columns, &mut input);
+            let names = row.iter().map(|e| expr_constr.parse(e)).collect::<DBResult<Vec<String>>>()?;
+            input.process(1)?;
+            row_blocks.push((input, names));
+        }
+        if self.subquery_steps.borrow().len() != subqueries
+        {
+            return Err("Subqueries in VALUES unsupported yet".to_string());
+        }
+
+        let mut block = ColumnBlock::new();
+        for i in 0..width
+        {
+            let types:Vec<TypeName> = row_blocks.iter().map(|(b, names)| b.col_at(&names[i]).type_name()).collect();
+            let common = unify_types(&types).map_err(|_| {
+                let names:Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                format!("Values of column{} have incompatible types: {}", i + 1, names.join(", "))
+            })?;
+            let mut values = Column::new(ColumnHeader::new(&format!("column{}", i + 1), common.clone()));
+            values.resize(rows.len());
+            for (row, (b, names)) in row_blocks.iter().enumerate()
+            {
+                cast_column(b.col_at(&names[i]), common.base().clone())?.copy_to(&mut values, row);
+            }
+            block.add(values, DontTouchSource::new_ref());
+        }
+        Ok(Rc::new(RefCell::new(block)))
+    }
+
+    //The source rows are type checked against the schema and cast to the types of the columns, the omitted columns
+    //get the default values. The rows become visible to readers only when the whole INSERT is written
+    fn parse_insert(&self, table_name:&ObjectName, columns:&[Ident], source:&Query) -> DBResult<Plan>
+    {
+        let name = table_name.to_string();
+        let table = self.db.get_table(&name).ok_or(format!("Table {} don't exists", name))?;
+        let mut targets = Vec::<&ColumnHeader>::new();
+        for c in columns
+        {
+            let header = table.schema().find_col(&c.value).ok_or(format!("Column {} not found in {}", c.value, name))?;
+            if targets.contains(&header)
+            {
+                return Err(format!("Column {} is listed twice", c.value));
+            }
+            targets.push(header);
+        }
+        if columns.is_empty()
+        {
+            targets = table.schema().headers_ref().iter().collect();
+        }
+
+        //The rows of the query are inserted chunk by chunk as it produces them
+        let mut steps = self.parse_query(source)?.into_steps();
+        let relation = ConcatRelation::new(vec![steps.pop().unwrap()])?;
+        let headers:Vec<ColumnHeader> = relation.columns().into_iter().map(|(_, h)| h).collect();
+        if headers.len() != targets.len()
+        {
+            return Err(format!("INSERT into {} has {} columns but {} values", name, targets.len(), headers.len()));
+        }
+        let mut input = ColumnBlock::new();
+        let mut sources = HashMap::<String, String>::new();
+        for (target, h) in targets.iter().zip(headers.iter())
+        {
+            if !insert_fits(&h.type_name(), &target.type_name())
+            {
+                return Err(format!("Column {} is {}, can't insert {} of type {}", target.name(), target.type_name(), h.name(), h.type_name()));
+            }
+            input.add(Column::new(h.clone()), relation.make_source(None, h.name())?);
+            sources.insert(target.name().to_string(), h.name().to_string());
+        }
+
+        let mut output = ColumnBlock::new();
+        output.add(Column::new(ColumnHeader::new("inserted", TypeName::DBInt)), DontTouchSource::new_ref());
+        let insert_proc = InsertProcessor::new_ref(table.clone(), sources);
+        let mut step = ExecuteStep::new(input, output);
+        step.add_proc(relation.make_driver()?);
+        step.add_proc(insert_proc.clone());
+        step.add_post_proc(insert_proc);
+        steps.push(step);
+        Ok(Plan::from_steps(steps))
+    }
 
+    //CTEs shadow the tables of the database
+    fn get_relation(&self, name:&str, alias:Option<&TableAlias>) -> DBResult<RelationRef<'a>>
+    {
+        let alias_name = alias.map(|a| a.name.value.as_ref());
+        if let Some((block, cte_columns)) = self.ctes.borrow().get(name)
+        {
+            let columns = match alias {
+                Some(a) if !a.columns.is_empty() => &a.columns,
+                _ => cte_columns
+            };
+            return Ok(Box::new(BlockRelation::new(block.clone(), alias_name.unwrap_or(name), columns)?));
+        }
+        match self.db.get_table(name) {
+            Some(t) => Ok(Box::new(TableRelation::new(t, alias_name))),
+            None => Err(format!("Table {} don't exists", name))
+        }
     }
 
     fn parse_limit(lim_expr:&Option<Expr>) -> Option<usize>
@@ -67,11 +329,31 @@ impl<'a>  Constructor<'a>
 
     fn parse_select(&self, select:&Select, offset:Option<usize>, limit:Option<usize>, order:&Vec<OrderByExpr>) -> DBResult<Plan>
     {
-        let table = self.parse_from(&select.from)?;
+        let has_aggregates = select.projection.iter().any(
+            |itm| match itm {
+                SelectItem::UnnamedExpr(e) | SelectItem::ExprWithAlias{expr:e, ..} => contains_aggregate(self.db.functions(), e),
+                _ => false
+            }
+        );
+        if !select.group_by.is_empty() || has_aggregates
+        {
+            return self.parse_aggr_select(select, offset, limit, order);
+        }
+        let mut steps = Vec::<ExecuteStep>::new();
+        let relation = self.parse_array_join(self.parse_from(&select.from, &mut steps)?, select)?;
         let mut input = ColumnBlock::new();
-        let mut expr_constr = ExprConstructor::new(&table, &mut input);
+        let mut expr_constr = ExprConstructor::new(self, relation.as_ref(), &mut input);
+        expr_constr.shadow_columns(select.projection.iter().filter_map(
+            |itm| match itm {
+                SelectItem::ExprWithAlias{alias, ..} => Some(alias),
+                _ => None
+            }
+        ));
         let filter_col_name = match &select.selection {
-            Some(e) => Some(expr_constr.parse(&e)?),
+            Some(e) => Some(Self::check_filter("WHERE", e, |e| {
+                let col_name = expr_constr.parse(e)?;
+                Ok((col_name.clone(), expr_constr.input().col_at(&col_name).type_name()))
+            })?),
             None => None
         };
 
@@ -84,10 +366,16 @@ impl<'a>  Constructor<'a>
                 SelectItem::UnnamedExpr(e) => {
                     res_cols.push(expr_constr.parse(&e)?);
                 },
+                SelectItem::ExprWithAlias{expr, alias} => {
+                    let col_name = expr_constr.parse(expr)?;
+                    res_cols.push(expr_constr.add_alias(alias, &col_name)?);
+                },
                 SelectItem::Wildcard => {
-                    res_cols.append(&mut expr_constr.process_wild());
+                    res_cols.append(&mut expr_constr.process_wild(None)?);
+                },
+                SelectItem::QualifiedWildcard(name) => {
+                    res_cols.append(&mut expr_constr.process_wild(Some(&name.to_string()))?);
                 },
-                other => {return Err(format!("{} is not supported yet", other));}
             }
 
         }
@@ -96,11 +384,51 @@ impl<'a>  Constructor<'a>
         let mut order_fields = Vec::<(String, bool)>::new();
         for order_expr in order
         {
-            let col_name = expr_constr.parse(&order_expr.expr)?;
+            //Names of the result columns are taken before the names of the relation columns
+            let col_name = match &order_expr.expr {
+                Expr::Identifier(ident) if res_cols.contains(&ident.value) => ident.value.clone(),
+                e => expr_constr.parse(e)?
+            };
             let is_asc = order_expr.asc.unwrap_or(true);
             order_fields.push((col_name.clone(), is_asc));
         }
 
+        if select.distinct
+        {
+            if let Some((col_name, _)) = order_fields.iter().find(|(name, _)| !res_cols.contains(name))
+            {
+                return Err(format!("For SELECT DISTINCT, ORDER BY expression {} must appear in select list", col_name));
+            }
+        }
+
+        let output = Self::select_output(&input, &res_cols, &order_fields);
+        let mut step = ExecuteStep::new(input, output);
+        step.add_proc(relation.make_driver()?);
+        Self::add_select_procs(&mut step, filter_col_name, res_cols, order_fields, select.distinct, offset, limit);
+        steps.push(step);
+        Ok(Plan::from_steps(steps))
+    }
+
+    //Result columns are visible, ORDER BY columns are added invisibly if not in the result
+    //Filters are applied row by row, so the expression must be a predicate
+    //parse gives the column of the expression and its type
+    fn check_filter(clause:&str, e:&Expr, mut parse:impl FnMut(&Expr) -> DBResult<(String, TypeName)>) -> DBResult<String>
+    {
+        //NULL is false
+        let (col_name, type_name) = parse(e)?;
+        match type_name.base() {
+            TypeName::DBBool => Ok(col_name),
+            //NULL literal is cast to Nullable(Bool), so no rows pass
+            TypeName::DBNothing => {
+                let data_type = DataType::Custom(ObjectName(vec![Ident::new("Nullable(Bool)")]));
+                Ok(parse(&Expr::Cast{expr:Box::new(e.clone()), data_type})?.0)
+            },
+            _ => Err(format!("{} expression {} must be Bool, got {}", clause, col_name, type_name))
+        }
+    }
+
+    fn select_output(input:&ColumnBlock, res_cols:&[String], order_fields:&[(String, bool)]) -> ColumnBlock
+    {
         let mut output = ColumnBlock::new();
 
         for rcol in res_cols.iter()
@@ -114,8 +442,7 @@ impl<'a>  Constructor<'a>
 
         for (col_name, _) in order_fields.iter()
         {
-            //let not_in_res = res_cols.iter().find(|&x| x == &col_name).is_none();
-            if !output.has_col(&col_name)
+            if !output.has_col(col_name)
             {
                 let col = input.col_at(col_name);
                 output.add_invisible(
@@ -125,19 +452,34 @@ impl<'a>  Constructor<'a>
 
             }
         }
+        output
+    }
 
-
-
-
-
-
-        let mut step = ExecuteStep::new(input, output);
-        step.add_proc(
-            ChunkedProcessor::new_ref(table_size_iterator(&table).unwrap())
-        );
-
+    //Processors after the driver: filtering, DISTINCT, ORDER BY, OFFSET and LIMIT
+    fn add_select_procs(step:&mut ExecuteStep, filter_col_name:Option<String>, res_cols:Vec<String>,
+                        order_fields:Vec<(String, bool)>, distinct:bool, offset:Option<usize>, limit:Option<usize>)
+    {
         let has_order = !order_fields.is_empty();
 
+        if distinct
+        {
+            //Without ORDER BY it is enough to collect offset + limit unique rows
+            let max_rows = match limit {
+                Some(l) if !has_order => Some(l + offset.unwrap_or(0)),
+                _ => None
+            };
+            step.add_proc(DistinctProcessor::new_ref(filter_col_name, res_cols, max_rows));
+            if has_order
+            {
+                step.add_post_proc(OrderByPostProcessor::new_ref(order_fields, offset, limit));
+            }
+            else
+            {
+                step.add_post_proc(OffsetLimitPostProcessor::new_ref(offset, limit));
+            }
+            return;
+        }
+
         let copy_offset = if has_order {None} else {offset};
         let copy_limit = if has_order {None} else {limit};
 
@@ -155,30 +497,159 @@ impl<'a>  Constructor<'a>
         }
         else
         {
-            step.add_post_proc(append_proc_ref.clone());
+            step.add_post_proc(append_proc_ref);
         }
+    }
 
-        Ok(Plan::new(step))
+    fn parse_aggr_select(&self, select:&Select, offset:Option<usize>, limit:Option<usize>, order:&Vec<OrderByExpr>) -> DBResult<Plan>
+    {
+        let mut steps = Vec::<ExecuteStep>::new();
+        let relation = self.parse_array_join(self.parse_from(&select.from, &mut steps)?, select)?;
+        let mut input = ColumnBlock::new();
+        let mut expr_constr = ExprConstructor::new(self, relation.as_ref(), &mut input);
+        let filter_col_name = match &select.selection {
+            Some(e) => Some(Self::check_filter("WHERE", e, |e| {
+                let col_name = expr_constr.parse(e)?;
+                Ok((col_name.clone(), expr_constr.input().col_at(&col_name).type_name()))
+            })?),
+            None => None
+        };
+
+        let mut group_cols = Vec::<String>::new();
+        for e in select.group_by.iter()
+        {
+            group_cols.push(expr_constr.parse(e)?);
+        }
+
+        let mut aggr_constr = AggrConstructor::new(&mut expr_constr, filter_col_name, group_cols);
+
+        let mut res_cols = Vec::<String>::new();
+        for itm in select.projection.iter()
+        {
+            match itm {
+                SelectItem::UnnamedExpr(e) => {
+                    let col_name = aggr_constr.parse(e)?;
+                    aggr_constr.show(&col_name);
+                    res_cols.push(col_name);
+                },
+                SelectItem::ExprWithAlias{expr, alias} => {
+                    let col_name = aggr_constr.parse(expr)?;
+                    let alias_name = aggr_constr.add_alias(alias, &col_name)?;
+                    aggr_constr.show(&alias_name);
+                    res_cols.push(alias_name);
+                },
+                other => {return Err(format!("{} is not supported in aggregate queries yet", other));}
+            }
+        }
+
+        let having_col_name = match &select.having {
+            Some(e) => Some(Self::check_filter("HAVING", e, |e| {
+                let col_name = aggr_constr.parse(e)?;
+                Ok((col_name.clone(), aggr_constr.output().col_at(&col_name).type_name()))
+            })?),
+            None => None
+        };
+
+        let mut order_fields = Vec::<(String, bool)>::new();
+        for order_expr in order
+        {
+            let col_name = aggr_constr.parse(&order_expr.expr)?;
+            if select.distinct && !res_cols.contains(&col_name)
+            {
+                return Err(format!("For SELECT DISTINCT, ORDER BY expression {} must appear in select list", col_name));
+            }
+            order_fields.push((col_name, order_expr.asc.unwrap_or(true)));
+        }
+
+        let (aggr_proc, output) = aggr_constr.finish();
+        expr_constr.ensure_rows()?;
+        let aggr_proc_ref = aggr_proc.into_ref();
+
+        let mut step = ExecuteStep::new(input, output);
+        step.add_proc(relation.make_driver()?);
+        step.add_proc(aggr_proc_ref.clone());
+        step.add_post_proc(aggr_proc_ref);
+        if let Some(name) = having_col_name
+        {
+            step.add_post_proc(FilterPostProcessor::new_ref(name));
+        }
+        if select.distinct
+        {
+            step.add_post_proc(DistinctPostProcessor::new_ref(res_cols));
+        }
+
+        if order_fields.is_empty()
+        {
+            step.add_post_proc(OffsetLimitPostProcessor::new_ref(offset, limit));
+        }
+        else
+        {
+            step.add_post_proc(OrderByPostProcessor::new_ref(order_fields, offset, limit));
+        }
+
+        steps.push(step);
+        Ok(Plan::from_steps(steps))
     }
 
 
-    fn parse_from(&self, from:&Vec<TableWithJoins>) -> DBResult<&Table>
+    //arrayJoin calls of the query unnest the relation by their argument, so they must have the same argument
+    fn parse_array_join(&self, relation:RelationRef<'a>, select:&Select) -> DBResult<RelationRef<'a>>
+    {
+        let mut args = Vec::<Expr>::new();
+        for itm in select.projection.iter()
+        {
+            if let SelectItem::UnnamedExpr(e) | SelectItem::ExprWithAlias{expr:e, ..} = itm
+            {
+                array_join_args(e, &mut args);
+            }
+        }
+        for e in select.selection.iter().chain(select.group_by.iter())
+        {
+            array_join_args(e, &mut args);
+        }
+        match args.first() {
+            None => Ok(relation),
+            Some(arg) if args.iter().any(|a| a.to_string() != arg.to_string()) => {
+                Err("arrayJoin of different arrays in one query unsupported yet".to_string())
+            },
+            Some(arg) => Ok(Box::new(ArrayJoinRelation::new(self, relation, arg)?))
+        }
+    }
+
+    //Steps of subqueries are added to steps, they must be executed before the main step
+    fn parse_from(&self, from:&[TableWithJoins], steps:&mut Vec<ExecuteStep>) -> DBResult<RelationRef<'a>>
     {
         if from.len() != 1
         {
             return Err("Unexpected number of tables".to_string());
         }
-        if from[0].joins.len() > 0
+        self.parse_table_with_joins(&from[0], steps)
+    }
+
+    fn parse_table_with_joins(&self, twj:&TableWithJoins, steps:&mut Vec<ExecuteStep>) -> DBResult<RelationRef<'a>>
+    {
+        let mut relation = self.parse_table_factor(&twj.relation, steps)?;
+        for join in twj.joins.iter()
         {
-            return Err("Joins unsupported yet".to_string());
+            let right = self.parse_table_factor(&join.relation, steps)?;
+            relation = Box::new(JoinRelation::new(relation, right, &join.join_operator)?);
         }
-        match &from[0].relation {
-            TableFactor::Table{name,.. } => {
-                match self.db.get_table(&name.0[0].value) {
-                    Some(t) => Ok(&t),
-                    None => Err(format!("Table {} don't exists", name.0[0].value))
-                }
-            }
+        Ok(relation)
+    }
+
+    fn parse_table_factor(&self, factor:&TableFactor, steps:&mut Vec<ExecuteStep>) -> DBResult<RelationRef<'a>>
+    {
+        match factor {
+            TableFactor::Table{name, alias, ..} => self.get_relation(&name.0[0].value, alias.as_ref()),
+            TableFactor::NestedJoin(twj) => self.parse_table_with_joins(twj, steps),
+            TableFactor::Derived{lateral:false, subquery, alias} => {
+                let plan = self.parse_query(subquery)?;
+                let output = plan.output();
+                steps.append(&mut plan.into_steps());
+                let name = alias.as_ref().map(|a| a.name.value.as_ref()).unwrap_or("subquery");
+                let columns = alias.as_ref().map(|a| a.columns.as_slice()).unwrap_or(&[]);
+                Ok(Box::new(BlockRelation::new(output, name, columns)?))
+            },
             other => Err(format!("{} unsupported yet", other))
         }
     }
@@ -187,12 +658,127 @@ impl<'a>  Constructor<'a>
     fn parse_sql(sql:&str) ->DBResult<Vec<Statement>>
     {
         let dialect = GenericDialect {};
-        match Parser::parse_sql(&dialect, sql)
+        let tokens = Tokenizer::new(&dialect, sql).tokenize().map_err(|e| ParserError::from(e).to_string())?;
+        let mut parser = Parser::new(array_literals(tokens)?, &dialect);
+        let mut statements = Vec::<Statement>::new();
+        loop
         {
-            Ok(v) => Ok(v),
-            Err(e) => Err(e.to_string())
+            while parser.consume_token(&Token::SemiColon) {}
+            match parser.peek_token() {
+                Token::EOF => break,
+                _ if !statements.is_empty() => {
+                    let found = parser.peek_token();
+                    return Err(ParserError::ParserError(format!("Expected end of statement, found: {}", found)).to_string());
+                },
+                _ => statements.push(parser.parse_statement().map_err(|e| e.to_string())?)
+            }
+        }
+        Ok(statements)
+    }
+}
+
+//The parser knows [ only as the subscript of a column, so the array literal [a, b] is rewritten to array(a, b)
+//and the subscript of a call or a literal f(x)[i] to arrayElement(f(x), i).
+//[ is a literal unless it follows a column name, a closing parenthesis or another subscript
+fn array_literals(tokens:Vec<Token>) -> DBResult<Vec<Token>>
+{
+    let mut res = Vec::<Token>::with_capacity(tokens.len());
+    //Whether the open brackets close with )
+    let mut brackets = Vec::<bool>::new();
+    for token in tokens
+    {
+        match token {
+            Token::LBracket => {
+                let prev = res.iter().rposition(|t| !matches!(t, Token::Whitespace(_)));
+                match prev.map(|i| (i, &res[i])) {
+                    Some((_, Token::Word(w))) if w.quote_style.is_some() || w.keyword == Keyword::NoKeyword || !is_expr_keyword(w.keyword) => {
+                        brackets.push(false);
+                        res.push(Token::LBracket);
+                    },
+                    Some((_, Token::RBracket)) => {
+                        brackets.push(false);
+                        res.push(Token::LBracket);
+                    },
+                    Some((close, Token::RParen)) => {
+                        let start = call_start(&res, close)?;
+                        res.splice(start..start, [Token::make_word("arrayElement", None), Token::LParen]);
+                        brackets.push(true);
+                        res.push(Token::Comma);
+                    },
+                    _ => {
+                        brackets.push(true);
+                        res.extend([Token::make_word("array", None), Token::LParen]);
+                    }
+                }
+            },
+            Token::RBracket => {
+                let closes_paren = brackets.pop().ok_or("Unexpected ]")?;
+                res.push(if closes_paren {Token::RParen} else {Token::RBracket});
+            },
+            other => res.push(other)
+        }
+    }
+    Ok(res)
+}
+
+//Start of the parenthesized expression or the call closed by ) at close
+fn call_start(tokens:&[Token], close:usize) -> DBResult<usize>
+{
+    let mut depth = 0;
+    let mut open = None;
+    for i in (0..=close).rev()
+    {
+        match tokens[i] {
+            Token::RParen => depth += 1,
+            Token::LParen => {
+                depth -= 1;
+                if depth == 0
+                {
+                    open = Some(i);
+                    break;
+                }
+            },
+            _ => {}
         }
     }
+    let open = open.ok_or("Unexpected )")?;
+    let name = tokens[..open].iter().rposition(|t| !matches!(t, Token::Whitespace(_)));
+    Ok(match name.map(|i| (i, &tokens[i])) {
+        Some((i, Token::Word(w))) if w.quote_style.is_some() || w.keyword == Keyword::NoKeyword || !is_expr_keyword(w.keyword) => i,
+        _ => open
+    })
+}
+
+//Values fit the column if they convert without losing their meaning: integers to any numbers, decimals to decimals
+//and Float, strings to dates and LowCardinality. NULL fits Nullable columns only, arrays fit element by element
+fn insert_fits(from:&TypeName, to:&TypeName) -> bool
+{
+    if from.is_nullable() && !to.is_nullable()
+    {
+        return false;
+    }
+    let is_number = |t:&TypeName| t.is_integer() || *t == TypeName::DBFloat || matches!(t, TypeName::DBDecimal(..));
+    match (from.base(), to.base()) {
+        (from, to) if from == to => true,
+        (TypeName::DBNothing, _) => true,
+        (TypeName::DBArray(from), TypeName::DBArray(to)) => insert_fits(from, to),
+        (from, to) if from.is_integer() => is_number(to),
+        (TypeName::DBDecimal(..), TypeName::DBDecimal(..) | TypeName::DBFloat) => true,
+        (TypeName::DBFloat, TypeName::DBDecimal(..)) => true,
+        (TypeName::DBString, TypeName::DBDate | TypeName::DBDateTime | TypeName::DBLowCardinality) => true,
+        (TypeName::DBLowCardinality, TypeName::DBString) | (TypeName::DBDate, TypeName::DBDateTime) => true,
+        _ => false
+    }
+}
+
+//Keywords after which an expression starts
+fn is_expr_keyword(keyword:Keyword) -> bool
+{
+    matches!(keyword,
+        Keyword::SELECT | Keyword::WHERE | Keyword::AND | Keyword::OR | Keyword::NOT | Keyword::ON | Keyword::BY
+        | Keyword::HAVING | Keyword::WHEN | Keyword::THEN | Keyword::ELSE | Keyword::IN | Keyword::IS | Keyword::BETWEEN
+        | Keyword::LIKE | Keyword::ILIKE | Keyword::CASE | Keyword::DISTINCT | Keyword::VALUES
+    )
 }
 
 #[cfg(test)]
@@ -216,13 +802,22 @@ mod test {
         assert!(constr.make_plan(test_query).is_err());
 
         let test_query = "select id, age from regs where id + age";
+        assert_eq!(constr.make_plan(test_query).err(), Some("WHERE expression id + age must be Bool, got Int".to_string()));
+
+        let test_query = "select gender, count(id) from regs group by gender having count(id)";
+        assert!(constr.make_plan(test_query).is_err());
+
+        let test_query = "select id, age from regs where true";
         assert!(!constr.make_plan(test_query).is_err());
 
-        let test_query = "select id, age from regs where id + 100.5";
+        let test_query = "select id, age from regs where id + 100.5 > 105";
+        assert!(!constr.make_plan(test_query).is_err());
+
+        let test_query = "select id, age from regs where id + 'a'";
         assert!(constr.make_plan(test_query).is_err());
 
         let test_query = "select id, age from regs where id + 100";
-        assert!(!constr.make_plan(test_query).is_err());
+        assert!(constr.make_plan(test_query).is_err());
 
         println!("====================");
         let test_query = "select id from regs limit 100";
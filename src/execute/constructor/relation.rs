@@ -0,0 +1,461 @@
+use super::*;
+use crate::blocks::BlockRef;
+use crate::columns::Column;
+use crate::columns::header::ColumnHeader;
+use crate::execute::steps::ProcessorRef;
+use crate::execute::steps::join::*;
+use crate::execute::steps::array_join::*;
+use super::expr::ExprConstructor;
+use crate::functions::regular::cast::promote_comparable;
+use crate::functions::regular::low_cardinality::materialize_low_cardinality;
+use std::fs::File;
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+
+//Something in FROM the columns can be taken from: a table or a join of relations
+pub trait Relation
+{
+    //Returns the full qualified name and the header of the column, None if there is no such column.
+    //Unqualified names that exist in several tables are an error
+    fn find_col(&self, qualifier:Option<&str>, name:&str) -> DBResult<Option<(String, ColumnHeader)>>;
+    fn make_source(&self, qualifier:Option<&str>, name:&str) -> DBResult<ColumnSourceRef>;
+    //Columns for SELECT * as (qualifier, header)
+    fn columns(&self) -> Vec<(String, ColumnHeader)>;
+    //Processor which fills the block with the next chunk of rows
+    fn make_driver(&self) -> DBResult<ProcessorRef>;
+}
+
+pub type RelationRef<'a> = Box<dyn Relation + 'a>;
+
+pub struct TableRelation<'a>
+{
+    table :&'a Table,
+    alias :String,
+}
+
+impl<'a> TableRelation<'a>
+{
+    pub fn new(table:&'a Table, alias:Option<&str>) -> Self
+    {
+        Self{table, alias:alias.unwrap_or_else(|| table.name()).to_string()}
+    }
+}
+
+impl<'a> Relation for TableRelation<'a>
+{
+    fn find_col(&self, qualifier:Option<&str>, name:&str) -> DBResult<Option<(String, ColumnHeader)>>
+    {
+        if qualifier.is_some() && qualifier != Some(self.alias.as_ref())
+        {
+            return Ok(None);
+        }
+        Ok(
+            self.table.schema().find_col(name).map(
+                |h| (format!("{}.{}", self.alias, name), h.clone())
+            )
+        )
+    }
+
+    fn make_source(&self, qualifier:Option<&str>, name:&str) -> DBResult<ColumnSourceRef>
+    {
+        let (_, header) = self.find_col(qualifier, name)?.ok_or(
+            format!("Filed {} not found in {}", name, self.table.name())
+        )?;
+        let file = File::open(self.table.col_path(name).unwrap()).map_err(|e| e.to_string())?;
+        Ok(ExternalSource::new_ref(file, header.type_name()))
+    }
+
+    fn columns(&self) -> Vec<(String, ColumnHeader)>
+    {
+        self.table.schema().headers_ref().iter().map(|h| (self.alias.clone(), h.clone())).collect()
+    }
+
+    fn make_driver(&self) -> DBResult<ProcessorRef>
+    {
+        let sizes = table_size_iterator(self.table).map_err(|e| e.to_string())?;
+        Ok(ChunkedProcessor::new_ref(sizes))
+    }
+}
+
+//Output of a previous step, e.g. a subquery in FROM or a CTE
+pub struct BlockRelation
+{
+    block :BlockRef,
+    headers :Vec<ColumnHeader>,
+    src_names :Vec<String>,
+    alias :String,
+}
+
+impl BlockRelation
+{
+    //Not empty columns rename the columns of the block, as in `WITH t(a, b) AS (...)`
+    pub fn new(block:BlockRef, alias:&str, columns:&[Ident]) -> DBResult<Self>
+    {
+        let mut headers = block.borrow().headers();
+        let src_names:Vec<String> = headers.iter().map(|h| h.name().to_string()).collect();
+        if !columns.is_empty()
+        {
+            if columns.len() != headers.len()
+            {
+                return Err(
+                    format!("{} has {} columns but {} column names are given", alias, headers.len(), columns.len())
+                );
+            }
+            headers = headers.iter().zip(columns.iter()).map(
+                |(h, c)| ColumnHeader::new(&c.value, h.type_name())
+            ).collect();
+        }
+        Ok(Self{block, headers, src_names, alias:alias.to_string()})
+    }
+}
+
+impl Relation for BlockRelation
+{
+    fn find_col(&self, qualifier:Option<&str>, name:&str) -> DBResult<Option<(String, ColumnHeader)>>
+    {
+        if qualifier.is_some() && qualifier != Some(self.alias.as_ref())
+        {
+            return Ok(None);
+        }
+        Ok(
+            self.headers.iter().find(|h| h.name() == name).map(
+                |h| (format!("{}.{}", self.alias, name), h.clone())
+            )
+        )
+    }
+
+    fn make_source(&self, _qualifier:Option<&str>, name:&str) -> DBResult<ColumnSourceRef>
+    {
+        let pos = self.headers.iter().position(|h| h.name() == name).ok_or(
+            format!("Filed {} not found in {}", name, self.alias)
+        )?;
+        Ok(BlockSource::new_ref(self.block.clone(), &self.src_names[pos]))
+    }
+
+    fn columns(&self) -> Vec<(String, ColumnHeader)>
+    {
+        self.headers.iter().map(|h| (self.alias.clone(), h.clone())).collect()
+    }
+
+    fn make_driver(&self) -> DBResult<ProcessorRef>
+    {
+        Ok(SingleChunkProcessor::new_ref(self.block.clone()))
+    }
+}
+
+//Rows of the outputs of several steps one after another, as in UNION ALL. The steps are driven by the relation
+//(see ConcatProcessor). Columns are matched by position, names are taken from the first output
+pub struct ConcatRelation
+{
+    branches :RefCell<Vec<ExecuteStep>>,
+    blocks :Vec<BlockRef>,
+    headers :Vec<ColumnHeader>,
+    current :Rc<Cell<usize>>,
+}
+
+impl ConcatRelation
+{
+    pub fn new(branches:Vec<ExecuteStep>) -> DBResult<Self>
+    {
+        let blocks:Vec<BlockRef> = branches.iter().map(|s| s.output()).collect();
+        let headers = blocks[0].borrow().headers();
+        for block in blocks.iter().skip(1)
+        {
+            check_set_columns(&headers, &block.borrow().headers())?;
+        }
+        Ok(Self{branches:RefCell::new(branches), blocks, headers, current:Rc::new(Cell::new(0))})
+    }
+}
+
+impl Relation for ConcatRelation
+{
+    fn find_col(&self, qualifier:Option<&str>, name:&str) -> DBResult<Option<(String, ColumnHeader)>>
+    {
+        if qualifier.is_some()
+        {
+            return Ok(None);
+        }
+        Ok(self.headers.iter().find(|h| h.name() == name).map(|h| (name.to_string(), h.clone())))
+    }
+
+    fn make_source(&self, _qualifier:Option<&str>, name:&str) -> DBResult<ColumnSourceRef>
+    {
+        let pos = self.headers.iter().position(|h| h.name() == name).ok_or(
+            format!("Filed {} not found", name)
+        )?;
+        let srcs = self.blocks.iter().map(
+            |b| b.borrow().headers()[pos].name().to_string()
+        ).collect();
+        Ok(ConcatSource::new_ref(self.blocks.clone(), srcs, self.current.clone()))
+    }
+
+    fn columns(&self) -> Vec<(String, ColumnHeader)>
+    {
+        self.headers.iter().map(|h| (String::new(), h.clone())).collect()
+    }
+
+    fn make_driver(&self) -> DBResult<ProcessorRef>
+    {
+        Ok(ConcatProcessor::new_ref(self.branches.take(), self.current.clone()))
+    }
+}
+
+//Queries of set operations must return the same number of columns of the same types
+pub fn check_set_columns(left:&[ColumnHeader], right:&[ColumnHeader]) -> DBResult<()>
+{
+    if left.len() != right.len()
+    {
+        return Err(
+            format!("Queries of set operation have different numbers of columns: {} and {}", left.len(), right.len())
+        );
+    }
+    for (i, (l, r)) in left.iter().zip(right.iter()).enumerate()
+    {
+        if l.type_name() != r.type_name()
+        {
+            return Err(
+                format!(
+                    "Column {} of set operation has different types: {} is {} but {} is {}",
+                    i + 1, l.name(), l.type_name(), r.name(), r.type_name()
+                )
+            );
+        }
+    }
+    Ok(())
+}
+
+pub struct JoinRelation<'a>
+{
+    kind :JoinKind,
+    left :RelationRef<'a>,
+    right :RelationRef<'a>,
+    state :JoinState,
+    keys :Vec<JoinKey>,
+}
+
+impl<'a> JoinRelation<'a>
+{
+    pub fn new(left:RelationRef<'a>, right:RelationRef<'a>, operator:&JoinOperator) -> DBResult<Self>
+    {
+        let (kind, constraint) = match operator {
+            JoinOperator::Inner(c) => (JoinKind::Inner, c),
+            JoinOperator::LeftOuter(c) => (JoinKind::Left, c),
+            other => {return Err(format!("{:?} unsupported yet", other));}
+        };
+        let mut res = Self{kind, left, right, state:JoinState::new(), keys:Vec::new()};
+        match constraint {
+            JoinConstraint::On(e) => res.parse_on(e)?,
+            JoinConstraint::Using(idents) => {
+                for ident in idents.iter()
+                {
+                    res.add_key((None, &ident.value), (None, &ident.value))?;
+                }
+            },
+            _ => {return Err("JOIN without ON or USING unsupported yet".to_string());}
+        }
+        Ok(res)
+    }
+
+    fn parse_on(&mut self, expr:&Expr) -> DBResult<()>
+    {
+        match expr {
+            Expr::BinaryOp{left, op:BinaryOperator::And, right} => {
+                self.parse_on(left)?;
+                self.parse_on(right)
+            },
+            Expr::BinaryOp{left, op:BinaryOperator::Eq, right} => {
+                let l = column_ref(left)?;
+                let r = column_ref(right)?;
+                //Operands may be written in any order
+                if self.left.find_col(l.0, l.1)?.is_some()
+                {
+                    self.add_key(l, r)
+                }
+                else
+                {
+                    self.add_key(r, l)
+                }
+            },
+            Expr::Nested(e) => self.parse_on(e),
+            other => Err(format!("Only equalities joined by AND are supported in JOIN ON, got {}", other))
+        }
+    }
+
+    fn add_key(&mut self, left:(Option<&str>, &str), right:(Option<&str>, &str)) -> DBResult<()>
+    {
+        let (_, left_head) = self.left.find_col(left.0, left.1)?.ok_or(
+            format!("Filed {} not found in the left side of JOIN", left.1)
+        )?;
+        let (_, right_head) = self.right.find_col(right.0, right.1)?.ok_or(
+            format!("Filed {} not found in the right side of JOIN", right.1)
+        )?;
+        //NULL keys match nothing, so the keys are compared in the common type of their bases, LowCardinality as String
+        let types = [left_head.type_name(), right_head.type_name()].map(|t| materialize_low_cardinality(t.base()));
+        let type_name = match promote_comparable(&types) {
+            Some(promoted) => promoted[0].clone(),
+            None if types[0] == types[1] => types[0].clone(),
+            None => return Err(
+                format!(
+                    "JOIN key types mismatch: {} is {} but {} is {}",
+                    left.1, left_head.type_name(), right.1, right_head.type_name()
+                )
+            )
+        };
+        let left = self.side_column(false, left.0, left.1)?;
+        let right = self.side_column(true, right.0, right.1)?;
+        self.keys.push(JoinKey{left, right, type_name});
+        Ok(())
+    }
+
+    //Columns of the right side are NULL in the unmatched rows of LEFT JOIN
+    fn right_header(&self, header:ColumnHeader) -> ColumnHeader
+    {
+        match self.kind {
+            JoinKind::Left => ColumnHeader::new(header.name(), header.type_name().to_nullable()),
+            JoinKind::Inner => header
+        }
+    }
+
+    //Adds the column to the block of the side if needed, returns its name there
+    fn side_column(&self, is_right:bool, qualifier:Option<&str>, name:&str) -> DBResult<String>
+    {
+        let (relation, block) = if is_right {
+            (&self.right, &self.state.right_block)
+        } else {
+            (&self.left, &self.state.left_block)
+        };
+        let (col_name, header) = relation.find_col(qualifier, name)?.unwrap();
+        let mut block = block.borrow_mut();
+        if !block.has_col(&col_name)
+        {
+            block.add(
+                Column::new(ColumnHeader::new(&col_name, header.type_name())),
+                relation.make_source(qualifier, name)?
+            );
+        }
+        Ok(col_name)
+    }
+}
+
+impl<'a> Relation for JoinRelation<'a>
+{
+    fn find_col(&self, qualifier:Option<&str>, name:&str) -> DBResult<Option<(String, ColumnHeader)>>
+    {
+        match (self.left.find_col(qualifier, name)?, self.right.find_col(qualifier, name)?) {
+            (Some(_), Some(_)) => Err(format!("Column {} is ambiguous", name)),
+            (Some(c), None) => Ok(Some(c)),
+            (None, Some((col_name, header))) => Ok(Some((col_name, self.right_header(header)))),
+            (None, None) => Ok(None)
+        }
+    }
+
+    fn make_source(&self, qualifier:Option<&str>, name:&str) -> DBResult<ColumnSourceRef>
+    {
+        let is_right = self.left.find_col(qualifier, name)?.is_none();
+        let col_name = self.side_column(is_right, qualifier, name)?;
+        let (block, rows):(&BlockRef, _) = if is_right {
+            (&self.state.right_data, &self.state.right_rows)
+        } else {
+            (&self.state.left_block, &self.state.left_rows)
+        };
+        Ok(IndexedSource::new_ref(block.clone(), &col_name, rows.clone()))
+    }
+
+    fn columns(&self) -> Vec<(String, ColumnHeader)>
+    {
+        let mut res = self.left.columns();
+        res.extend(self.right.columns().into_iter().map(|(alias, h)| (alias, self.right_header(h))));
+        res
+    }
+
+    fn make_driver(&self) -> DBResult<ProcessorRef>
+    {
+        Ok(
+            JoinProcessor::new_ref(
+                self.kind, self.left.make_driver()?, self.right.make_driver()?, self.state.clone(), self.keys.clone()
+            )
+        )
+    }
+}
+
+//Rows of the inner relation repeated for each element of the array expression, the element is the column
+//named as the arrayJoin call (see array_join_column). Empty and NULL arrays give no rows
+pub struct ArrayJoinRelation<'a>
+{
+    inner :RelationRef<'a>,
+    state :ArrayJoinState,
+    array_col :String,
+    name :String,
+    header :ColumnHeader,
+}
+
+impl<'a> ArrayJoinRelation<'a>
+{
+    pub fn new(constr:&Constructor<'a>, inner:RelationRef<'a>, arg:&Expr) -> DBResult<Self>
+    {
+        let state = ArrayJoinState::new();
+        let array_col = {
+            let mut block = state.inner_block.borrow_mut();
+            ExprConstructor::new(constr, inner.as_ref(), &mut block).parse(arg)?
+        };
+        let type_name = state.inner_block.borrow().col_at(&array_col).type_name();
+        let elem = type_name.base().element().ok_or_else(
+            || format!("Argument of arrayJoin must be Array, got {}", type_name)
+        )?.clone();
+        let name = format!("arrayJoin({})", arg);
+        let header = ColumnHeader::new(&name, elem);
+        Ok(Self{inner, state, array_col, name, header})
+    }
+}
+
+impl<'a> Relation for ArrayJoinRelation<'a>
+{
+    fn find_col(&self, qualifier:Option<&str>, name:&str) -> DBResult<Option<(String, ColumnHeader)>>
+    {
+        if qualifier.is_none() && name == self.name
+        {
+            return Ok(Some((self.name.clone(), self.header.clone())));
+        }
+        self.inner.find_col(qualifier, name)
+    }
+
+    fn make_source(&self, qualifier:Option<&str>, name:&str) -> DBResult<ColumnSourceRef>
+    {
+        if qualifier.is_none() && name == self.name
+        {
+            return Ok(ArrayElementsSource::new_ref(self.state.inner_block.clone(), &self.array_col, self.state.elems.clone()));
+        }
+        let (col_name, header) = self.inner.find_col(qualifier, name)?.ok_or(
+            format!("Filed {} not found", name)
+        )?;
+        let mut block = self.state.inner_block.borrow_mut();
+        if !block.has_col(&col_name)
+        {
+            block.add(
+                Column::new(ColumnHeader::new(&col_name, header.type_name())),
+                self.inner.make_source(qualifier, name)?
+            );
+        }
+        Ok(IndexedSource::new_ref(self.state.inner_block.clone(), &col_name, self.state.rows.clone()))
+    }
+
+    fn columns(&self) -> Vec<(String, ColumnHeader)>
+    {
+        self.inner.columns()
+    }
+
+    fn make_driver(&self) -> DBResult<ProcessorRef>
+    {
+        Ok(ArrayJoinProcessor::new_ref(self.inner.make_driver()?, self.state.clone(), &self.array_col))
+    }
+}
+
+//Splits a column reference into the optional qualifier and the column name
+pub fn column_ref(expr:&Expr) -> DBResult<(Option<&str>, &str)>
+{
+    match expr {
+        Expr::Identifier(ident) => Ok((None, &ident.value)),
+        Expr::CompoundIdentifier(idents) if idents.len() == 2 => Ok((Some(&idents[0].value), &idents[1].value)),
+        other => Err(format!("{} is not a column", other))
+    }
+}
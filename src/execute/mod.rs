@@ -10,17 +10,27 @@ use crate::db::DB;
 use constructor::Constructor;
 
 
-//TODO While simple of one step. In the future, it needs to be expanded to add sequential steps
+//Steps are executed in order, a step may read the output of the previous ones
 pub struct Plan
 {
-    step :ExecuteStep,
+    steps :Vec<ExecuteStep>,
 }
 
 impl Plan
 {
     pub fn new(step:ExecuteStep) -> Self
     {
-        Self{step}
+        Self{steps:vec![step]}
+    }
+
+    pub fn from_steps(steps:Vec<ExecuteStep>) -> Self
+    {
+        Self{steps}
+    }
+
+    pub fn into_steps(self) -> Vec<ExecuteStep>
+    {
+        self.steps
     }
 
     pub fn from_sql(db:&DB, sql:&str) -> DBResult<Self>
@@ -31,17 +41,21 @@ impl Plan
 
     pub fn execute(&mut self) -> DBResult<()>
     {
-        self.step.execute()
+        for step in self.steps.iter_mut()
+        {
+            step.execute()?;
+        }
+        Ok(())
     }
 
     pub fn output(&self) -> BlockRef
     {
-        self.step.output().clone()
+        self.steps.last().unwrap().output()
     }
 
     pub fn result_cli_table(&self, max_rows:usize) -> TableStruct
     {
-        let out = self.step.output();
+        let out = self.output();
         let bl = out.borrow();
         bl.cli_table(max_rows)
     }
@@ -52,7 +66,12 @@ mod test {
     use super::*;
     use crate::test_misc::*;
     use crate::types::types::*;
+    use crate::types::TypeName;
+    use crate::columns::Column;
+    use crate::functions::regular::{RegFunction, RegFunctionBuilder};
+    use crate::functions::aggregate::{AggrColumn, AggrColumnRef, AggrFunctionBuilder};
     use cli_table::print_stdout;
+    use crate::io::db::table_size_iterator;
 
     #[test]
     fn limit()
@@ -108,4 +127,1221 @@ mod test {
             );
         cleanup_test_table("order_db");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn group_by()
+    {
+        cleanup_test_table("group_db");
+        let db = create_test_db("group_db", 10);
+        let mut plan = Plan::from_sql(&db, "select gender, sum(value), sum(id) from regs group by gender order by gender").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.rows_len(), 2);
+        assert_eq!(
+            *out_block.col_at("gender").downcast_data_ref::<DBString>().unwrap().data_ref(),
+            vec!["Female".to_string(), "Male".to_string()]
+            );
+        assert_eq!(
+            *out_block.col_at("sum(value)").downcast_data_ref::<DBFloat>().unwrap().data_ref(),
+            vec![6.5, 4.]
+            );
+        assert_eq!(
+            out_block.col_at("sum(id)").downcast_data_ref::<DBInt>().unwrap().as_ref(),
+            vec![30 as i64, 25]
+            );
+
+        let mut plan = Plan::from_sql(&db, "select gender, age > 5, sum(id) from regs where id > 2 group by gender, age > 5 order by sum(id) desc limit 3").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.rows_len(), 3);
+        assert_eq!(
+            out_block.col_at("sum(id)").downcast_data_ref::<DBInt>().unwrap().as_ref(),
+            vec![18 as i64, 16, 10]
+            );
+
+        assert!(Plan::from_sql(&db, "select id, sum(value) from regs group by gender").is_err());
+        cleanup_test_table("group_db");
+    }
+
+    #[test]
+    fn aggregate_functions()
+    {
+        cleanup_test_table("aggr_func_db");
+        let db = create_test_db("aggr_func_db", 10);
+        let mut plan = Plan::from_sql(
+            &db,
+            "select gender, count(*), count(id), min(age), max(value), avg(id), uniq(value), count(DISTINCT value), min(gender) \
+             from regs group by gender order by gender"
+        ).unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.rows_len(), 2);
+        assert_eq!(*out_block.col_at("count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5, 5]);
+        assert_eq!(*out_block.col_at("count(id)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5, 5]);
+        assert_eq!(*out_block.col_at("min(age)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![2, 3]);
+        assert_eq!(*out_block.col_at("max(value)").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![2.5, 2.]);
+        assert_eq!(*out_block.col_at("avg(id)").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![6., 5.]);
+        assert_eq!(*out_block.col_at("uniq(value)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 3]);
+        assert_eq!(*out_block.col_at("count(DISTINCT value)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 3]);
+        assert_eq!(
+            *out_block.col_at("min(gender)").downcast_data_ref::<DBString>().unwrap().data_ref(),
+            vec!["Female".to_string(), "Male".to_string()]
+            );
+        assert_eq!(out_block.col_at("avg(id)").type_name(), TypeName::DBFloat);
+
+        assert!(Plan::from_sql(&db, "select gender, avg(gender) from regs group by gender").is_err());
+        assert!(Plan::from_sql(&db, "select gender, sum(DISTINCT id) from regs group by gender").is_err());
+        cleanup_test_table("aggr_func_db");
+    }
+
+    #[test]
+    fn aggregate_without_group()
+    {
+        cleanup_test_table("global_aggr_db");
+        let db = create_test_db("global_aggr_db", 10);
+        let mut plan = Plan::from_sql(&db, "select sum(value), count(*), max(id) from regs where age > 5").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.rows_len(), 1);
+        assert_eq!(*out_block.col_at("sum(value)").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![7.5]);
+        assert_eq!(*out_block.col_at("count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![6]);
+        assert_eq!(*out_block.col_at("max(id)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![6]);
+
+        let mut plan = Plan::from_sql(&db, "select count(*), sum(id) from regs where age > 100").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.rows_len(), 1);
+        assert_eq!(*out_block.col_at("count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![0]);
+        assert_eq!(*out_block.col_at("sum(id)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![0]);
+
+        //avg, min and max have no value for empty input
+        let mut plan = Plan::from_sql(&db, "select count(*), sum(value), avg(value), min(id), max(id) from regs where id < 0").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.rows_len(), 1);
+        assert_eq!(*out_block.col_at("count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![0]);
+        assert_eq!(*out_block.col_at("sum(value)").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![0.0]);
+        for name in ["avg(value)", "min(id)", "max(id)"]
+        {
+            assert!(out_block.col_at(name).type_name().is_nullable());
+            assert_eq!(*out_block.col_at(name).nulls().unwrap(), vec![true]);
+        }
+
+        assert!(Plan::from_sql(&db, "select id, count(*) from regs").is_err());
+        assert!(Plan::from_sql(&db, "select id from regs where sum(id) > 1").is_err());
+        cleanup_test_table("global_aggr_db");
+    }
+
+    #[test]
+    fn having()
+    {
+        cleanup_test_table("having_db");
+        let db = create_test_db("having_db", 10);
+        let mut plan = Plan::from_sql(
+            &db,
+            "select gender, age > 5, sum(id) from regs group by gender, age > 5 having sum(id) > 10 and gender = 'Female' order by sum(id)"
+        ).unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.rows_len(), 2);
+        assert_eq!(*out_block.col_at("sum(id)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![12, 18]);
+
+        let mut plan = Plan::from_sql(
+            &db,
+            "select gender, sum(id) / count(*), max(id) - min(id) from regs group by gender having count(*) * 2 > 8 order by gender"
+        ).unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.rows_len(), 2);
+        assert_eq!(
+            *out_block.col_at("sum(id) / count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(),
+            vec![6, 5]
+            );
+        assert_eq!(*out_block.col_at("max(id) - min(id)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![8, 8]);
+
+        assert!(Plan::from_sql(&db, "select gender, sum(id) from regs group by gender having age > 5").is_err());
+        cleanup_test_table("having_db");
+    }
+
+    #[test]
+    fn distinct()
+    {
+        cleanup_test_table("distinct_db");
+        let db = create_test_db("distinct_db", 10);
+        let mut plan = Plan::from_sql(&db, "select distinct gender from regs").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(
+            *out_block.col_at("gender").downcast_data_ref::<DBString>().unwrap().data_ref(),
+            vec!["Male".to_string(), "Female".to_string()]
+            );
+
+        let mut plan = Plan::from_sql(&db, "select distinct value, gender from regs where id > 1 order by value desc, gender limit 3 offset 1").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("value").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![2., 1.5, 1.]);
+
+        let mut plan = Plan::from_sql(&db, "select distinct value from regs limit 2 offset 1").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("value").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![0.5, 1.]);
+
+        let mut plan = Plan::from_sql(&db, "select distinct count(*) from regs group by gender").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5]);
+
+        assert!(Plan::from_sql(&db, "select distinct gender from regs order by id").is_err());
+        cleanup_test_table("distinct_db");
+    }
+
+    #[test]
+    fn aliases()
+    {
+        cleanup_test_table("alias_db");
+        let db = create_test_db("alias_db", 10);
+        let mut plan = Plan::from_sql(&db, "select id, age + 1 as next_age from regs where id > 5 order by next_age limit 2").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert!(!out_block.has_col("age + 1"));
+        assert_eq!(*out_block.col_at("next_age").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 4]);
+        assert_eq!(*out_block.col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![10, 9]);
+        assert_eq!(out_block.col_at("next_age").header().name(), "next_age");
+
+        let mut plan = Plan::from_sql(&db, "select gender g, count(*) as c, count(*) + 1 from regs group by gender having c > 1 order by g desc").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(
+            *out_block.col_at("g").downcast_data_ref::<DBString>().unwrap().data_ref(),
+            vec!["Male".to_string(), "Female".to_string()]
+            );
+        assert_eq!(*out_block.col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5, 5]);
+        assert_eq!(*out_block.col_at("count(*) + 1").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![6, 6]);
+
+        //The alias shadows the column in the output and in ORDER BY, the column is still used by the expressions
+        let mut plan = Plan::from_sql(&db, "select id + 1 as id, id * 2 as d from regs where id < 4 order by id desc").unwrap();
+        plan.execute().unwrap();
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![4, 3, 2]);
+        assert_eq!(*out_block.col_at("d").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![6, 4, 2]);
+        assert!(Plan::from_sql(&db, "select id as age, age from regs").is_ok());
+
+        assert!(Plan::from_sql(&db, "select id as a, age as a from regs").is_err());
+        cleanup_test_table("alias_db");
+    }
+
+    #[test]
+    fn join()
+    {
+        cleanup_test_table("join_db");
+        let db = create_test_db("join_db", 10);
+        let mut plan = Plan::from_sql(&db, "select a.id, b.id from regs a join regs b on a.id = b.age order by a.id").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("a.id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(*out_block.col_at("b.id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![10, 9, 8, 7, 6, 5, 4, 3, 2]);
+
+        //Unmatched rows of LEFT JOIN get NULLs
+        let mut plan = Plan::from_sql(&db, "select a.id, b.id from regs a left join regs b on b.age = a.id order by a.id limit 3").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("a.id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 2, 3]);
+        assert_eq!(*out_block.col_at("b.id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![0, 10, 9]);
+        assert_eq!(out_block.col_at("b.id").type_name(), TypeName::DBInt.to_nullable());
+        assert_eq!(*out_block.col_at("b.id").nulls().unwrap(), vec![true, false, false]);
+
+        let mut plan = Plan::from_sql(&db, "select count(*) from regs a join regs b on a.id = b.age and a.value = b.value").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3]);
+
+        let mut plan = Plan::from_sql(&db, "select * from regs a join regs b using (id) where b.age > 9").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(out_block.cols_len(), 8);
+        assert_eq!(*out_block.col_at("a.id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 2]);
+
+        //NULL keys match nothing, in LEFT JOIN their rows get NULLs
+        let sql = "select a.x, b.y from (values (1), (2), (NULL)) a(x) {} join (values (1, 10), (NULL, 20)) b(x, y) on a.x = b.x order by a.x";
+        let mut plan = Plan::from_sql(&db, &sql.replace("{}", "")).unwrap();
+        plan.execute().unwrap();
+        assert_eq!(plan.output().borrow().col_at("b.y").data_ref().to_string_at(0), "10");
+        assert_eq!(plan.output().borrow().rows_len(), 1);
+        let mut plan = Plan::from_sql(&db, &sql.replace("{}", "left")).unwrap();
+        plan.execute().unwrap();
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("b.y").nulls().unwrap(), vec![false, true, true]);
+        assert_eq!(out_block.col_at("b.y").data_ref().to_string_at(0), "10");
+
+        //Keys of different integer types and Nullable keys are compared in the common type
+        let sql = "select a.x, b.y from (values (1), (2)) a(x) join (select cast(id as UInt8) x, if(id > 1, id, NULL) y from regs) b on a.x = b.x join regs c on b.y = c.id order by a.x";
+        let mut plan = Plan::from_sql(&db, sql).unwrap();
+        plan.execute().unwrap();
+        assert_eq!(plan.output().borrow().col_at("b.y").data_ref().to_string_at(0), "2");
+        assert_eq!(plan.output().borrow().rows_len(), 1);
+
+        let sql = "select count(*) c from regs a join (select toLowCardinality(gender) g from regs) b on a.gender = b.g";
+        let mut plan = Plan::from_sql(&db, sql).unwrap();
+        plan.execute().unwrap();
+        assert_eq!(plan.output().borrow().col_at("c").data_ref().to_string_at(0), "50");
+
+        assert!(Plan::from_sql(&db, "select regs.id from regs").is_ok());
+        assert!(Plan::from_sql(&db, "select id from regs a join regs b on a.id = b.id").is_err());
+        assert!(Plan::from_sql(&db, "select a.id from regs a join regs b on a.id = b.gender").is_err());
+        assert!(Plan::from_sql(&db, "select a.id from regs a join regs b on a.id > b.id").is_err());
+        cleanup_test_table("join_db");
+    }
+
+    #[test]
+    fn derived_tables()
+    {
+        cleanup_test_table("derived_db");
+        let db = create_test_db("derived_db", 10);
+        let mut plan = Plan::from_sql(
+            &db,
+            "select gender, c from (select gender, count(*) c from regs where id > 3 group by gender) where c > 3"
+        ).unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("gender").downcast_data_ref::<DBString>().unwrap().data_ref(), vec!["Female".to_string()]);
+        assert_eq!(*out_block.col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![4]);
+
+        let mut plan = Plan::from_sql(
+            &db,
+            "select r.id, s.c from regs r join (select gender, count(*) c from regs group by gender) s \
+             on r.gender = s.gender where r.id < 3 order by r.id"
+        ).unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("r.id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 2]);
+        assert_eq!(*out_block.col_at("s.c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5, 5]);
+
+        let mut plan = Plan::from_sql(&db, "select max(id) from (select id from (select id, age from regs) t where id < 5)").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("max(id)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![4]);
+
+        assert!(Plan::from_sql(&db, "select age from (select id from regs)").is_err());
+        cleanup_test_table("derived_db");
+    }
+
+    #[test]
+    fn ctes()
+    {
+        cleanup_test_table("cte_db");
+        let db = create_test_db("cte_db", 10);
+        let mut plan = Plan::from_sql(
+            &db,
+            "with g as (select gender, count(*) c from regs where id > 3 group by gender), \
+                  m as (select max(c) mc from g) \
+             select g.gender, g.c from g join m on g.c = m.mc"
+        ).unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("g.gender").downcast_data_ref::<DBString>().unwrap().data_ref(), vec!["Female".to_string()]);
+        assert_eq!(*out_block.col_at("g.c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![4]);
+
+        //The CTE is calculated once for both references
+        let mut plan = Plan::from_sql(
+            &db,
+            "with g as (select gender, count(*) c from regs group by gender) \
+             select count(*) from g a join g b on a.gender = b.gender"
+        ).unwrap();
+        assert_eq!(plan.steps.len(), 2);
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![2]);
+
+        let mut plan = Plan::from_sql(&db, "with t(x) as (select id from regs) select max(x) from t").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("max(x)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![10]);
+
+        assert!(Plan::from_sql(&db, "with t(x, y) as (select id from regs) select x from t").is_err());
+        assert!(Plan::from_sql(&db, "select id from (with t as (select id from regs) select id from t) s join t on s.id = t.id").is_err());
+        cleanup_test_table("cte_db");
+    }
+
+    #[test]
+    fn set_operations()
+    {
+        cleanup_test_table("set_op_db");
+        let db = create_test_db("set_op_db", 10);
+        let queries = [
+            ("select id from regs where id < 4 union all select id from regs where id > 8 order by id", vec![1, 2, 3, 9, 10]),
+            ("select id from regs where id = 1 union all select age as x from regs where id = 1", vec![1, 11]),
+            ("select id from regs where id < 3 union all select id from regs where id < 3 order by id desc limit 3", vec![2, 2, 1]),
+            ("select id from regs where id < 4 union select id from regs where id < 6 order by id", vec![1, 2, 3, 4, 5]),
+            ("select id from regs where id < 6 intersect select id from regs where id > 3 order by id", vec![4, 5]),
+            ("select id from regs where id < 6 except select id from regs where id > 3 order by id", vec![1, 2, 3]),
+            //The plain queries are streamed chunk by chunk, with ORDER BY or GROUP BY they are read at once
+            ("select id from regs where id > 7 union all select id from regs where id < 3 union all select id from regs where id = 5", vec![8, 9, 10, 1, 2, 5]),
+            ("select * from (select id from regs order by id desc limit 2) union all select count(*) from regs group by gender", vec![10, 9, 5, 5]),
+            ("select id from regs union all select id from regs limit 3 offset 5", vec![6, 7, 8]),
+        ];
+        for (sql, expected) in queries.iter()
+        {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+
+            let out_block_ref = plan.output();
+            let out_block = out_block_ref.borrow();
+            assert_eq!(*out_block.col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), *expected, "{}", sql);
+        }
+
+        let mut plan = Plan::from_sql(&db, "select gender from regs union select gender from regs order by gender").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(
+            *out_block.col_at("gender").downcast_data_ref::<DBString>().unwrap().data_ref(),
+            vec!["Female".to_string(), "Male".to_string()]
+            );
+
+        assert!(Plan::from_sql(&db, "select id from regs union select gender from regs").is_err());
+        assert!(Plan::from_sql(&db, "select id from regs union select id, age from regs").is_err());
+        assert!(Plan::from_sql(&db, "select id from regs intersect all select id from regs").is_err());
+        cleanup_test_table("set_op_db");
+    }
+
+    #[test]
+    fn in_predicates()
+    {
+        cleanup_test_table("in_db");
+        let db = create_test_db("in_db", 10);
+        let queries = [
+            ("select id from regs where id in (1, 3, 20) order by id", vec![1, 3]),
+            ("select id from regs where gender not in ('Male') and id < 5 order by id", vec![2, 4]),
+            ("select id from regs where value in (1.5) order by id", vec![4, 10]),
+            ("select id from regs where id - 5 in (-1, -3, 20) order by id", vec![2, 4]),
+            ("select id from regs where value in (1, 1.5) and id < 5 order by id", vec![3, 4]),
+            ("select id from regs where value in (1, 2) order by id", vec![3, 5, 9]),
+            ("select id from regs where age in (2, 3.5) order by id", vec![10]),
+            ("select id from regs where gender in (select toLowCardinality(gender) from regs where id = 2) and id < 5 order by id", vec![2, 4]),
+            ("select id from regs where toLowCardinality(gender) in (select toLowCardinality(gender) from regs where id = 1) and id < 5 order by id", vec![1, 3]),
+            ("select id from regs where age in (select id + 5 from regs where id < 3) order by id", vec![5, 6]),
+        ];
+        for (sql, expected) in queries.iter()
+        {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+
+            let out_block_ref = plan.output();
+            let out_block = out_block_ref.borrow();
+            assert_eq!(*out_block.col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), *expected, "{}", sql);
+        }
+
+        let mut plan = Plan::from_sql(
+            &db,
+            "with t as (select id from regs where id > 8) select count(*) from regs where id not in (select id from t)"
+        ).unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("count(*)").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![8]);
+
+        //NULL in the set makes the values not found in it NULL
+        let strings = |sql:&str, name:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            let out = plan.output();
+            let b = out.borrow();
+            let col = b.col_at(name);
+            (0..col.len()).map(|i| col.data_ref().to_string_at(i)).collect::<Vec<String>>()
+        };
+        assert_eq!(strings("select id in (1, NULL) as r, id not in (1, NULL) as n from regs where id < 3 order by id", "r"), vec!["true", "NULL"]);
+        assert_eq!(strings("select id in (1, NULL) as r, id not in (1, NULL) as n from regs where id < 3 order by id", "n"), vec!["false", "NULL"]);
+        assert_eq!(strings("select NULL in (1, 2) as r from regs where id = 1", "r"), vec!["NULL"]);
+        assert_eq!(strings("select id from regs where id in (1, NULL)", "id"), vec!["1"]);
+        assert_eq!(strings("select count(*) c from regs where id not in (select if(id > 2, id, NULL) from regs)", "c"), vec!["0"]);
+
+        assert!(Plan::from_sql(&db, "select id from regs where id in ('a')").is_err());
+        assert!(Plan::from_sql(&db, "select id from regs where id in (1, 'a')").is_err());
+        assert!(Plan::from_sql(&db, "select id from regs where id in (select id, age from regs)").is_err());
+        cleanup_test_table("in_db");
+    }
+
+    #[test]
+    fn pattern_predicates()
+    {
+        cleanup_test_table("pattern_db");
+        let db = create_test_db("pattern_db", 10);
+        let queries = [
+            ("select id from regs where id between 3 and 5 order by id", vec![3, 4, 5]),
+            ("select id from regs where id not between 2 and 9 order by id", vec![1, 10]),
+            ("select id from regs where gender like 'M%' and id < 4 order by id", vec![1, 3]),
+            ("select id from regs where gender ilike '%MALE' and id < 3 order by id", vec![1, 2]),
+            ("select id from regs where gender not like '_ale' and id < 5 order by id", vec![2, 4]),
+            ("select id from regs where match(gender, '^Fe') and id < 5 order by id", vec![2, 4]),
+        ];
+        for (sql, expected) in queries.iter()
+        {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+
+            let out_block_ref = plan.output();
+            let out_block = out_block_ref.borrow();
+            assert_eq!(*out_block.col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), *expected, "{}", sql);
+        }
+
+        let mut plan = Plan::from_sql(&db, "select gender, count(*) from regs group by gender having gender like 'F%'").unwrap();
+        plan.execute().unwrap();
+
+        let out_block_ref = plan.output();
+        let out_block = out_block_ref.borrow();
+        assert_eq!(*out_block.col_at("gender").downcast_data_ref::<DBString>().unwrap().data_ref(), vec!["Female".to_string()]);
+
+        assert!(Plan::from_sql(&db, "select id from regs where id like 'a'").is_err());
+        assert!(Plan::from_sql(&db, "select id from regs where id between 1 and 'a'").is_err());
+        assert!(Plan::from_sql(&db, "select id from regs where undefined_function(id)").is_err());
+        cleanup_test_table("pattern_db");
+    }
+
+    #[test]
+    fn conditionals()
+    {
+        cleanup_test_table("cond_db");
+        let db = create_test_db("cond_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+        let strings = |v:&[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+
+        let out = run("select case when id < 3 then 'low' when id < 6 then 'mid' else 'high' end as level from regs order by id limit 7");
+        assert_eq!(
+            *out.borrow().col_at("level").downcast_data_ref::<DBString>().unwrap().data_ref(),
+            strings(&["low", "low", "mid", "mid", "mid", "high", "high"])
+        );
+
+        let out = run("select case gender when 'Male' then 1 end as m from regs order by id limit 4");
+        assert_eq!(*out.borrow().col_at("m").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 0, 1, 0]);
+
+        let out = run("select if(id > 8, value, 0) as v from regs where id > 6 order by id");
+        assert_eq!(*out.borrow().col_at("v").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![0., 0., 1., 1.5]);
+
+        let out = run("select multiIf(id < 2, 'a', id < 3, 'b', 'c') as r from regs where id < 5 order by id");
+        assert_eq!(*out.borrow().col_at("r").downcast_data_ref::<DBString>().unwrap().data_ref(), strings(&["a", "b", "c", "c"]));
+
+        let out = run("select gender, sum(case when id > 5 then 1 else 0 end) as s from regs group by gender order by gender");
+        assert_eq!(*out.borrow().col_at("s").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 2]);
+
+        let out = run("select case when count(*) > 5 then 'many' else 'few' end as c from regs");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBString>().unwrap().data_ref(), strings(&["many"]));
+
+        //coalesce takes the first non-NULL argument, the arguments are unified as the branches
+        let out = run("select coalesce(if(id < 3, NULL, value), id) as c, coalesce(multiIf(id < 2, NULL, id < 4, 'b', NULL), gender) as g from regs where id < 5 order by id");
+        assert_eq!(out.borrow().col_at("c").type_name(), TypeName::DBFloat);
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![1., 2., 1., 1.5]);
+        assert_eq!(*out.borrow().col_at("g").downcast_data_ref::<DBString>().unwrap().data_ref(), strings(&["Male", "b", "b", "Female"]));
+        assert!(Plan::from_sql(&db, "select coalesce(id, gender) from regs").is_err());
+
+        assert!(Plan::from_sql(&db, "select case when id > 1 then 1 else 'a' end from regs").is_err());
+        assert!(Plan::from_sql(&db, "select case when gender then 1 end from regs").is_err());
+        assert!(Plan::from_sql(&db, "select if(id > 1, 1) from regs").is_err());
+        cleanup_test_table("cond_db");
+    }
+
+    #[test]
+    fn casts()
+    {
+        cleanup_test_table("cast_db");
+        let db = create_test_db("cast_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+
+        let out = run("select id from regs where id + 0.5 > value * 4 and id < 6 order by id");
+        assert_eq!(*out.borrow().col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 2]);
+
+        let out = run("select id / 2.0 as h from regs where id <= 3 order by id");
+        assert_eq!(*out.borrow().col_at("h").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![0.5, 1., 1.5]);
+
+        let out = run("select id from regs where value = 1 order by id");
+        assert_eq!(*out.borrow().col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 9]);
+
+        let out = run("select cast(value * 3 as Int) as v, id::String as s from regs where id < 5 order by id");
+        assert_eq!(*out.borrow().col_at("v").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![0, 1, 3, 4]);
+        assert_eq!(*out.borrow().col_at("s").downcast_data_ref::<DBString>().unwrap().data_ref(), vec!["1", "2", "3", "4"]);
+
+        let out = run("select sum(cast(cast(id as String) as Float)) as s from regs");
+        assert_eq!(*out.borrow().col_at("s").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![55.]);
+
+        //x::type is CAST(x AS type), also chained and inside aggregates
+        let out = run("select '12'::Int + id as n, id::String::Float as f, sum(value::Int) as v from regs where value::Int = 0 group by id order by id");
+        assert_eq!(*out.borrow().col_at("n").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![13, 14, 19, 20]);
+        assert_eq!(*out.borrow().col_at("f").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![1., 2., 7., 8.]);
+        assert_eq!(*out.borrow().col_at("v").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![0; 4]);
+        assert!(Plan::from_sql(&db, "select id::Undefined from regs").is_err());
+
+        let mut plan = Plan::from_sql(&db, "select cast(gender as Int) from regs").unwrap();
+        assert_eq!(plan.execute().unwrap_err(), "Can't cast 'Male' to Int");
+        assert!(Plan::from_sql(&db, "select cast(id as Undefined) from regs").is_err());
+        cleanup_test_table("cast_db");
+    }
+
+    #[test]
+    fn bool_filters()
+    {
+        cleanup_test_table("bool_db");
+        let db = create_test_db("bool_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+
+        let out = run("select id, id > 8 as big from regs where id > 6 and not id = 8 order by id");
+        assert_eq!(*out.borrow().col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![7, 9, 10]);
+        assert_eq!(*out.borrow().col_at("big").downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![false, true, true]);
+
+        let out = run("select count(*) as c from regs where cast(id % 2 as Bool) or false");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5]);
+
+        let out = run("select cast(id < 3 as Int) as i, cast(id < 3 as String) as s from regs where id <= 3 order by id");
+        assert_eq!(*out.borrow().col_at("i").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 1, 0]);
+        assert_eq!(*out.borrow().col_at("s").downcast_data_ref::<DBString>().unwrap().data_ref(), vec!["true", "true", "false"]);
+
+        assert!(Plan::from_sql(&db, "select id from regs where id % 2").is_err());
+        assert!(Plan::from_sql(&db, "select gender from regs group by gender having sum(id)").is_err());
+        cleanup_test_table("bool_db");
+    }
+
+    #[test]
+    fn nulls()
+    {
+        cleanup_test_table("null_db");
+        let db = create_test_db("null_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+
+        //NULLs go last
+        let out = run("select id, if(id % 3 = 0, NULL, id) as n from regs where id <= 6 order by n, id");
+        assert_eq!(*out.borrow().col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 2, 4, 5, 3, 6]);
+        assert_eq!(out.borrow().col_at("n").type_name(), TypeName::DBInt.to_nullable());
+        assert_eq!(*out.borrow().col_at("n").nulls().unwrap(), vec![false, false, false, false, true, true]);
+
+        let out = run("select id + if(id > 1, NULL, 1) as n, coalesce(if(id > 2, NULL, id), 0) as c from regs where id < 5 order by id");
+        assert_eq!(*out.borrow().col_at("n").nulls().unwrap(), vec![false, true, true, true]);
+        assert_eq!(out.borrow().col_at("c").type_name(), TypeName::DBInt);
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 2, 0, 0]);
+
+        let out = run("select count(*) as c from regs where if(id > 5, NULL, id) is null");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5]);
+
+        //NULL or true is true, NULL and false is false, NULL in WHERE is false
+        let out = run("select id from regs where if(id > 5, NULL, id > 2) or id = 10 order by id");
+        assert_eq!(*out.borrow().col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 4, 5, 10]);
+        let out = run("select count(*) as c from regs where not (if(id > 5, NULL, true) and false)");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![10]);
+        let out = run("select count(*) as c from regs where not if(id > 5, NULL, false)");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5]);
+
+        //Aggregate functions skip NULLs
+        let out = run("select sum(if(id % 2 = 0, NULL, id)) as s, count(if(id % 2 = 0, NULL, id)) as c, count(*) as a from regs");
+        assert_eq!(*out.borrow().col_at("s").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![25]);
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5]);
+        assert_eq!(*out.borrow().col_at("a").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![10]);
+        //Groups without values give NULL, count doesn't
+        let out = run("select gender, sum(if(id > 0, NULL, id)) as s, max(if(id % 2 = 0, NULL, id)) as m, count(if(id > 0, NULL, id)) as c from regs group by gender order by gender");
+        assert_eq!(out.borrow().col_at("s").type_name(), TypeName::DBInt.to_nullable());
+        assert_eq!(*out.borrow().col_at("s").nulls().unwrap(), vec![true, true]);
+        assert_eq!(*out.borrow().col_at("m").nulls().unwrap(), vec![true, false]);
+        assert_eq!(out.borrow().col_at("m").downcast_data_ref::<DBInt>().unwrap().data_ref()[1], 9);
+        assert_eq!(out.borrow().col_at("c").type_name(), TypeName::DBInt);
+        let out = run("select avg(if(id > 0, NULL, value)) as a from regs");
+        assert_eq!(*out.borrow().col_at("a").nulls().unwrap(), vec![true]);
+
+        //NULL literal fits any argument and gives NULL
+        let out = run("select id = NULL as e, id + NULL as p, not NULL as n, NULL and true as a, NULL or true as o from regs limit 1");
+        assert_eq!(out.borrow().col_at("e").type_name(), TypeName::DBBool.to_nullable());
+        assert_eq!(out.borrow().col_at("p").type_name(), TypeName::DBInt.to_nullable());
+        for name in ["e", "p", "n", "a"]
+        {
+            assert_eq!(*out.borrow().col_at(name).nulls().unwrap(), vec![true], "{}", name);
+        }
+        assert_eq!(*out.borrow().col_at("o").downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![true]);
+        assert_eq!(*out.borrow().col_at("o").nulls().unwrap(), vec![false]);
+        let out = run("select count(*) as c from regs where NULL or id = NULL");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![0]);
+        let out = run("select count(*) as c from regs where NULL");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![0]);
+        let out = run("select gender, count(*) as c from regs group by gender having NULL");
+        assert_eq!(out.borrow().rows_len(), 0);
+
+        let mut plan = Plan::from_sql(&db, "select cast(if(id > 5, NULL, id) as Int) from regs").unwrap();
+        assert!(plan.execute().is_err());
+        cleanup_test_table("null_db");
+    }
+
+    #[test]
+    fn dates()
+    {
+        cleanup_test_table("date_db");
+        let db = create_test_db("date_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+        let day = "cast(concat('2024-01-1', cast(id - 1 as String)) as Date)";
+
+        //Strings are compared with dates as dates
+        let out = run(&format!("select id from regs where {} >= '2024-01-14' and id < 10 order by id", day));
+        assert_eq!(*out.borrow().col_at("id").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5, 6, 7, 8, 9]);
+
+        let out = run(&format!("select max({}) as m, min(toStartOfMonth({})) as s from regs", day, day));
+        assert_eq!(out.borrow().col_at("m").type_name(), TypeName::DBDate.to_nullable());
+        assert_eq!(out.borrow().col_at("m").data_ref().to_string_at(0), "2024-01-19");
+        assert_eq!(out.borrow().col_at("s").data_ref().to_string_at(0), "2024-01-01");
+
+        let out = run("select dateDiff('day', DATE '2024-01-01', '2024-03-01') as d, toYear(DATE '2024-01-01') as y, \
+            toStartOfHour(cast('2024-01-01 10:30:00' as DateTime)) as h, now() > DATE '2024-01-01' as n from regs where id = 1");
+        assert_eq!(*out.borrow().col_at("d").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![60]);
+        assert_eq!(*out.borrow().col_at("y").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![2024]);
+        assert_eq!(out.borrow().col_at("h").data_ref().to_string_at(0), "2024-01-01 10:00:00");
+        assert_eq!(*out.borrow().col_at("n").downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![true]);
+
+        assert!(Plan::from_sql(&db, "select toYear(id) from regs").is_err());
+        let mut plan = Plan::from_sql(&db, "select cast('2024-13-01' as Date) from regs").unwrap();
+        assert!(plan.execute().is_err());
+        cleanup_test_table("date_db");
+    }
+
+    #[test]
+    fn narrow_integers()
+    {
+        cleanup_test_table("narrow_db");
+        let db = create_test_db("narrow_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+
+        let out = run("select cast(age as UInt8) + cast(id as Int16) as s, cast(age as UInt8) as a from regs where id < 3 order by id");
+        assert_eq!(out.borrow().col_at("s").type_name(), TypeName::DBInt);
+        assert_eq!(*out.borrow().col_at("s").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![12, 12]);
+        assert_eq!(*out.borrow().col_at("a").downcast_data_ref::<DBUInt8>().unwrap().data_ref(), vec![11, 10]);
+
+        //The sum is widened, so it doesn't overflow UInt8
+        let out = run("select sum(cast(id + 190 as UInt8)) as s, avg(cast(id as UInt32)) as a, max(cast(id as UInt16)) as m from regs");
+        assert_eq!(*out.borrow().col_at("s").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1955]);
+        assert_eq!(*out.borrow().col_at("a").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![5.5]);
+        assert_eq!(*out.borrow().col_at("m").downcast_data_ref::<DBUInt16>().unwrap().data_ref(), vec![10]);
+
+        let out = run("select count(*) as c from regs where cast(id as UInt8) in (1, 2, 3) and cast(age as Int8) > 5");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3]);
+
+        //UInt64 above the range of Int stays UInt64, its sum is Decimal(38, 0)
+        let big = "cast(9223372036854775807 as UInt64) + cast(id as UInt64)";
+        let out = run(&format!("select sum({b}) as s, count(*) as c, max(cast({b} as Float)) as f, min(cast(id as UInt64) - cast(age as UInt64)) as d \
+            from regs where {b} > cast(9223372036854775807 as UInt64)", b = big));
+        assert_eq!(out.borrow().col_at("s").type_name(), TypeName::DBDecimal(38, 0));
+        assert_eq!(out.borrow().col_at("s").data_ref().to_string_at(0), "92233720368547758125");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![10]);
+        assert_eq!(*out.borrow().col_at("f").downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![9223372036854775817.0]);
+        assert_eq!(*out.borrow().col_at("d").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![-10]);
+
+        let mut plan = Plan::from_sql(&db, "select cast(id * 100 as UInt8) from regs").unwrap();
+        assert!(plan.execute().is_err());
+        let mut plan = Plan::from_sql(&db, "select cast(9223372036854775807 as UInt64) * cast(3 as UInt64) from regs").unwrap();
+        assert_eq!(plan.execute(), Err("Integer overflow in 9223372036854775807 * 3".to_string()));
+        let mut plan = Plan::from_sql(&db, "select id + 9223372036854775807 from regs").unwrap();
+        assert_eq!(plan.execute(), Err("Integer overflow in 1 + 9223372036854775807".to_string()));
+        let mut plan = Plan::from_sql(&db, "select -9223372036854775807 - id from regs").unwrap();
+        assert_eq!(plan.execute(), Err("Integer overflow in -9223372036854775807 - 2".to_string()));
+        let out = run("select cast(18446744073709551615 as UInt64) as m from regs where id = 1");
+        assert_eq!(*out.borrow().col_at("m").downcast_data_ref::<DBUInt64>().unwrap().data_ref(), vec![u64::MAX]);
+        let mut plan = Plan::from_sql(&db, "select cast(100000000000000000000.5 as UInt64) from regs").unwrap();
+        assert_eq!(plan.execute(), Err("Can't cast 100000000000000000000 to UInt64: out of range".to_string()));
+        let mut plan = Plan::from_sql(&db, "select sum(id * 922337203685477580) from regs").unwrap();
+        assert_eq!(plan.execute(), Err("Integer overflow in sum".to_string()));
+        cleanup_test_table("narrow_db");
+    }
+
+    #[test]
+    fn decimals()
+    {
+        cleanup_test_table("decimal_db");
+        let db = create_test_db("decimal_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+        let strings = |out:&BlockRef, name:&str| {
+            let b = out.borrow();
+            let col = b.col_at(name);
+            (0..col.len()).map(|i| col.data_ref().to_string_at(i)).collect::<Vec<String>>()
+        };
+
+        let out = run("select cast(id as Decimal(10, 2)) / 4 + DECIMAL '0.125' as p from regs where id < 4 order by id");
+        assert_eq!(out.borrow().col_at("p").type_name(), TypeName::DBDecimal(12, 3));
+        assert_eq!(strings(&out, "p"), vec!["0.375", "0.625", "0.875"]);
+
+        //Exact sums, the quotients are truncated to the scale of the dividend
+        let out = run("select sum(cast(id as Decimal(10, 2)) / 3) as s, sum(DECIMAL '0.1') as t, avg(DECIMAL '0.10' * id) as a from regs");
+        assert_eq!(out.borrow().col_at("s").type_name(), TypeName::DBDecimal(38, 2));
+        assert_eq!(strings(&out, "s"), vec!["18.30"]);
+        assert_eq!(strings(&out, "t"), vec!["1.0"]);
+        assert_eq!(strings(&out, "a"), vec!["0.55"]);
+
+        let out = run("select count(*) as c from regs where cast(id as Decimal(5, 1)) / 2 >= DECIMAL '2.5'");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![6]);
+        let out = run("select count(*) as c from regs where cast(id as Decimal(5, 2)) between 2 and DECIMAL(3, 1) '3.5'");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![2]);
+
+        let out = run("select max(if(id > 5, DECIMAL '1.5', cast(id as Decimal(4, 0)))) as m, any(cast(id as Decimal(5, 1)) + value) as f from regs where id = 5");
+        assert_eq!(strings(&out, "m"), vec!["5.0"]);
+        assert_eq!(out.borrow().col_at("f").type_name(), TypeName::DBFloat.to_nullable());
+
+        //Fractional literals next to Decimal stay exact
+        let d = "cast(id as Decimal(10, 2))";
+        let out = run(&format!("select {d} * 1.1 as m, {d} + -0.05 as a, sum({d} * 0.2) as s from regs where {d} = 3.00 group by id", d = d));
+        assert_eq!(out.borrow().col_at("m").type_name(), TypeName::DBDecimal(12, 3));
+        assert_eq!(strings(&out, "m"), vec!["3.300"]);
+        assert_eq!(strings(&out, "a"), vec!["2.95"]);
+        assert_eq!(strings(&out, "s"), vec!["0.600"]);
+
+        let out = run("select cast(age as Decimal(6, 1)) / 4 as d from regs order by d limit 2");
+        assert_eq!(strings(&out, "d"), vec!["0.5", "0.7"]);
+
+        let mut plan = Plan::from_sql(&db, "select cast(id as Decimal(3, 2)) from regs").unwrap();
+        assert_eq!(plan.execute().err(), Some("10.00 is out of range of Decimal(3, 2)".to_string()));
+        assert!(Plan::from_sql(&db, "select DECIMAL(3, 1) '123.4' from regs").is_err());
+        cleanup_test_table("decimal_db");
+    }
+
+    #[test]
+    fn low_cardinality()
+    {
+        cleanup_test_table("low_cardinality_db");
+        let db = create_test_db("low_cardinality_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+        let strings = |out:&BlockRef, name:&str| {
+            let b = out.borrow();
+            let col = b.col_at(name);
+            (0..col.len()).map(|i| col.data_ref().to_string_at(i)).collect::<Vec<String>>()
+        };
+        let regs = "(select id, toLowCardinality(gender) as g, toLowCardinality(if(id < 4, 'low', 'high')) as h from regs)";
+
+        let out = run(&format!("select g, count(*) as c, max(id) as m from {} group by g order by g", regs));
+        assert_eq!(out.borrow().col_at("g").type_name(), TypeName::DBLowCardinality);
+        assert_eq!(strings(&out, "g"), vec!["Female", "Male"]);
+        assert_eq!(strings(&out, "c"), vec!["5", "5"]);
+        assert_eq!(strings(&out, "m"), vec!["10", "9"]);
+
+        let out = run(&format!("select g, h, count(*) as c from {} where g = 'Male' and h != 'high' group by g, h", regs));
+        assert_eq!(strings(&out, "h"), vec!["low"]);
+        assert_eq!(strings(&out, "c"), vec!["2"]);
+
+        //Functions which don't handle LowCardinality get Strings, comparing two LowCardinality columns uses their dictionaries
+        let out = run(&format!("select concat(g, '!') as e, g = h as same, cast(h as String) as s from {} where id < 3 order by id", regs));
+        assert_eq!(out.borrow().col_at("e").type_name(), TypeName::DBString);
+        assert_eq!(strings(&out, "e"), vec!["Male!", "Female!"]);
+        assert_eq!(strings(&out, "same"), vec!["false", "false"]);
+        assert_eq!(strings(&out, "s"), vec!["low", "low"]);
+
+        let out = run(&format!("select count(*) as c, max(g = 'Other') as o from {} where 'high' = h and g != 'Other'", regs));
+        assert_eq!(strings(&out, "c"), vec!["7"]);
+        assert_eq!(strings(&out, "o"), vec!["false"]);
+
+        let out = run(&format!("select distinct h from {} where g != 'Female' order by h desc", regs));
+        assert_eq!(strings(&out, "h"), vec!["low", "high"]);
+        assert!(Plan::from_sql(&db, &format!("select id from {} where g = 1", regs)).is_err());
+        cleanup_test_table("low_cardinality_db");
+    }
+
+    #[test]
+    fn arrays()
+    {
+        cleanup_test_table("arrays_db");
+        let db = create_test_db("arrays_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+        let strings = |out:&BlockRef, name:&str| {
+            let b = out.borrow();
+            let col = b.col_at(name);
+            (0..col.len()).map(|i| col.data_ref().to_string_at(i)).collect::<Vec<String>>()
+        };
+        let regs = "(select id, [id, id * 10] as a, if(id < 3, ['x', gender], []) as t from regs)";
+
+        let out = run(&format!("select a, length(a) as l, a[2] as second, arrayElement(a, -1) as last, a[3] as missing from {} where id < 3 order by id", regs));
+        assert_eq!(out.borrow().col_at("a").type_name(), TypeName::DBArray(Box::new(TypeName::DBInt)));
+        assert_eq!(strings(&out, "a"), vec!["[1, 10]", "[2, 20]"]);
+        assert_eq!(strings(&out, "l"), vec!["2", "2"]);
+        assert_eq!(strings(&out, "second"), vec!["10", "20"]);
+        assert_eq!(strings(&out, "last"), vec!["10", "20"]);
+        assert_eq!(strings(&out, "missing"), vec!["0", "0"]);
+
+        let out = run(&format!("select id from {} where has(t, 'Female') or has(a, 90)", regs));
+        assert_eq!(strings(&out, "id"), vec!["2", "9"]);
+
+        let out = run("select ['a', NULL] as n, [] as e, [[1], [2, 3]] as nested from regs limit 1");
+        assert_eq!(strings(&out, "n"), vec!["['a', NULL]"]);
+        assert_eq!(strings(&out, "e"), vec!["[]"]);
+        assert_eq!(strings(&out, "nested"), vec!["[[1], [2, 3]]"]);
+
+        //Rows are repeated for each element, empty arrays give no rows
+        let out = run(&format!("select id, arrayJoin(t) as tag from {} order by id, tag", regs));
+        assert_eq!(strings(&out, "id"), vec!["1", "1", "2", "2"]);
+        assert_eq!(strings(&out, "tag"), vec!["Male", "x", "Female", "x"]);
+
+        let out = run(&format!("select arrayJoin(t) as tag, count(*) as c from {} group by arrayJoin(t) order by tag", regs));
+        assert_eq!(strings(&out, "tag"), vec!["Female", "Male", "x"]);
+        assert_eq!(strings(&out, "c"), vec!["1", "1", "2"]);
+
+        let out = run("select gender, groupArray(id) as ids from regs where id < 6 group by gender order by gender");
+        assert_eq!(strings(&out, "ids"), vec!["[2, 4]", "[1, 3, 5]"]);
+
+        let out = run(&format!("select t, count(*) as c from {} group by t order by c", regs));
+        assert_eq!(strings(&out, "c"), vec!["1", "1", "8"]);
+        assert_eq!(strings(&out, "t")[2], "[]");
+
+        let out = run("select [1, 2][2] as a, [[1, 2], [3]][1][2] as b, ([id, age])[-1] as c from regs where id = 3");
+        assert_eq!(strings(&out, "a"), vec!["2"]);
+        assert_eq!(strings(&out, "b"), vec!["2"]);
+        assert_eq!(strings(&out, "c"), vec!["9"]);
+
+        let out = run("select gender, groupArray(id)[1] as first from regs group by gender order by gender");
+        assert_eq!(strings(&out, "first"), vec!["2", "1"]);
+
+        assert!(Plan::from_sql(&db, "select [1, 'a'] from regs").is_err());
+        assert!(Plan::from_sql(&db, "select arrayJoin(id) from regs").is_err());
+        assert!(Plan::from_sql(&db, &format!("select arrayJoin(a), arrayJoin(t) from {}", regs)).is_err());
+        cleanup_test_table("arrays_db");
+    }
+
+    #[test]
+    fn insert()
+    {
+        cleanup_test_table("insert_db");
+        let db = create_test_db("insert_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+        let strings = |out:&BlockRef, name:&str| {
+            let b = out.borrow();
+            let col = b.col_at(name);
+            (0..col.len()).map(|i| col.data_ref().to_string_at(i)).collect::<Vec<String>>()
+        };
+
+        let out = run("select * from (values (2, 'b'), (1, NULL), (3.5, 'c')) as v(n, s) order by n");
+        assert_eq!(strings(&out, "n"), vec!["1", "2", "3.5"]);
+        assert_eq!(strings(&out, "s"), vec!["NULL", "b", "c"]);
+
+        let out = run("insert into regs values (11, -1, 'Male', 1.5), (12, 0, 'Female', 2)");
+        assert_eq!(strings(&out, "inserted"), vec!["2"]);
+        //Omitted columns get the default values
+        run("insert into regs (gender, id) values ('Male', 13)");
+        let out = run("select id, age, gender, value from regs where id > 10 order by id");
+        assert_eq!(strings(&out, "id"), vec!["11", "12", "13"]);
+        assert_eq!(strings(&out, "age"), vec!["-1", "0", "0"]);
+        assert_eq!(strings(&out, "gender"), vec!["Male", "Female", "Male"]);
+        assert_eq!(strings(&out, "value"), vec!["1.5", "2", "0"]);
+
+        let out = run("insert into regs select id + 100, age, gender, value from regs where id <= 3");
+        assert_eq!(strings(&out, "inserted"), vec!["3"]);
+        let out = run("select count(*) as c, max(id) as m from regs");
+        assert_eq!(strings(&out, "c"), vec!["16"]);
+        assert_eq!(strings(&out, "m"), vec!["103"]);
+
+        let out = run("insert into regs select * from regs where id < 0");
+        assert_eq!(strings(&out, "inserted"), vec!["0"]);
+
+        for sql in [
+            "insert into regs values ('x', 1, 'Male', 1.0)",
+            "insert into regs values (1.5, 1, 'Male', 1.0)",
+            "insert into regs values (NULL, 1, 'Male', 1.0)",
+            "insert into regs values (1, 1, 'Male')",
+            "insert into regs (id, wrong) values (1, 2)",
+            "insert into regs (id, id) values (1, 2)",
+            "insert into undef values (1)",
+            "insert into regs values (1, 1, 'Male', 1.0), (2, 'x', 'Male', 1.0)",
+        ]
+        {
+            assert!(Plan::from_sql(&db, sql).is_err(), "{}", sql);
+        }
+        let out = run("select count(*) as c from regs");
+        assert_eq!(strings(&out, "c"), vec!["16"]);
+
+        //The chunks written before the query fails are cut off
+        let mut plan = Plan::from_sql(&db, "insert into regs (id, age) select id, intDiv(id, id - 9) from regs").unwrap();
+        assert!(plan.execute().is_err());
+        drop(plan);
+        run("insert into regs (id) values (20)");
+        let out = run("select count(*) as c, max(id) as m from regs");
+        assert_eq!(strings(&out, "c"), vec!["17"]);
+        assert_eq!(strings(&out, "m"), vec!["103"]);
+
+        //Each chunk of the query becomes a block of the table
+        let blocks = || table_size_iterator(db.get_table("regs").unwrap()).unwrap().count();
+        let before = blocks();
+        let out = run("insert into regs select * from regs");
+        assert_eq!(strings(&out, "inserted"), vec!["17"]);
+        assert_eq!(blocks(), 2 * before);
+
+        cleanup_test_table("insert_db");
+    }
+
+    #[test]
+    fn string_functions()
+    {
+        cleanup_test_table("string_db");
+        let db = create_test_db("string_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+        let queries = [
+            ("select upper(gender) as s from regs where id < 3 order by id", vec!["MALE", "FEMALE"]),
+            ("select concat(lower(gender), '-', substring(gender from 2 for 2)) as s from regs where id < 3 order by id", vec!["male-al", "female-em"]),
+            ("select trim(both 'F' from gender) as s from regs where id < 3 order by id", vec!["Male", "emale"]),
+            ("select replace(gender, 'ale', 'oo') as s from regs where id < 3 order by id", vec!["Moo", "Femoo"]),
+            ("select splitByChar('a', gender, 2) as s from regs where id < 3 order by id", vec!["le", "le"]),
+            ("select substr(gender, 3) as s from regs where id < 3 order by id", vec!["le", "male"]),
+        ];
+        for (sql, expected) in queries.iter()
+        {
+            let out = run(sql);
+            assert_eq!(*out.borrow().col_at("s").downcast_data_ref::<DBString>().unwrap().data_ref(), *expected, "{}", sql);
+        }
+
+        let out = run("select length(gender) as l, position(gender, 'ale') as p from regs where startsWith(gender, 'F') and id < 5 order by id");
+        assert_eq!(*out.borrow().col_at("l").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![6, 6]);
+        assert_eq!(*out.borrow().col_at("p").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![4, 4]);
+
+        let out = run("select count(*) as c from regs where ends_with(gender, 'male')");
+        assert_eq!(*out.borrow().col_at("c").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![5]);
+
+        assert!(Plan::from_sql(&db, "select length(id) from regs").is_err());
+        assert!(Plan::from_sql(&db, "select upper(gender, gender) from regs").is_err());
+        cleanup_test_table("string_db");
+    }
+
+    #[test]
+    fn math_functions()
+    {
+        cleanup_test_table("math_db");
+        let db = create_test_db("math_db", 10);
+        let run = |sql:&str| {
+            let mut plan = Plan::from_sql(&db, sql).unwrap();
+            plan.execute().unwrap();
+            plan.output()
+        };
+        let queries = [
+            ("select id % 3 as r from regs where id < 5 order by id", vec![1, 2, 0, 1]),
+            ("select intDiv(id, 3) as r from regs where id < 5 order by id", vec![0, 0, 1, 1]),
+            ("select id & 6 | 1 as r from regs where id < 5 order by id", vec![1, 3, 3, 5]),
+            ("select id ^ 1 as r from regs where id < 5 order by id", vec![0, 3, 2, 5]),
+            ("select -id + +2 as r from regs where id < 5 order by id", vec![1, 0, -1, -2]),
+            ("select abs(age - 8) as r from regs where id < 5 order by id", vec![3, 2, 1, 0]),
+            ("select greatest(id, age, 7) as r from regs where id < 5 order by id", vec![11, 10, 9, 8]),
+            ("select least(id, age) as r from regs where id > 5 order by id", vec![6, 5, 4, 3, 2]),
+            ("select round(id * 10, -2) as r from regs where id > 3 and id < 7 order by id", vec![0, 100, 100]),
+        ];
+        for (sql, expected) in queries.iter()
+        {
+            let out = run(sql);
+            assert_eq!(*out.borrow().col_at("r").downcast_data_ref::<DBInt>().unwrap().data_ref(), *expected, "{}", sql);
+        }
+
+        let queries = [
+            ("select sqrt(id * id) as r from regs where id < 4 order by id", vec![1., 2., 3.]),
+            ("select pow(2, id) as r from regs where id < 4 order by id", vec![2., 4., 8.]),
+            ("select round(value / 3, 2) as r from regs where id < 4 order by id", vec![0., 0.17, 0.33]),
+            ("select floor(value) + ceil(value) as r from regs where id < 4 order by id", vec![0., 1., 2.]),
+            ("select round(ln(exp(id)) + log10(100), 6) as r from regs where id < 4 order by id", vec![3., 4., 5.]),
+            ("select value % 1 as r from regs where id < 4 order by id", vec![0., 0.5, 0.]),
+        ];
+        for (sql, expected) in queries.iter()
+        {
+            let out = run(sql);
+            assert_eq!(*out.borrow().col_at("r").downcast_data_ref::<DBFloat>().unwrap().data_ref(), *expected, "{}", sql);
+        }
+
+        let mut plan = Plan::from_sql(&db, "select id / (id - 3) from regs").unwrap();
+        assert_eq!(plan.execute(), Err("Division by zero".to_string()));
+        let mut plan = Plan::from_sql(&db, "select id % (id - id) from regs").unwrap();
+        assert_eq!(plan.execute(), Err("Division by zero".to_string()));
+        let out = run("select id / 0.0 as r from regs where id = 1");
+        assert!(out.borrow().col_at("r").downcast_data_ref::<DBFloat>().unwrap().data_ref()[0].is_infinite());
+
+        assert!(Plan::from_sql(&db, "select value & 1 from regs").is_err());
+        assert!(Plan::from_sql(&db, "select -gender from regs").is_err());
+        assert!(Plan::from_sql(&db, "select greatest(id, gender) from regs").is_err());
+        cleanup_test_table("math_db");
+    }
+
+    struct Twice {}
+
+    impl RegFunction for Twice
+    {
+        fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+        {
+            let src_it = src[0].downcast_data_iter::<DBInt>().unwrap();
+            for (s, d) in src_it.zip(dest.downcast_data_iter_mut::<DBInt>().unwrap())
+            {
+                *d = s * 2;
+            }
+            Ok(())
+        }
+        fn to_string(&self, src:Vec<String>) -> String
+        {
+            format!("twice({})", src[0])
+        }
+    }
+
+    struct TwiceBuilder {}
+
+    impl RegFunctionBuilder for TwiceBuilder
+    {
+        fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+        {
+            match src[..] {
+                [TypeName::DBInt] => Ok(TypeName::DBInt),
+                _ => Err("twice expects Int".to_string())
+            }
+        }
+        fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+        {
+            self.result_type(src)?;
+            Ok(Box::new(Twice{}))
+        }
+    }
+
+    struct ProductColumn
+    {
+        data :Vec<i64>,
+    }
+
+    impl AggrColumn for ProductColumn
+    {
+        fn push_empty(&mut self) -> usize
+        {
+            self.data.push(1);
+            self.data.len()
+        }
+        fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
+        {
+            self.data[to] *= src[0].downcast_data_ref::<DBInt>().unwrap()[src_at];
+            Ok(())
+        }
+        fn finalize_to_column(&mut self, dest:&mut Column)
+        {
+            let col = dest.downcast_data_mut::<DBInt>().unwrap();
+            col.resize(0, 0);
+            col.append(&mut self.data);
+        }
+    }
+
+    struct ProductBuilder {}
+
+    impl AggrFunctionBuilder for ProductBuilder
+    {
+        fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+        {
+            match src[..] {
+                [TypeName::DBInt] => Ok(TypeName::DBInt),
+                _ => Err("product expects Int".to_string())
+            }
+        }
+        fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>
+        {
+            self.result_type(src)?;
+            Ok(Box::new(ProductColumn{data:Vec::new()}))
+        }
+    }
+
+    #[test]
+    fn user_functions()
+    {
+        cleanup_test_table("udf_db");
+        let mut db = create_test_db("udf_db", 10);
+        assert!(Plan::from_sql(&db, "select twice(id) from regs").is_err());
+
+        db.functions_mut().register_function("twice", Box::new(TwiceBuilder{})).unwrap();
+        db.functions_mut().register_aggregate_function("product", Box::new(ProductBuilder{})).unwrap();
+        assert!(db.functions_mut().register_function("Sum", Box::new(TwiceBuilder{})).is_err());
+
+        let mut plan = Plan::from_sql(&db, "select twice(id) + 1 as t from regs where id < 4 order by id").unwrap();
+        plan.execute().unwrap();
+        assert_eq!(*plan.output().borrow().col_at("t").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 5, 7]);
+
+        let mut plan = Plan::from_sql(&db, "select gender, PRODUCT(twice(id)) as p from regs where id < 5 group by gender order by gender").unwrap();
+        plan.execute().unwrap();
+        assert_eq!(*plan.output().borrow().col_at("p").downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![32, 12]);
+
+        assert!(Plan::from_sql(&db, "select twice(gender) from regs").is_err());
+        cleanup_test_table("udf_db");
+    }
+}
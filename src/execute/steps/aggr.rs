@@ -1,29 +1,142 @@
 use super::*;
 use crate::functions::aggregate::*;
+use crate::columns::Column;
+use crate::types::types::*;
+use crate::columns::low_cardinality::{LowCardinalityStorage, downcast_low_cardinality_mut};
+use crate::io::serialize::ByteSerialize;
+use std::collections::HashMap;
+use itertools::izip;
 
 struct AggrItem
 {
     aggr_col :AggrColumnRef,
-    srcs :Vec<usize>,
-    dest :usize
+    srcs :Vec<String>,
+    dest :String
 }
 
+//Groups are identified by the packed values of the group columns, so any column type can be a key.
+//LowCardinality group columns are packed as the codes in the dictionaries of the processor
 pub struct AggrProcessor
 {
-    group_inds :Vec<usize>,
-    items :Vec<AggrItem>
+    filter_col_name :Option<String>,
+    group_cols :Vec<String>,
+    group_dicts :Vec<LowCardinalityStorage>,
+    items :Vec<AggrItem>,
+    groups :HashMap<Vec<u8>, usize>,
+    keys :Vec<Vec<u8>>,
 }
 
 impl AggrProcessor
 {
-    pub fn new(group_inds:Vec<usize>) -> Self
+    pub fn new(filter_col_name:Option<String>, group_cols:Vec<String>) -> Self
     {
-        Self{group_inds, items:Vec::new()}
+        let group_dicts = group_cols.iter().map(|_| LowCardinalityStorage::new()).collect();
+        Self{filter_col_name, group_cols, group_dicts, items:Vec::new(), groups:HashMap::new(), keys:Vec::new()}
     }
 
-    pub fn add_item(&mut self, aggr_col:AggrColumnRef, srcs:Vec<usize>, dest:usize)
+    pub fn add_item(&mut self, aggr_col:AggrColumnRef, srcs:Vec<String>, dest:String)
     {
         self.items.push(AggrItem{aggr_col, srcs, dest})
     }
+
+    pub fn into_ref(self) -> Rc<RefCell<Self>>
+    {
+        Rc::new(RefCell::new(self))
+    }
+
+    fn push_group(&mut self, key:&[u8]) -> usize
+    {
+        let group = self.keys.len();
+        for itm in self.items.iter_mut()
+        {
+            itm.aggr_col.push_empty();
+        }
+        self.groups.insert(key.to_vec(), group);
+        self.keys.push(key.to_vec());
+        group
+    }
 }
 
+impl Processor for AggrProcessor
+{
+    fn run(&mut self, input :BlockRef, _output :BlockRef) -> DBResult<ProcessStatus>
+    {
+        let input = input.borrow();
+        let filter = self.filter_col_name.as_ref().map(
+            |name| input.col_at(name).downcast_data_ref::<DBBool>().unwrap()
+        );
+        let group_cols:Vec<&Column> = self.group_cols.iter().map(|name| input.col_at(name)).collect();
+        //Codes of the block in the dictionaries of the processor
+        let group_codes:Vec<Option<(&LowCardinalityStorage, Vec<u32>)>> = izip!(group_cols.iter(), self.group_dicts.iter_mut()).map(
+            |(col, dict)| col.data_ref().as_any().downcast_ref::<LowCardinalityStorage>().map(|lc| (lc, lc.translation_to(dict)))
+        ).collect();
+        let items_srcs:Vec<Vec<&Column>> = self.items.iter().map(
+            |itm| itm.srcs.iter().map(|name| input.col_at(name)).collect()
+        ).collect();
+
+        let mut key = Vec::<u8>::new();
+        for row in 0..input.rows_len()
+        {
+            if let Some(flt) = filter
+            {
+                if !flt[row] {continue;}
+            }
+            key.clear();
+            for (col, codes) in izip!(group_cols.iter(), group_codes.iter())
+            {
+                match codes {
+                    Some((lc, codes)) => codes[lc.codes()[row] as usize].to_byte(&mut key).unwrap(),
+                    None => col.pack_value_to(row, &mut key)
+                }
+            }
+            let group = match self.groups.get(&key) {
+                Some(g) => *g,
+                None => self.push_group(&key)
+            };
+            for (itm, srcs) in izip!(self.items.iter_mut(), items_srcs.iter())
+            {
+                //Aggregate functions skip NULLs
+                if srcs.iter().any(|col| col.is_null(row)) {continue;}
+                itm.aggr_col.append_value(srcs.clone(), row, group)?;
+            }
+        }
+
+        Ok(ProcessStatus::MustGoOn)
+    }
+}
+
+impl PostProcessor for AggrProcessor
+{
+    fn run(&mut self, output :BlockRef) -> DBResult<()>
+    {
+        //Aggregation without GROUP BY always returns a single row, even for empty input
+        if self.group_cols.is_empty() && self.keys.is_empty()
+        {
+            self.push_group(&[]);
+        }
+        let mut out = output.borrow_mut();
+        out.resize(self.keys.len());
+        for (row, key) in self.keys.iter().enumerate()
+        {
+            let mut src = key.as_slice();
+            for (name, dict) in izip!(self.group_cols.iter(), self.group_dicts.iter())
+            {
+                let col = out.col_at_mut(name);
+                if !col.data_ref().as_any().is::<LowCardinalityStorage>()
+                {
+                    col.unpack_value_from(row, &mut src);
+                    continue;
+                }
+                let mut code:u32 = 0;
+                code.from_byte(&mut src).unwrap();
+                downcast_low_cardinality_mut(col.data_mut().as_mut()).unwrap().set_value(row, &dict.dict()[code as usize]);
+            }
+        }
+        for itm in self.items.iter_mut()
+        {
+            itm.aggr_col.finalize_to_column(out.col_at_mut(&itm.dest));
+        }
+        //Calculate expressions over group keys and aggregate results
+        out.process(self.keys.len())
+    }
+}
@@ -0,0 +1,89 @@
+use super::*;
+use crate::columns::array::downcast_array_ref;
+
+//Blocks shared between ArrayJoinProcessor and the sources of the columns
+#[derive(Clone)]
+pub struct ArrayJoinState
+{
+    pub inner_block :BlockRef,
+    //For each output row: the row of the inner block and the position of the element in the values of the array
+    pub rows :Rc<RefCell<Vec<usize>>>,
+    pub elems :Rc<RefCell<Vec<usize>>>,
+}
+
+impl ArrayJoinState
+{
+    pub fn new() -> Self
+    {
+        Self{
+            inner_block:Rc::new(RefCell::new(ColumnBlock::new())),
+            rows:Rc::new(RefCell::new(Vec::new())),
+            elems:Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+}
+
+impl Default for ArrayJoinState
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+//Each chunk of the inner relation fills the input block with its rows repeated for each element of the array,
+//the rows with empty or NULL arrays are dropped
+pub struct ArrayJoinProcessor
+{
+    driver :ProcessorRef,
+    state :ArrayJoinState,
+    array_col :String,
+    unused :BlockRef,
+}
+
+impl ArrayJoinProcessor
+{
+    pub fn new(driver:ProcessorRef, state:ArrayJoinState, array_col:&str) -> Self
+    {
+        Self{driver, state, array_col:array_col.to_string(), unused:Rc::new(RefCell::new(ColumnBlock::new()))}
+    }
+    pub fn new_ref(driver:ProcessorRef, state:ArrayJoinState, array_col:&str) -> ProcessorRef
+    {
+        Rc::new(
+            RefCell::new(
+                Self::new(driver, state, array_col)
+            )
+        )
+    }
+}
+
+impl Processor for ArrayJoinProcessor
+{
+    fn run(&mut self, input :BlockRef, _output :BlockRef) -> DBResult<ProcessStatus>
+    {
+        if let ProcessStatus::MustStop = self.driver.borrow_mut().run(self.state.inner_block.clone(), self.unused.clone())?
+        {
+            return Ok(ProcessStatus::MustStop);
+        }
+        let rows = {
+            let inner = self.state.inner_block.borrow();
+            let col = inner.col_at(&self.array_col);
+            let arr = downcast_array_ref(col.data_ref().as_ref()).unwrap();
+            let mut rows = self.state.rows.borrow_mut();
+            let mut elems = self.state.elems.borrow_mut();
+            rows.clear();
+            elems.clear();
+            for row in (0..col.len()).filter(|row| !col.is_null(*row))
+            {
+                for i in arr.row_range(row)
+                {
+                    rows.push(row);
+                    elems.push(i);
+                }
+            }
+            rows.len()
+        };
+        input.borrow_mut().process(rows)?;
+        Ok(ProcessStatus::MustGoOn)
+    }
+}
@@ -0,0 +1,134 @@
+use super::*;
+use crate::columns::Column;
+use crate::types::types::*;
+use std::collections::HashSet;
+
+//Rows are compared by the packed values of the key columns, so any column types can be deduplicated
+pub struct DistinctProcessor
+{
+    filter_col_name:Option<String>,
+    key_cols:Vec<String>,
+    max_rows:Option<usize>,
+    keys:HashSet<Vec<u8>>,
+}
+
+impl DistinctProcessor
+{
+    //max_rows stops reading as soon as enough unique rows are collected
+    pub fn new(filter_col_name:Option<String>, key_cols:Vec<String>, max_rows:Option<usize>) -> Self
+    {
+        Self{filter_col_name, key_cols, max_rows, keys:HashSet::new()}
+    }
+    pub fn new_ref(filter_col_name:Option<String>, key_cols:Vec<String>, max_rows:Option<usize>) -> Rc<RefCell<Self>>
+    {
+        Rc::new(
+            RefCell::new(
+                Self::new(filter_col_name, key_cols, max_rows)
+            )
+        )
+    }
+}
+
+impl Processor for DistinctProcessor
+{
+    fn run(&mut self, input :BlockRef, output :BlockRef) -> DBResult<ProcessStatus>
+    {
+        if let Some(max_rows) = self.max_rows
+        {
+            if self.keys.len() >= max_rows
+            {
+                return Ok(ProcessStatus::MustStop);
+            }
+        }
+
+        let input = input.borrow();
+        let filter = self.filter_col_name.as_ref().map(
+            |name| input.col_at(name).downcast_data_ref::<DBBool>().unwrap()
+        );
+        let key_cols:Vec<&Column> = self.key_cols.iter().map(|name| input.col_at(name)).collect();
+
+        let mut new_keys = Vec::<Vec<u8>>::new();
+        let mut key = Vec::<u8>::new();
+        for row in 0..input.rows_len()
+        {
+            if let Some(flt) = filter
+            {
+                if !flt[row] {continue;}
+            }
+            if let Some(max_rows) = self.max_rows
+            {
+                if self.keys.len() >= max_rows {break;}
+            }
+            key.clear();
+            for col in key_cols.iter()
+            {
+                col.pack_value_to(row, &mut key);
+            }
+            if !self.keys.contains(&key)
+            {
+                self.keys.insert(key.clone());
+                new_keys.push(key.clone());
+            }
+        }
+
+        let mut out = output.borrow_mut();
+        let offset = out.rows_len();
+        out.resize(offset + new_keys.len());
+        for (i, key) in new_keys.iter().enumerate()
+        {
+            let mut src = key.as_slice();
+            for name in self.key_cols.iter()
+            {
+                out.col_at_mut(name).unpack_value_from(offset + i, &mut src);
+            }
+        }
+
+        Ok(ProcessStatus::MustGoOn)
+    }
+}
+
+//Removes duplicated rows from the already filled output, the first occurrence is kept
+pub struct DistinctPostProcessor
+{
+    key_cols:Vec<String>,
+}
+
+impl DistinctPostProcessor
+{
+    pub fn new(key_cols:Vec<String>) -> Self
+    {
+        Self{key_cols}
+    }
+    pub fn new_ref(key_cols:Vec<String>) -> Rc<RefCell<Self>>
+    {
+        Rc::new(
+            RefCell::new(
+                Self::new(key_cols)
+            )
+        )
+    }
+}
+
+impl PostProcessor for DistinctPostProcessor
+{
+    fn run(&mut self, output :BlockRef) -> DBResult<()>
+    {
+        let mut out = output.borrow_mut();
+        let mut keys = HashSet::<Vec<u8>>::new();
+        let mut perms = Vec::<usize>::new();
+        for row in 0..out.rows_len()
+        {
+            let mut key = Vec::<u8>::new();
+            for name in self.key_cols.iter()
+            {
+                out.col_at(name).pack_value_to(row, &mut key);
+            }
+            if keys.insert(key)
+            {
+                perms.push(row);
+            }
+        }
+        out.permute(&perms);
+        Ok(())
+    }
+}
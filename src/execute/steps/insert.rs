@@ -0,0 +1,150 @@
+use super::*;
+use crate::db::table::Table;
+use crate::columns::Column;
+use crate::columns::header::ColumnHeader;
+use crate::functions::regular::cast::cast_to;
+use crate::io::column::{make_col_writer, ColWriterPtr};
+use crate::io::db::append_table_sizes;
+use crate::types::types::DBInt;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use itertools::izip;
+
+//Column file of the table open for appending
+struct ColumnFile
+{
+    file :File,
+    //Length before the INSERT, the file is truncated back if it fails
+    start_len :u64,
+    writer :ColWriterPtr,
+}
+
+impl ColumnFile
+{
+    fn open(table:&Table, header:&ColumnHeader) -> std::io::Result<Self>
+    {
+        let file = OpenOptions::new().create(true).append(true).open(table.col_path(header.name()).unwrap())?;
+        let start_len = file.metadata()?.len();
+        let writer = make_col_writer(header.type_name(), file.try_clone()?);
+        Ok(Self{file, start_len, writer})
+    }
+}
+
+//Appends each chunk of the input to the column files of the table as a new block. The sizes of the blocks are added to
+//_sizes.bin after all of them are written, so readers never see torn blocks. The output gets the number of inserted rows
+pub struct InsertProcessor
+{
+    table :Table,
+    //Input columns of the table columns in the order of the schema, the omitted ones get the default value (NULL for Nullable)
+    sources :Vec<Option<String>>,
+    files :Vec<ColumnFile>,
+    sizes :Vec<u32>,
+    //Whether the blocks are added to _sizes.bin, otherwise the written chunks are cut off on drop
+    committed :bool,
+}
+
+impl InsertProcessor
+{
+    pub fn new(table:Table, sources:HashMap<String, String>) -> Self
+    {
+        let sources = table.schema().headers_ref().iter().map(|h| sources.get(h.name()).cloned()).collect();
+        Self{table, sources, files:Vec::new(), sizes:Vec::new(), committed:false}
+    }
+    pub fn new_ref(table:Table, sources:HashMap<String, String>) -> Rc<RefCell<Self>>
+    {
+        Rc::new(
+            RefCell::new(
+                Self::new(table, sources)
+            )
+        )
+    }
+
+    fn write_chunk(&mut self, input:&ColumnBlock) -> DBResult<()>
+    {
+        let rows = input.rows_len();
+        if rows == 0
+        {
+            return Ok(());
+        }
+        let size = u32::try_from(rows).map_err(|_| format!("Chunk of {} rows is too large to insert", rows))?;
+        if self.files.is_empty()
+        {
+            for h in self.table.schema().headers_ref().iter()
+            {
+                self.files.push(ColumnFile::open(&self.table, h).map_err(|e| e.to_string())?);
+            }
+        }
+        for (h, src, file) in izip!(self.table.schema().headers_ref().iter(), self.sources.iter(), self.files.iter_mut())
+        {
+            let mut col = Column::new(h.clone());
+            col.resize(rows);
+            match src {
+                Some(name) => cast_to(input.col_at(name), &mut col).map_err(|e| format!("Column {}: {}", h.name(), e))?,
+                None => if let Some(nulls) = col.nulls_mut()
+                {
+                    nulls.fill(true);
+                }
+            }
+            file.writer.write_col(col.data_ref()).map_err(|e| e.to_string())?;
+        }
+        self.sizes.push(size);
+        Ok(())
+    }
+
+    fn commit(&mut self) -> std::io::Result<()>
+    {
+        for f in self.files.iter()
+        {
+            f.file.sync_all()?;
+        }
+        append_table_sizes(&self.table, &self.sizes)?;
+        self.committed = true;
+        Ok(())
+    }
+
+    //The written chunks aren't referenced by _sizes.bin yet, they are cut off so the next INSERT starts at a block boundary
+    fn rollback(&mut self)
+    {
+        for f in self.files.iter()
+        {
+            f.file.set_len(f.start_len).unwrap_or_default();
+        }
+        self.sizes.clear();
+    }
+}
+
+impl Processor for InsertProcessor
+{
+    fn run(&mut self, input:BlockRef, _output:BlockRef) -> DBResult<ProcessStatus>
+    {
+        self.write_chunk(&input.borrow()).map(|_| ProcessStatus::MustGoOn)
+    }
+}
+
+impl PostProcessor for InsertProcessor
+{
+    fn run(&mut self, output:BlockRef) -> DBResult<()>
+    {
+        if !self.sizes.is_empty()
+        {
+            self.commit().map_err(|e| e.to_string())?;
+        }
+        let rows:i64 = self.sizes.iter().map(|s| *s as i64).sum();
+        let mut out = output.borrow_mut();
+        out.resize(1);
+        out.col_at_mut("inserted").downcast_data_mut::<DBInt>().unwrap()[0] = rows;
+        Ok(())
+    }
+}
+
+//The INSERT fails if any step does, not only the writing of a chunk, so the written chunks are cut off unless committed
+impl Drop for InsertProcessor
+{
+    fn drop(&mut self)
+    {
+        if !self.committed
+        {
+            self.rollback();
+        }
+    }
+}
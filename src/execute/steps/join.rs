@@ -0,0 +1,218 @@
+use super::*;
+use super::processor::FilteredAppendToOutputProcessor;
+use crate::blocks::source::DontTouchSource;
+use crate::columns::Column;
+use crate::columns::header::ColumnHeader;
+use crate::functions::regular::cast::cast_column;
+use crate::functions::regular::low_cardinality::materialize_low_cardinality;
+use crate::types::TypeName;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JoinKind
+{
+    Inner,
+    Left,
+}
+
+//Columns compared by JOIN ON and the type they are compared in, e.g. Int32 key joined with Int is cast to Int
+#[derive(Clone)]
+pub struct JoinKey
+{
+    pub left :String,
+    pub right :String,
+    pub type_name :TypeName,
+}
+
+//Blocks shared between JoinProcessor and the sources of the joined columns
+#[derive(Clone)]
+pub struct JoinState
+{
+    pub left_block :BlockRef,
+    pub right_block :BlockRef,
+    pub right_data :BlockRef,
+    pub left_rows :Rc<RefCell<Vec<usize>>>,
+    pub right_rows :Rc<RefCell<Vec<usize>>>,
+}
+
+impl JoinState
+{
+    pub fn new() -> Self
+    {
+        Self{
+            left_block:Rc::new(RefCell::new(ColumnBlock::new())),
+            right_block:Rc::new(RefCell::new(ColumnBlock::new())),
+            right_data:Rc::new(RefCell::new(ColumnBlock::new())),
+            left_rows:Rc::new(RefCell::new(Vec::new())),
+            right_rows:Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+}
+
+//Hash join: the right side is read entirely and hashed by the packed key columns,
+//then chunks of the left side are probed against it.
+//Each probed chunk fills the input block with the matched rows of both sides
+pub struct JoinProcessor
+{
+    kind :JoinKind,
+    left_driver :ProcessorRef,
+    right_driver :ProcessorRef,
+    state :JoinState,
+    keys :Vec<JoinKey>,
+    hash :Option<HashMap<Vec<u8>, Vec<usize>>>,
+    unused :BlockRef,
+}
+
+impl JoinProcessor
+{
+    pub fn new(kind:JoinKind, left_driver:ProcessorRef, right_driver:ProcessorRef, state:JoinState, keys:Vec<JoinKey>) -> Self
+    {
+        Self{
+            kind, left_driver, right_driver, state, keys,
+            hash:None,
+            unused:Rc::new(RefCell::new(ColumnBlock::new()))
+        }
+    }
+    pub fn new_ref(kind:JoinKind, left_driver:ProcessorRef, right_driver:ProcessorRef, state:JoinState, keys:Vec<JoinKey>) -> ProcessorRef
+    {
+        Rc::new(
+            RefCell::new(
+                Self::new(kind, left_driver, right_driver, state, keys)
+            )
+        )
+    }
+
+    fn build(&mut self) -> DBResult<()>
+    {
+        for (_, col) in self.state.right_block.borrow().col_iter()
+        {
+            self.state.right_data.borrow_mut().add(col.clone_empty(), DontTouchSource::new_ref());
+        }
+        let mut append = FilteredAppendToOutputProcessor::new(None, None, None);
+        while let ProcessStatus::MustGoOn = self.right_driver.borrow_mut().run(self.state.right_block.clone(), self.unused.clone())?
+        {
+            Processor::run(&mut append, self.state.right_block.clone(), self.state.right_data.clone())?;
+        }
+
+        let mut hash = HashMap::<Vec<u8>, Vec<usize>>::new();
+        let mut data = self.state.right_data.borrow_mut();
+        let rows = data.rows_len();
+        {
+            let casted = cast_keys(self.keys.iter().map(|k| (data.col_at(&k.right), &k.type_name)))?;
+            let key_cols:Vec<&Column> = self.keys.iter().zip(casted.iter()).map(|(k, c)| c.as_ref().unwrap_or(data.col_at(&k.right))).collect();
+            for row in 0..rows
+            {
+                let mut key = Vec::<u8>::new();
+                if pack_key(&key_cols, row, &mut key)
+                {
+                    hash.entry(key).or_default().push(row);
+                }
+            }
+        }
+        //The extra row is used for unmatched rows of LEFT JOIN, its values are NULL
+        data.resize(rows + 1);
+        if self.kind == JoinKind::Left
+        {
+            *data = nullable_block(&data);
+        }
+        self.hash = Some(hash);
+        Ok(())
+    }
+}
+
+//Key columns cast to the key type, None if the column has it already.
+//LowCardinality packs its strings, so it isn't cast to String
+fn cast_keys<'a>(cols:impl Iterator<Item = (&'a Column, &'a TypeName)>) -> DBResult<Vec<Option<Column>>>
+{
+    cols.map(
+        |(col, to)| {
+            if materialize_low_cardinality(col.type_name().base()) != *to {Some(cast_column(col, to.clone()))} else {None}
+        }.transpose()
+    ).collect()
+}
+
+//Packs the key values of the row, false if any of them is NULL: NULL key matches nothing.
+//The values of Nullable keys are packed without the null flag to match the keys of the other side
+fn pack_key(key_cols:&[&Column], row:usize, key:&mut Vec<u8>) -> bool
+{
+    for col in key_cols.iter()
+    {
+        if col.is_null(row)
+        {
+            return false;
+        }
+        match col.data_ref().inner() {
+            Some(values) => values.pack_value_to(row, key),
+            None => col.pack_value_to(row, key)
+        }
+    }
+    true
+}
+
+//The columns of the right side are Nullable in LEFT JOIN, the last row is NULL
+fn nullable_block(data:&ColumnBlock) -> ColumnBlock
+{
+    let mut res = ColumnBlock::new();
+    let rows = data.rows_len();
+    for h in data.headers()
+    {
+        let mut col = Column::new(ColumnHeader::new(h.name(), h.type_name().to_nullable()));
+        col.resize(rows);
+        data.col_at(h.name()).copy_to(&mut col, 0);
+        col.nulls_mut().unwrap()[rows - 1] = true;
+        res.add(col, DontTouchSource::new_ref());
+    }
+    res
+}
+
+impl Processor for JoinProcessor
+{
+    fn run(&mut self, input :BlockRef, _output :BlockRef) -> DBResult<ProcessStatus>
+    {
+        if self.hash.is_none()
+        {
+            self.build()?;
+        }
+        if let ProcessStatus::MustStop = self.left_driver.borrow_mut().run(self.state.left_block.clone(), self.unused.clone())?
+        {
+            return Ok(ProcessStatus::MustStop);
+        }
+
+        let rows = {
+            let left = self.state.left_block.borrow();
+            let hash = self.hash.as_ref().unwrap();
+            let default_row = self.state.right_data.borrow().rows_len() - 1;
+            let casted = cast_keys(self.keys.iter().map(|k| (left.col_at(&k.left), &k.type_name)))?;
+            let key_cols:Vec<&Column> = self.keys.iter().zip(casted.iter()).map(|(k, c)| c.as_ref().unwrap_or(left.col_at(&k.left))).collect();
+            let mut left_rows = self.state.left_rows.borrow_mut();
+            let mut right_rows = self.state.right_rows.borrow_mut();
+            left_rows.clear();
+            right_rows.clear();
+
+            let mut key = Vec::<u8>::new();
+            for row in 0..left.rows_len()
+            {
+                key.clear();
+                let matched = if pack_key(&key_cols, row, &mut key) {hash.get(&key)} else {None};
+                match matched {
+                    Some(matched) => {
+                        for r in matched.iter()
+                        {
+                            left_rows.push(row);
+                            right_rows.push(*r);
+                        }
+                    },
+                    None if self.kind == JoinKind::Left => {
+                        left_rows.push(row);
+                        right_rows.push(default_row);
+                    },
+                    None => {}
+                }
+            }
+            left_rows.len()
+        };
+
+        input.borrow_mut().process(rows)?;
+        Ok(ProcessStatus::MustGoOn)
+    }
+}
@@ -1,4 +1,9 @@
 pub mod processor;
+pub mod aggr;
+pub mod distinct;
+pub mod join;
+pub mod array_join;
+pub mod insert;
 use std::cell::RefCell;
 use std::rc::Rc;
 use crate::DBResult;
@@ -19,6 +24,11 @@ pub trait Processor
 pub trait PostProcessor
 {
     fn run(&mut self, output :BlockRef) -> DBResult<()>;
+    //Doesn't need all the rows of the output, so it can be taken chunk by chunk (see ExecuteStep::can_stream)
+    fn streams(&self) -> bool
+    {
+        false
+    }
 }
 
 pub type ProcessorRef = Rc<RefCell<dyn Processor>>;
@@ -64,20 +74,35 @@ impl ExecuteStep
 
     pub fn execute(&mut self) -> DBResult<()>
     {
-        let mut stopped = false;
-        while !stopped {
-            for p in self.processors.iter_mut()
-            {
-                match p.borrow_mut().run(self.input.clone(), self.output.clone())? {
-                    ProcessStatus::MustStop => {stopped = true; break},
-                    ProcessStatus::MustGoOn => {}
-                }
+        while self.run_chunk()? {}
+        self.finish()
+    }
+
+    //Runs the processors on the next chunk, false if they are done
+    pub fn run_chunk(&mut self) -> DBResult<bool>
+    {
+        for p in self.processors.iter_mut()
+        {
+            match p.borrow_mut().run(self.input.clone(), self.output.clone())? {
+                ProcessStatus::MustStop => return Ok(false),
+                ProcessStatus::MustGoOn => {}
             }
         }
+        Ok(true)
+    }
+
+    pub fn finish(&mut self) -> DBResult<()>
+    {
         for p in self.post_processors.iter_mut()
         {
             p.borrow_mut().run(self.output.clone())?;
         }
         Ok(())
     }
+
+    //The rows appended to the output by each chunk are final, so the output may be cleared between the chunks
+    pub fn can_stream(&self) -> bool
+    {
+        self.post_processors.iter().all(|p| p.borrow().streams())
+    }
 }
\ No newline at end of file
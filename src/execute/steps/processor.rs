@@ -1,6 +1,7 @@
 use super::*;
 use crate::types::types::*;
 use std::cmp::Ordering;
+use std::cell::Cell;
 
 pub struct ChunkedProcessor<Iter:Iterator<Item = u32>>
 {
@@ -38,6 +39,107 @@ impl<Iter:Iterator<Item = u32>> Processor for ChunkedProcessor<Iter>
     }
 }
 
+//Fills the input with all rows of the source block at once
+pub struct SingleChunkProcessor
+{
+    block:BlockRef,
+    done:bool
+}
+
+impl SingleChunkProcessor
+{
+    pub fn new(block:BlockRef) -> Self
+    {
+        Self{block, done:false}
+    }
+
+    pub fn new_ref(block:BlockRef) -> ProcessorRef
+    {
+        Rc::new(
+            RefCell::new(Self::new(block))
+        )
+    }
+}
+
+impl Processor for SingleChunkProcessor
+{
+    fn run(&mut self, input :BlockRef, _output :BlockRef) -> DBResult<ProcessStatus>
+    {
+        if self.done
+        {
+            return Ok(ProcessStatus::MustStop);
+        }
+        self.done = true;
+        let rows = self.block.borrow().rows_len();
+        input.borrow_mut().process(rows)?;
+        Ok(ProcessStatus::MustGoOn)
+    }
+}
+
+//Drives the last steps of the queries of UNION ALL one after another, the sources take columns of the output of
+//the current one. The rows of the steps which stream are passed on chunk by chunk, the others (e.g. with ORDER BY
+//or GROUP BY) are executed at once
+pub struct ConcatProcessor
+{
+    branches:Vec<ExecuteStep>,
+    current:Rc<Cell<usize>>,
+    //The current branch is done, the next run moves to the next one
+    exhausted:bool
+}
+
+impl ConcatProcessor
+{
+    pub fn new(branches:Vec<ExecuteStep>, current:Rc<Cell<usize>>) -> Self
+    {
+        Self{branches, current, exhausted:false}
+    }
+
+    pub fn new_ref(branches:Vec<ExecuteStep>, current:Rc<Cell<usize>>) -> ProcessorRef
+    {
+        Rc::new(
+            RefCell::new(Self::new(branches, current))
+        )
+    }
+}
+
+impl Processor for ConcatProcessor
+{
+    fn run(&mut self, input :BlockRef, _output :BlockRef) -> DBResult<ProcessStatus>
+    {
+        loop {
+            if self.exhausted
+            {
+                self.current.set(self.current.get() + 1);
+                self.exhausted = false;
+            }
+            let step = match self.branches.get_mut(self.current.get()) {
+                Some(step) => step,
+                None => return Ok(ProcessStatus::MustStop)
+            };
+            if step.can_stream()
+            {
+                step.output().borrow_mut().resize(0);
+                if !step.run_chunk()?
+                {
+                    step.finish()?;
+                    self.exhausted = true;
+                }
+            }
+            else
+            {
+                step.execute()?;
+                self.exhausted = true;
+            }
+            let rows = step.output().borrow().rows_len();
+            if rows > 0
+            {
+                input.borrow_mut().process(rows)?;
+                return Ok(ProcessStatus::MustGoOn);
+            }
+        }
+    }
+}
+
 pub struct FilteredAppendToOutputProcessor
 {
     filter_col_name:Option<String>,
@@ -84,7 +186,7 @@ impl Processor for FilteredAppendToOutputProcessor
            None => None
         };
         let add_size:i64 = match filter_col {
-            Some(col) => col.downcast_data_iter::<DBInt>().unwrap().sum(),
+            Some(col) => col.downcast_data_iter::<DBBool>().unwrap().filter(|flt| **flt).count() as i64,
             None => input.rows_len() as i64
         };
 
@@ -126,6 +228,10 @@ impl PostProcessor for FilteredAppendToOutputProcessor
         output.borrow_mut().fit_offset_limit(self.rest_offset.unwrap_or(0), self.limit);
         Ok(())
     }
+    fn streams(&self) -> bool
+    {
+        self.offset == 0 && self.limit.is_none()
+    }
 }
 
 pub struct OrderByPostProcessor
@@ -188,7 +294,77 @@ impl PostProcessor for OrderByPostProcessor
             None => perms.len()
         };
 
-        output.permute(&perms[self.offset..to]);
+        let from = std::cmp::min(self.offset, to);
+        output.permute(&perms[from..to]);
         Ok(())
     }
-}
\ No newline at end of file
+}
+pub struct OffsetLimitPostProcessor
+{
+    offset:usize,
+    limit:Option<usize>
+}
+
+impl OffsetLimitPostProcessor
+{
+    pub fn new(offset:Option<usize>, limit:Option<usize>) -> Self
+    {
+        Self{offset:offset.unwrap_or(0), limit}
+    }
+    pub fn new_ref(offset:Option<usize>, limit:Option<usize>) -> Rc<RefCell<Self>>
+    {
+        Rc::new(
+            RefCell::new(
+                Self::new(offset, limit)
+            )
+        )
+    }
+}
+
+impl PostProcessor for OffsetLimitPostProcessor
+{
+    fn run(&mut self, output :BlockRef) -> DBResult<()>
+    {
+        let mut out = output.borrow_mut();
+        let offset = std::cmp::min(self.offset, out.rows_len());
+        out.fit_offset_limit(offset, self.limit);
+        Ok(())
+    }
+}
+
+//Keeps only rows of the output for which the filter column is true
+pub struct FilterPostProcessor
+{
+    filter_col_name:String
+}
+
+impl FilterPostProcessor
+{
+    pub fn new(filter_col_name:String) -> Self
+    {
+        Self{filter_col_name}
+    }
+    pub fn new_ref(filter_col_name:String) -> Rc<RefCell<Self>>
+    {
+        Rc::new(
+            RefCell::new(
+                Self::new(filter_col_name)
+            )
+        )
+    }
+}
+
+impl PostProcessor for FilterPostProcessor
+{
+    fn run(&mut self, output :BlockRef) -> DBResult<()>
+    {
+        let mut out = output.borrow_mut();
+        let perms:Vec<usize> = out.col_at(&self.filter_col_name).downcast_data_iter::<DBBool>().unwrap()
+            .enumerate()
+            .filter(|(_, flt)| **flt)
+            .map(|(i, _)| i)
+            .collect();
+        out.permute(&perms);
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use super::*;
+use crate::types::TypeName;
+use crate::columns::data::make_storage;
+use crate::columns::array::downcast_array_mut;
+
+/* ======== GroupArray ========== */
+
+//Values of each group are kept packed one after another, NULLs are skipped by AggrProcessor
+pub struct GroupArrayAggrColumn
+{
+    elem :TypeName,
+    data :Vec<(usize, Vec<u8>)>,
+}
+
+impl GroupArrayAggrColumn
+{
+    pub fn new(elem:TypeName) -> Self
+    {
+        Self{elem, data:Vec::new()}
+    }
+    pub fn new_ref(elem:TypeName) -> AggrColumnRef
+    {
+        Box::new(Self::new(elem))
+    }
+}
+
+impl AggrColumn for GroupArrayAggrColumn
+{
+    fn push_empty(&mut self) -> usize
+    {
+        self.data.push((0, Vec::new()));
+        self.data.len()
+    }
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        //The values are packed without the null map of Nullable
+        let col = src[0].data_ref();
+        let values = if src[0].type_name().is_nullable() {col.inner().unwrap()} else {col};
+        let (count, packed) = &mut self.data[to];
+        values.pack_value_to(src_at, packed);
+        *count += 1;
+        Ok(())
+    }
+    fn finalize_to_column(&mut self, dest:&mut Column)
+    {
+        let mut values = make_storage(self.elem.clone());
+        values.resize(self.data.iter().map(|(count, _)| count).sum());
+        let mut at = 0;
+        for (count, packed) in self.data.iter()
+        {
+            let mut src = packed.as_slice();
+            for _ in 0..*count
+            {
+                values.unpack_value_from(at, &mut src);
+                at += 1;
+            }
+        }
+        let lens:Vec<usize> = self.data.drain(..).map(|(count, _)| count).collect();
+        dest.resize(lens.len());
+        downcast_array_mut(dest.data_mut().as_mut()).unwrap().replace_rows(0, &lens, &values);
+    }
+}
+
+//groupArray(x) collects the values of the group into Array(T)
+pub struct GroupArrayAggrBuilder {}
+
+impl GroupArrayAggrBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn AggrFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl Default for GroupArrayAggrBuilder
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl AggrFunctionBuilder for GroupArrayAggrBuilder
+{
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        match src.as_slice() {
+            [elem] => Ok(TypeName::DBArray(Box::new(elem.clone()))),
+            _ => Err("groupArray expects 1 argument".to_string())
+        }
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>
+    {
+        self.result_type(src.clone())?;
+        Ok(GroupArrayAggrColumn::new_ref(src[0].clone()))
+    }
+    fn nullable_result(&self) -> bool
+    {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+    use crate::types::types::*;
+
+    #[test]
+    fn group_array()
+    {
+        let mut src = Column::new(ColumnHeader::new("src", TypeName::DBString.to_nullable()));
+        src.resize(5);
+        for (i, s) in ["a", "b", "", "c", "d"].iter().enumerate()
+        {
+            src.downcast_data_mut::<DBString>().unwrap()[i] = s.to_string();
+        }
+        src.nulls_mut().unwrap()[2] = true;
+        let mut aggr = GroupArrayAggrBuilder::new().build(vec![TypeName::DBString]).unwrap();
+        for _ in 0..3
+        {
+            aggr.push_empty();
+        }
+        for i in (0..src.len()).filter(|i| !src.is_null(*i))
+        {
+            aggr.append_value(vec![&src], i, i % 2).unwrap();
+        }
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBArray(Box::new(TypeName::DBString))));
+        aggr.finalize_to_column(&mut dest);
+        let res:Vec<String> = (0..dest.len()).map(|i| dest.data_ref().to_string_at(i)).collect();
+        assert_eq!(res, vec!["['a', 'd']", "['b', 'c']", "[]"]);
+    }
+}
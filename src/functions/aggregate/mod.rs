@@ -1,14 +1,20 @@
 pub mod simple;
+pub mod uniq;
+pub mod array;
+pub mod null;
 
 use crate::DBResult;
 use crate::columns::Column;
 use crate::types::TypeName;
+use crate::functions::registry::FunctionRegistry;
 use simple::*;
+use uniq::*;
+use array::*;
 
 pub trait AggrColumn
 {
     fn push_empty(&mut self) -> usize;
-    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize);
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>;
     fn finalize_to_column(&mut self, dest:&mut Column);
 }
 
@@ -18,23 +24,30 @@ pub trait AggrFunctionBuilder
 {
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>;
     fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>;
+    //The result is NULL for the groups without values if any of the arguments is Nullable (see null::NullableAggrColumn),
+    //counting functions give their result for no values
+    fn nullable_result(&self) -> bool
+    {
+        true
+    }
+    //Aggregation without GROUP BY returns a row for empty input too, the result is NULL there
+    //for the functions which have no value without rows (min, max, avg...)
+    fn null_on_empty(&self) -> bool
+    {
+        false
+    }
 }
 
 pub type AggrFunctionBuilderRef = Box<dyn AggrFunctionBuilder>;
 
-pub fn is_aggregate_function(name:&str) -> bool
+pub fn register_functions(registry:&mut FunctionRegistry) -> DBResult<()>
 {
-    match name.to_lowercase().as_ref() {
-        "sum" | "any"  => true,
-        _ => false
-    }
+    registry.register_aggregate_function("sum", SumAggrBuilder::new_ref())?;
+    registry.register_aggregate_function("any", AnyAggrBuilder::new_ref())?;
+    registry.register_aggregate_function("count", CountAggrBuilder::new_ref())?;
+    registry.register_aggregate_function("min", MinAggrBuilder::new_ref())?;
+    registry.register_aggregate_function("max", MaxAggrBuilder::new_ref())?;
+    registry.register_aggregate_function("avg", AvgAggrBuilder::new_ref())?;
+    registry.register_aggregate_function("uniq", UniqAggrBuilder::new_ref())?;
+    registry.register_aggregate_function("groupArray", GroupArrayAggrBuilder::new_ref())
 }
-
-pub fn aggregate_function_builder(name:&str) -> Option<AggrFunctionBuilderRef>
-{
-    match name.to_lowercase().as_ref() {
-        "sum"  => Some(SumAggrBuilder::new_ref()),
-        "any"  => Some(AnyAggrBuilder::new_ref()),
-        _ => None
-    }
-}
\ No newline at end of file
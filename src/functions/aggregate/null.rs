@@ -0,0 +1,69 @@
+use super::*;
+use crate::columns::header::ColumnHeader;
+
+//Result of the function over Nullable arguments. AggrProcessor skips the rows with NULLs,
+//so the groups which got no values are NULL
+pub struct NullableAggrColumn
+{
+    inner :AggrColumnRef,
+    //Result type of the inner function
+    base :TypeName,
+    has_values :Vec<bool>,
+}
+
+impl NullableAggrColumn
+{
+    pub fn new_ref(inner:AggrColumnRef, base:TypeName) -> AggrColumnRef
+    {
+        Box::new(Self{inner, base, has_values:Vec::new()})
+    }
+}
+
+impl AggrColumn for NullableAggrColumn
+{
+    fn push_empty(&mut self) -> usize
+    {
+        self.has_values.push(false);
+        self.inner.push_empty()
+    }
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
+    {
+        self.has_values[to] = true;
+        self.inner.append_value(src, src_at, to)
+    }
+    fn finalize_to_column(&mut self, dest:&mut Column)
+    {
+        let mut res = Column::new(ColumnHeader::new(dest.name(), self.base.clone()));
+        self.inner.finalize_to_column(&mut res);
+        dest.resize(res.len());
+        res.copy_to(dest, 0);
+        *dest.nulls_mut().unwrap() = self.has_values.iter().map(|v| !*v).collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::types::*;
+
+    #[test]
+    fn nullable_sum()
+    {
+        let mut src = Column::new(ColumnHeader::new("src", TypeName::DBInt));
+        src.resize(2);
+        src.downcast_data_mut::<DBInt>().unwrap().data_mut().clone_from_slice(&[3, 4]);
+
+        let builder = SumAggrBuilder::new();
+        let mut sum = NullableAggrColumn::new_ref(builder.build(vec![TypeName::DBInt]).unwrap(), TypeName::DBInt);
+        for _ in 0..3
+        {
+            sum.push_empty();
+        }
+        sum.append_value(vec![&src], 0, 0).unwrap();
+        sum.append_value(vec![&src], 1, 2).unwrap();
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBInt.to_nullable()));
+        sum.finalize_to_column(&mut dest);
+        assert_eq!(*dest.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 0, 4]);
+        assert_eq!(*dest.nulls().unwrap(), vec![false, true, false]);
+    }
+}
@@ -1,7 +1,7 @@
 use super::*;
 use crate::types::{TypeName, DBType};
 use crate::types::types::*;
-use std::ops;
+use crate::types::decimal::{MAX_PRECISION, MAX_PRECISION_64};
 
 /* ======== Any ========== */
 
@@ -33,11 +33,12 @@ impl<T:DBType> AggrColumn for AnyAggrColumn<T>
         self.data.push(Default::default());
         self.data.len()
     }
-    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize)
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
     {
         assert_eq!(src.len(), 1);
         let col = src[0].downcast_data_ref::<T>().unwrap();
         self.data[to] = col[src_at].clone();
+        Ok(())
     }
     fn finalize_to_column(&mut self, dest:&mut Column)
     {
@@ -67,7 +68,7 @@ impl AggrFunctionBuilder for AnyAggrBuilder
         {
             return Err("any expects 1 argument".to_string());
         }
-        Ok(src[0])
+        Ok(src[0].clone())
 
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>
@@ -77,32 +78,79 @@ impl AggrFunctionBuilder for AnyAggrBuilder
             return Err("any expects 1 argument".to_string());
         }
 
-        match src[0]
+        match &src[0]
         {
             TypeName::DBInt => Ok(AnyAggrColumn::<DBInt>::new_ref()),
+            TypeName::DBInt8 => Ok(AnyAggrColumn::<DBInt8>::new_ref()),
+            TypeName::DBInt16 => Ok(AnyAggrColumn::<DBInt16>::new_ref()),
+            TypeName::DBInt32 => Ok(AnyAggrColumn::<DBInt32>::new_ref()),
+            TypeName::DBUInt8 => Ok(AnyAggrColumn::<DBUInt8>::new_ref()),
+            TypeName::DBUInt16 => Ok(AnyAggrColumn::<DBUInt16>::new_ref()),
+            TypeName::DBUInt32 => Ok(AnyAggrColumn::<DBUInt32>::new_ref()),
+            TypeName::DBUInt64 => Ok(AnyAggrColumn::<DBUInt64>::new_ref()),
             TypeName::DBFloat => Ok(AnyAggrColumn::<DBFloat>::new_ref()),
             TypeName::DBString => Ok(AnyAggrColumn::<DBString>::new_ref()),
+            TypeName::DBBool => Ok(AnyAggrColumn::<DBBool>::new_ref()),
+            TypeName::DBDate => Ok(AnyAggrColumn::<DBDate>::new_ref()),
+            TypeName::DBDateTime => Ok(AnyAggrColumn::<DBDateTime>::new_ref()),
+            TypeName::DBDecimal(precision, _) if *precision <= MAX_PRECISION_64 => Ok(AnyAggrColumn::<DBDecimal64>::new_ref()),
+            TypeName::DBDecimal(..) => Ok(AnyAggrColumn::<DBDecimal128>::new_ref()),
+            other => Err(format!("Wrong argument type {} for any", other))
         }
 
     }
+    fn null_on_empty(&self) -> bool
+    {
+        true
+    }
 }
 
 /* ========== Sum ============ */
 
-pub struct SumAggrColumn<T:DBType>
+//Integer sums return an error on overflow instead of panicking or wrapping around, Float follows IEEE 754
+pub trait CheckedSum: Sized
 {
-    data:Vec<T::InnerType>
+    fn checked_sum(self, r:Self) -> Option<Self>;
 }
 
-impl<T:DBType> SumAggrColumn<T>
-    where T::InnerType : ops::AddAssign<
-                T::InnerType
-                >,
-        T::InnerType : Clone + Default
+impl CheckedSum for i64
+{
+    fn checked_sum(self, r:i64) -> Option<i64>
+    {
+        self.checked_add(r)
+    }
+}
+
+impl CheckedSum for i128
+{
+    fn checked_sum(self, r:i128) -> Option<i128>
+    {
+        self.checked_add(r)
+    }
+}
+
+impl CheckedSum for f64
+{
+    fn checked_sum(self, r:f64) -> Option<f64>
+    {
+        Some(self + r)
+    }
+}
+
+//Values of S are summed as A, so the sum of the narrower integers doesn't overflow them
+pub struct SumAggrColumn<S:DBType, A:DBType = S>
+{
+    data:Vec<A::InnerType>,
+    _marker:std::marker::PhantomData<S>
+}
+
+impl<S:DBType, A:DBType> SumAggrColumn<S, A>
+    where A::InnerType : CheckedSum + Copy + Default,
+        S::InnerType : Clone + Into<A::InnerType>
 {
     pub fn new() -> Self
     {
-        Self{data:Vec::new()}
+        Self{data:Vec::new(), _marker:std::marker::PhantomData::<S>{}}
     }
     pub fn new_ref() -> AggrColumnRef
     {
@@ -110,11 +158,9 @@ impl<T:DBType> SumAggrColumn<T>
     }
 }
 
-impl<T:DBType> AggrColumn for SumAggrColumn<T>
-    where T::InnerType : ops::AddAssign<
-                T::InnerType
-                >,
-        T::InnerType : Clone + Default
+impl<S:DBType, A:DBType> AggrColumn for SumAggrColumn<S, A>
+    where A::InnerType : CheckedSum + Copy + Default,
+        S::InnerType : Clone + Into<A::InnerType>
 {
 
     fn push_empty(&mut self) -> usize
@@ -122,15 +168,16 @@ impl<T:DBType> AggrColumn for SumAggrColumn<T>
         self.data.push(Default::default());
         self.data.len()
     }
-    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize)
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
     {
         assert_eq!(src.len(), 1);
-        let col = src[0].downcast_data_ref::<T>().unwrap();
-        self.data[to] += col[src_at].clone();
+        let col = src[0].downcast_data_ref::<S>().unwrap();
+        self.data[to] = self.data[to].checked_sum(col[src_at].clone().into()).ok_or("Integer overflow in sum")?;
+        Ok(())
     }
     fn finalize_to_column(&mut self, dest:&mut Column)
     {
-        let col = dest.downcast_data_mut::<T>().unwrap();
+        let col = dest.downcast_data_mut::<A>().unwrap();
         col.resize(0, Default::default());
         col.append(&mut self.data);
     }
@@ -150,6 +197,8 @@ impl SumAggrBuilder {
 impl AggrFunctionBuilder for SumAggrBuilder
 {
 
+    //The sum of the narrower integers is Int. UInt64 is summed as Decimal(38, 0), so it doesn't overflow, and decimals
+    //exactly as Decimal(38, S)
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
         if src.len() != 1
@@ -157,9 +206,11 @@ impl AggrFunctionBuilder for SumAggrBuilder
             return Err("sum expects 1 argument".to_string());
         }
 
-        match src[0]
+        match &src[0]
         {
-            TypeName::DBInt => Ok(TypeName::DBInt),
+            TypeName::DBUInt64 => Ok(TypeName::DBDecimal(MAX_PRECISION, 0)),
+            TypeName::DBDecimal(_, scale) => Ok(TypeName::DBDecimal(MAX_PRECISION, *scale)),
+            t if t.is_integer() => Ok(TypeName::DBInt),
             TypeName::DBFloat => Ok(TypeName::DBFloat),
             other => Err(format!("Wrong argument type {} for sum", other))
         }
@@ -171,12 +222,510 @@ impl AggrFunctionBuilder for SumAggrBuilder
             return Err("sum expects 1 argument".to_string());
         }
 
-        match src[0]
+        match &src[0]
         {
             TypeName::DBInt => Ok(SumAggrColumn::<DBInt>::new_ref()),
+            TypeName::DBInt8 => Ok(SumAggrColumn::<DBInt8, DBInt>::new_ref()),
+            TypeName::DBInt16 => Ok(SumAggrColumn::<DBInt16, DBInt>::new_ref()),
+            TypeName::DBInt32 => Ok(SumAggrColumn::<DBInt32, DBInt>::new_ref()),
+            TypeName::DBUInt8 => Ok(SumAggrColumn::<DBUInt8, DBInt>::new_ref()),
+            TypeName::DBUInt16 => Ok(SumAggrColumn::<DBUInt16, DBInt>::new_ref()),
+            TypeName::DBUInt32 => Ok(SumAggrColumn::<DBUInt32, DBInt>::new_ref()),
+            TypeName::DBUInt64 => Ok(SumAggrColumn::<DBUInt64, DBDecimal128>::new_ref()),
             TypeName::DBFloat => Ok(SumAggrColumn::<DBFloat>::new_ref()),
+            TypeName::DBDecimal(precision, _) if *precision <= MAX_PRECISION_64 => Ok(SumAggrColumn::<DBDecimal64, DBDecimal128>::new_ref()),
+            TypeName::DBDecimal(..) => Ok(SumAggrColumn::<DBDecimal128>::new_ref()),
             other => Err(format!("Wrong argument type {} for sum", other))
         }
 
     }
-}
\ No newline at end of file
+}
+/* ========== Count ============ */
+
+pub struct CountAggrColumn
+{
+    data:Vec<i64>
+}
+
+impl CountAggrColumn
+{
+    pub fn new() -> Self
+    {
+        Self{data:Vec::new()}
+    }
+    pub fn new_ref() -> AggrColumnRef
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl AggrColumn for CountAggrColumn
+{
+
+    fn push_empty(&mut self) -> usize
+    {
+        self.data.push(0);
+        self.data.len()
+    }
+    fn append_value(&mut self, _src:Vec<&Column>, _src_at:usize, to:usize) -> DBResult<()>
+    {
+        self.data[to] += 1;
+        Ok(())
+    }
+    fn finalize_to_column(&mut self, dest:&mut Column)
+    {
+        let col = dest.downcast_data_mut::<DBInt>().unwrap();
+        col.resize(0, Default::default());
+        col.append(&mut self.data);
+    }
+}
+
+pub struct CountAggrBuilder {}
+
+
+impl CountAggrBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn AggrFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl AggrFunctionBuilder for CountAggrBuilder
+{
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() > 1
+        {
+            return Err("count expects 0 or 1 argument".to_string());
+        }
+        Ok(TypeName::DBInt)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>
+    {
+        self.result_type(src)?;
+        Ok(CountAggrColumn::new_ref())
+    }
+    fn nullable_result(&self) -> bool
+    {
+        false
+    }
+}
+
+/* ========== Min/Max ============ */
+
+pub struct MinAggrColumn<T:DBType>
+{
+    data:Vec<Option<T::InnerType>>
+}
+
+impl<T:DBType> MinAggrColumn<T>
+    where T::InnerType : Clone + Default + PartialOrd
+{
+    pub fn new() -> Self
+    {
+        Self{data:Vec::new()}
+    }
+    pub fn new_ref() -> AggrColumnRef
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl<T:DBType> AggrColumn for MinAggrColumn<T>
+    where T::InnerType : Clone + Default + PartialOrd
+{
+
+    fn push_empty(&mut self) -> usize
+    {
+        self.data.push(None);
+        self.data.len()
+    }
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        let val = &src[0].downcast_data_ref::<T>().unwrap()[src_at];
+        let is_less = match &self.data[to] {
+            Some(cur) => val < cur,
+            None => true
+        };
+        if is_less
+        {
+            self.data[to] = Some(val.clone());
+        }
+        Ok(())
+    }
+    fn finalize_to_column(&mut self, dest:&mut Column)
+    {
+        let col = dest.downcast_data_mut::<T>().unwrap();
+        col.resize(0, Default::default());
+        col.extend(self.data.drain(..).map(|v| v.unwrap_or_default()));
+    }
+}
+
+pub struct MaxAggrColumn<T:DBType>
+{
+    data:Vec<Option<T::InnerType>>
+}
+
+impl<T:DBType> MaxAggrColumn<T>
+    where T::InnerType : Clone + Default + PartialOrd
+{
+    pub fn new() -> Self
+    {
+        Self{data:Vec::new()}
+    }
+    pub fn new_ref() -> AggrColumnRef
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl<T:DBType> AggrColumn for MaxAggrColumn<T>
+    where T::InnerType : Clone + Default + PartialOrd
+{
+
+    fn push_empty(&mut self) -> usize
+    {
+        self.data.push(None);
+        self.data.len()
+    }
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        let val = &src[0].downcast_data_ref::<T>().unwrap()[src_at];
+        let is_greater = match &self.data[to] {
+            Some(cur) => val > cur,
+            None => true
+        };
+        if is_greater
+        {
+            self.data[to] = Some(val.clone());
+        }
+        Ok(())
+    }
+    fn finalize_to_column(&mut self, dest:&mut Column)
+    {
+        let col = dest.downcast_data_mut::<T>().unwrap();
+        col.resize(0, Default::default());
+        col.extend(self.data.drain(..).map(|v| v.unwrap_or_default()));
+    }
+}
+
+pub struct MinAggrBuilder {}
+
+
+impl MinAggrBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn AggrFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl AggrFunctionBuilder for MinAggrBuilder
+{
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() != 1
+        {
+            return Err("min expects 1 argument".to_string());
+        }
+        Ok(src[0].clone())
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>
+    {
+        if src.len() != 1
+        {
+            return Err("min expects 1 argument".to_string());
+        }
+
+        match &src[0]
+        {
+            TypeName::DBInt => Ok(MinAggrColumn::<DBInt>::new_ref()),
+            TypeName::DBInt8 => Ok(MinAggrColumn::<DBInt8>::new_ref()),
+            TypeName::DBInt16 => Ok(MinAggrColumn::<DBInt16>::new_ref()),
+            TypeName::DBInt32 => Ok(MinAggrColumn::<DBInt32>::new_ref()),
+            TypeName::DBUInt8 => Ok(MinAggrColumn::<DBUInt8>::new_ref()),
+            TypeName::DBUInt16 => Ok(MinAggrColumn::<DBUInt16>::new_ref()),
+            TypeName::DBUInt32 => Ok(MinAggrColumn::<DBUInt32>::new_ref()),
+            TypeName::DBUInt64 => Ok(MinAggrColumn::<DBUInt64>::new_ref()),
+            TypeName::DBFloat => Ok(MinAggrColumn::<DBFloat>::new_ref()),
+            TypeName::DBString => Ok(MinAggrColumn::<DBString>::new_ref()),
+            TypeName::DBBool => Ok(MinAggrColumn::<DBBool>::new_ref()),
+            TypeName::DBDate => Ok(MinAggrColumn::<DBDate>::new_ref()),
+            TypeName::DBDateTime => Ok(MinAggrColumn::<DBDateTime>::new_ref()),
+            TypeName::DBDecimal(precision, _) if *precision <= MAX_PRECISION_64 => Ok(MinAggrColumn::<DBDecimal64>::new_ref()),
+            TypeName::DBDecimal(..) => Ok(MinAggrColumn::<DBDecimal128>::new_ref()),
+            other => Err(format!("Wrong argument type {} for min", other))
+        }
+    }
+    fn null_on_empty(&self) -> bool
+    {
+        true
+    }
+}
+
+pub struct MaxAggrBuilder {}
+
+
+impl MaxAggrBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn AggrFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl AggrFunctionBuilder for MaxAggrBuilder
+{
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() != 1
+        {
+            return Err("max expects 1 argument".to_string());
+        }
+        Ok(src[0].clone())
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>
+    {
+        if src.len() != 1
+        {
+            return Err("max expects 1 argument".to_string());
+        }
+
+        match &src[0]
+        {
+            TypeName::DBInt => Ok(MaxAggrColumn::<DBInt>::new_ref()),
+            TypeName::DBInt8 => Ok(MaxAggrColumn::<DBInt8>::new_ref()),
+            TypeName::DBInt16 => Ok(MaxAggrColumn::<DBInt16>::new_ref()),
+            TypeName::DBInt32 => Ok(MaxAggrColumn::<DBInt32>::new_ref()),
+            TypeName::DBUInt8 => Ok(MaxAggrColumn::<DBUInt8>::new_ref()),
+            TypeName::DBUInt16 => Ok(MaxAggrColumn::<DBUInt16>::new_ref()),
+            TypeName::DBUInt32 => Ok(MaxAggrColumn::<DBUInt32>::new_ref()),
+            TypeName::DBUInt64 => Ok(MaxAggrColumn::<DBUInt64>::new_ref()),
+            TypeName::DBFloat => Ok(MaxAggrColumn::<DBFloat>::new_ref()),
+            TypeName::DBString => Ok(MaxAggrColumn::<DBString>::new_ref()),
+            TypeName::DBBool => Ok(MaxAggrColumn::<DBBool>::new_ref()),
+            TypeName::DBDate => Ok(MaxAggrColumn::<DBDate>::new_ref()),
+            TypeName::DBDateTime => Ok(MaxAggrColumn::<DBDateTime>::new_ref()),
+            TypeName::DBDecimal(precision, _) if *precision <= MAX_PRECISION_64 => Ok(MaxAggrColumn::<DBDecimal64>::new_ref()),
+            TypeName::DBDecimal(..) => Ok(MaxAggrColumn::<DBDecimal128>::new_ref()),
+            other => Err(format!("Wrong argument type {} for max", other))
+        }
+    }
+    fn null_on_empty(&self) -> bool
+    {
+        true
+    }
+}
+
+/* ========== Avg ============ */
+
+pub trait AsFloat
+{
+    fn as_float(&self) -> f64;
+}
+
+macro_rules! impl_as_float {
+    ($inner_ty:ty) => {
+        impl AsFloat for $inner_ty
+        {
+            fn as_float(&self) -> f64
+            {
+                *self as f64
+            }
+        }
+    };
+}
+
+impl_as_float!(i64);
+impl_as_float!(i8);
+impl_as_float!(i16);
+impl_as_float!(i32);
+impl_as_float!(u8);
+impl_as_float!(u16);
+impl_as_float!(u32);
+impl_as_float!(u64);
+
+impl AsFloat for f64
+{
+    fn as_float(&self) -> f64
+    {
+        *self
+    }
+}
+
+pub struct AvgAggrColumn<T:DBType>
+{
+    sums:Vec<f64>,
+    counts:Vec<usize>,
+    _marker:std::marker::PhantomData<T>
+}
+
+impl<T:DBType> AvgAggrColumn<T>
+    where T::InnerType : AsFloat
+{
+    pub fn new() -> Self
+    {
+        Self{sums:Vec::new(), counts:Vec::new(), _marker:std::marker::PhantomData::<T>{}}
+    }
+    pub fn new_ref() -> AggrColumnRef
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl<T:DBType> AggrColumn for AvgAggrColumn<T>
+    where T::InnerType : AsFloat
+{
+
+    fn push_empty(&mut self) -> usize
+    {
+        self.sums.push(0.);
+        self.counts.push(0);
+        self.sums.len()
+    }
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        let col = src[0].downcast_data_ref::<T>().unwrap();
+        self.sums[to] += col[src_at].as_float();
+        self.counts[to] += 1;
+        Ok(())
+    }
+    fn finalize_to_column(&mut self, dest:&mut Column)
+    {
+        let col = dest.downcast_data_mut::<DBFloat>().unwrap();
+        col.resize(0, Default::default());
+        for (sum, count) in self.sums.iter().zip(self.counts.iter())
+        {
+            col.push(if *count > 0 {sum / *count as f64} else {0.});
+        }
+        self.sums.clear();
+        self.counts.clear();
+    }
+}
+
+//Exact sum of the decimals divided by the count, the quotient is truncated to the scale of the argument
+pub struct DecimalAvgAggrColumn<T:DBType>
+{
+    sums:Vec<i128>,
+    counts:Vec<i128>,
+    _marker:std::marker::PhantomData<T>
+}
+
+impl<T:DBType> DecimalAvgAggrColumn<T>
+    where T::InnerType : Clone + Into<i128>
+{
+    pub fn new() -> Self
+    {
+        Self{sums:Vec::new(), counts:Vec::new(), _marker:std::marker::PhantomData::<T>{}}
+    }
+    pub fn new_ref() -> AggrColumnRef
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl<T:DBType> Default for DecimalAvgAggrColumn<T>
+    where T::InnerType : Clone + Into<i128>
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl<T:DBType> AggrColumn for DecimalAvgAggrColumn<T>
+    where T::InnerType : Clone + Into<i128>
+{
+
+    fn push_empty(&mut self) -> usize
+    {
+        self.sums.push(0);
+        self.counts.push(0);
+        self.sums.len()
+    }
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        let col = src[0].downcast_data_ref::<T>().unwrap();
+        self.sums[to] = self.sums[to].checked_add(col[src_at].clone().into()).ok_or("Integer overflow in avg")?;
+        self.counts[to] += 1;
+        Ok(())
+    }
+    fn finalize_to_column(&mut self, dest:&mut Column)
+    {
+        let col = dest.downcast_data_mut::<DBDecimal128>().unwrap();
+        col.resize(0, Default::default());
+        for (sum, count) in self.sums.iter().zip(self.counts.iter())
+        {
+            col.push(if *count > 0 {sum / count} else {0});
+        }
+        self.sums.clear();
+        self.counts.clear();
+    }
+}
+
+pub struct AvgAggrBuilder {}
+
+
+impl AvgAggrBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn AggrFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl AggrFunctionBuilder for AvgAggrBuilder
+{
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() != 1
+        {
+            return Err("avg expects 1 argument".to_string());
+        }
+
+        match &src[0]
+        {
+            t if t.is_integer() => Ok(TypeName::DBFloat),
+            TypeName::DBFloat => Ok(TypeName::DBFloat),
+            TypeName::DBDecimal(_, scale) => Ok(TypeName::DBDecimal(MAX_PRECISION, *scale)),
+            other => Err(format!("Wrong argument type {} for avg", other))
+        }
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>
+    {
+        if src.len() != 1
+        {
+            return Err("avg expects 1 argument".to_string());
+        }
+
+        match &src[0]
+        {
+            TypeName::DBInt => Ok(AvgAggrColumn::<DBInt>::new_ref()),
+            TypeName::DBInt8 => Ok(AvgAggrColumn::<DBInt8>::new_ref()),
+            TypeName::DBInt16 => Ok(AvgAggrColumn::<DBInt16>::new_ref()),
+            TypeName::DBInt32 => Ok(AvgAggrColumn::<DBInt32>::new_ref()),
+            TypeName::DBUInt8 => Ok(AvgAggrColumn::<DBUInt8>::new_ref()),
+            TypeName::DBUInt16 => Ok(AvgAggrColumn::<DBUInt16>::new_ref()),
+            TypeName::DBUInt32 => Ok(AvgAggrColumn::<DBUInt32>::new_ref()),
+            TypeName::DBUInt64 => Ok(AvgAggrColumn::<DBUInt64>::new_ref()),
+            TypeName::DBFloat => Ok(AvgAggrColumn::<DBFloat>::new_ref()),
+            TypeName::DBDecimal(precision, _) if *precision <= MAX_PRECISION_64 => Ok(DecimalAvgAggrColumn::<DBDecimal64>::new_ref()),
+            TypeName::DBDecimal(..) => Ok(DecimalAvgAggrColumn::<DBDecimal128>::new_ref()),
+            other => Err(format!("Wrong argument type {} for avg", other))
+        }
+
+    }
+    fn null_on_empty(&self) -> bool
+    {
+        true
+    }
+}
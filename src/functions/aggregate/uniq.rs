@@ -0,0 +1,112 @@
+use super::*;
+use crate::types::TypeName;
+use crate::types::types::*;
+use std::collections::HashSet;
+
+/* ======== Uniq ========== */
+
+//Values are compared by their packed representation, so uniq works for any types and number of arguments
+pub struct UniqAggrColumn
+{
+    data:Vec<HashSet<Vec<u8>>>,
+    key:Vec<u8>
+}
+
+impl UniqAggrColumn
+{
+    pub fn new() -> Self
+    {
+        Self{data:Vec::new(), key:Vec::new()}
+    }
+    pub fn new_ref() -> AggrColumnRef
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl AggrColumn for UniqAggrColumn
+{
+
+    fn push_empty(&mut self) -> usize
+    {
+        self.data.push(HashSet::new());
+        self.data.len()
+    }
+    fn append_value(&mut self, src:Vec<&Column>, src_at:usize, to:usize) -> DBResult<()>
+    {
+        self.key.clear();
+        for col in src.iter()
+        {
+            col.pack_value_to(src_at, &mut self.key);
+        }
+        if !self.data[to].contains(&self.key)
+        {
+            self.data[to].insert(self.key.clone());
+        }
+        Ok(())
+    }
+    fn finalize_to_column(&mut self, dest:&mut Column)
+    {
+        let col = dest.downcast_data_mut::<DBInt>().unwrap();
+        col.resize(0, Default::default());
+        col.extend(self.data.drain(..).map(|set| set.len() as i64));
+    }
+}
+
+pub struct UniqAggrBuilder {}
+
+
+impl UniqAggrBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn AggrFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl AggrFunctionBuilder for UniqAggrBuilder
+{
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.is_empty()
+        {
+            return Err("uniq expects at least 1 argument".to_string());
+        }
+        Ok(TypeName::DBInt)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<AggrColumnRef>
+    {
+        self.result_type(src)?;
+        Ok(UniqAggrColumn::new_ref())
+    }
+    fn nullable_result(&self) -> bool
+    {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+
+    #[test]
+    fn uniq_strings()
+    {
+        let mut src = Column::new(ColumnHeader::new("src", TypeName::DBString));
+        src.downcast_data_mut::<DBString>().unwrap().append(
+            &mut vec!["a".to_string(), "b".to_string(), "a".to_string(), "c".to_string(), "b".to_string()]
+        );
+        let mut aggr = UniqAggrBuilder::new().build(vec![TypeName::DBString]).unwrap();
+        aggr.push_empty();
+        aggr.push_empty();
+        for i in 0..src.len()
+        {
+            aggr.append_value(vec![&src], i, i % 2).unwrap();
+        }
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBInt));
+        aggr.finalize_to_column(&mut dest);
+        assert_eq!(*dest.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![2, 2]);
+    }
+}
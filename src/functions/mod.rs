@@ -1 +1,3 @@
-pub mod regular;
\ No newline at end of file
+pub mod regular;
+pub mod aggregate;
+pub mod registry;
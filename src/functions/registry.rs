@@ -0,0 +1,114 @@
+use crate::DBResult;
+use super::regular::{self, RegFunctionBuilder, RegFunctionBuilderRef};
+use super::aggregate::{self, AggrFunctionBuilder, AggrFunctionBuilderRef};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+//Functions callable by name from SQL. Names are case insensitive, operators are registered
+//as regular functions too (plus, equals, and...), so builtins and user functions are resolved the same way
+pub struct FunctionRegistry
+{
+    regular :HashMap<String, Rc<dyn RegFunctionBuilder>>,
+    aggregate :HashMap<String, Rc<dyn AggrFunctionBuilder>>,
+}
+
+impl FunctionRegistry
+{
+    //Registry with the builtin functions
+    pub fn new() -> Self
+    {
+        let mut registry = Self::empty();
+        regular::register_functions(&mut registry).unwrap();
+        aggregate::register_functions(&mut registry).unwrap();
+        registry
+    }
+
+    pub fn empty() -> Self
+    {
+        Self{regular:HashMap::new(), aggregate:HashMap::new()}
+    }
+
+    pub fn register_function(&mut self, name:&str, builder:RegFunctionBuilderRef) -> DBResult<()>
+    {
+        let key = self.free_key(name)?;
+        self.regular.insert(key, Rc::from(builder));
+        Ok(())
+    }
+
+    pub fn register_aggregate_function(&mut self, name:&str, builder:AggrFunctionBuilderRef) -> DBResult<()>
+    {
+        let key = self.free_key(name)?;
+        self.aggregate.insert(key, Rc::from(builder));
+        Ok(())
+    }
+
+    //Another name of the registered function
+    pub fn register_alias(&mut self, alias:&str, name:&str) -> DBResult<()>
+    {
+        let key = self.free_key(alias)?;
+        let name = name.to_lowercase();
+        if let Some(builder) = self.regular.get(&name)
+        {
+            let builder = builder.clone();
+            self.regular.insert(key, builder);
+        }
+        else if let Some(builder) = self.aggregate.get(&name)
+        {
+            let builder = builder.clone();
+            self.aggregate.insert(key, builder);
+        }
+        else
+        {
+            return Err(format!("Function {} not found", name));
+        }
+        Ok(())
+    }
+
+    pub fn function(&self, name:&str) -> Option<&dyn RegFunctionBuilder>
+    {
+        self.regular.get(&name.to_lowercase()).map(|b| b.as_ref())
+    }
+
+    pub fn aggregate_function(&self, name:&str) -> Option<&dyn AggrFunctionBuilder>
+    {
+        self.aggregate.get(&name.to_lowercase()).map(|b| b.as_ref())
+    }
+
+    pub fn is_aggregate_function(&self, name:&str) -> bool
+    {
+        self.aggregate.contains_key(&name.to_lowercase())
+    }
+
+    fn free_key(&self, name:&str) -> DBResult<String>
+    {
+        let key = name.to_lowercase();
+        if self.regular.contains_key(&key) || self.aggregate.contains_key(&key)
+        {
+            return Err(format!("Function {} is already registered", name));
+        }
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::functions::regular::math::MathBuilder;
+
+    #[test]
+    fn register()
+    {
+        let mut registry = FunctionRegistry::new();
+        assert!(registry.function("PLUS").is_some());
+        assert!(registry.function("sum").is_none());
+        assert!(registry.is_aggregate_function("Sum"));
+        assert!(registry.register_function("sum", Box::new(MathBuilder::new("sum", 1, None, Some(|a| Ok(a.iter().sum())), None, None))).is_err());
+
+        registry.register_function("sum_of", Box::new(MathBuilder::new("sum_of", 1, None, Some(|a| Ok(a.iter().sum())), None, None))).unwrap();
+        registry.register_alias("total", "SUM_OF").unwrap();
+        assert!(registry.function("total").is_some());
+        assert!(!registry.is_aggregate_function("total"));
+        assert!(registry.register_alias("x", "undefined").is_err());
+        assert!(FunctionRegistry::empty().function("plus").is_none());
+    }
+}
@@ -1,8 +1,75 @@
 use super::*;
 use crate::types::DBType;
 use crate::types::types::*;
+use super::cast::{promote_numeric, PromotedBuilder};
+use super::decimal::{is_decimal_args, DecimalArithmeticBuilder, DecimalOp};
 use itertools::izip;
-use std::ops;
+
+//Int +, - and * return an error on overflow instead of panicking or wrapping around, Float follows IEEE 754
+pub trait CheckedArithmetic<R>
+{
+    type Output;
+    fn checked_add_op(self, r:R) -> DBResult<Self::Output>;
+    fn checked_sub_op(self, r:R) -> DBResult<Self::Output>;
+    fn checked_mul_op(self, r:R) -> DBResult<Self::Output>;
+}
+
+fn int_overflow_error(l:impl std::fmt::Display, op:&str, r:impl std::fmt::Display) -> String
+{
+    format!("Integer overflow in {} {} {}", l, op, r)
+}
+
+impl CheckedArithmetic<i64> for i64
+{
+    type Output = i64;
+    fn checked_add_op(self, r:i64) -> DBResult<i64>
+    {
+        self.checked_add(r).ok_or_else(|| int_overflow_error(self, "+", r))
+    }
+    fn checked_sub_op(self, r:i64) -> DBResult<i64>
+    {
+        self.checked_sub(r).ok_or_else(|| int_overflow_error(self, "-", r))
+    }
+    fn checked_mul_op(self, r:i64) -> DBResult<i64>
+    {
+        self.checked_mul(r).ok_or_else(|| int_overflow_error(self, "*", r))
+    }
+}
+
+impl CheckedArithmetic<u64> for u64
+{
+    type Output = u64;
+    fn checked_add_op(self, r:u64) -> DBResult<u64>
+    {
+        self.checked_add(r).ok_or_else(|| int_overflow_error(self, "+", r))
+    }
+    fn checked_sub_op(self, r:u64) -> DBResult<u64>
+    {
+        self.checked_sub(r).ok_or_else(|| int_overflow_error(self, "-", r))
+    }
+    fn checked_mul_op(self, r:u64) -> DBResult<u64>
+    {
+        self.checked_mul(r).ok_or_else(|| int_overflow_error(self, "*", r))
+    }
+}
+
+impl CheckedArithmetic<f64> for f64
+{
+    type Output = f64;
+    fn checked_add_op(self, r:f64) -> DBResult<f64>
+    {
+        Ok(self + r)
+    }
+    fn checked_sub_op(self, r:f64) -> DBResult<f64>
+    {
+        Ok(self - r)
+    }
+    fn checked_mul_op(self, r:f64) -> DBResult<f64>
+    {
+        Ok(self * r)
+    }
+}
+
 /*========== Plus ==================*/
 pub struct Plus<L, R>
 {
@@ -29,9 +96,13 @@ impl OpResult for Plus<DBFloat, DBFloat>
 {
     type ResultType = DBFloat;
 }
+impl OpResult for Plus<DBUInt64, DBUInt64>
+{
+    type ResultType = DBUInt64;
+}
 
 impl<L:DBType, R:DBType> RegFunction for Plus<L, R>
-where L::InnerType:ops::Add<
+where L::InnerType:CheckedArithmetic<
             R::InnerType,
             Output = <<Plus<L, R> as OpResult>::ResultType as DBType>::InnerType
         >,
@@ -50,7 +121,7 @@ where L::InnerType:ops::Add<
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = *l + *r;
+            *d = l.checked_add_op(*r)?;
         }
 
         Ok(())
@@ -68,7 +139,7 @@ impl PlusBuilder {
     pub fn new() -> PlusBuilder {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_numeric)
     }
 }
 impl RegFunctionBuilder for PlusBuilder {
@@ -76,6 +147,10 @@ impl RegFunctionBuilder for PlusBuilder {
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
         assert_eq!(src.len(), 2);
+        if is_decimal_args(&src)
+        {
+            return DecimalArithmeticBuilder::new(DecimalOp::Plus).result_type(src);
+        }
         let err_str = format!("+ operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -84,6 +159,12 @@ impl RegFunctionBuilder for PlusBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBUInt64),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(TypeName::DBFloat),
@@ -97,6 +178,10 @@ impl RegFunctionBuilder for PlusBuilder {
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
     {
         assert_eq!(src.len(), 2);
+        if is_decimal_args(&src)
+        {
+            return DecimalArithmeticBuilder::new(DecimalOp::Plus).build(src);
+        }
         let err_str = format!("+ operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -105,6 +190,12 @@ impl RegFunctionBuilder for PlusBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(Plus::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(Plus::<DBFloat, DBFloat>::new())),
@@ -119,6 +210,19 @@ impl RegFunctionBuilder for PlusBuilder {
 }
 
 /*========== Minus =========*/
+//The difference of UInt64 may be negative, so it's computed as Int
+fn promote_minus(src:&[TypeName]) -> Option<Vec<TypeName>>
+{
+    if src.iter().all(|t| *t == TypeName::DBUInt64)
+    {
+        Some(vec![TypeName::DBInt; src.len()])
+    }
+    else
+    {
+        promote_numeric(src)
+    }
+}
+
 pub struct Minus<L, R>
 {
     _l :std::marker::PhantomData<L>,
@@ -146,7 +250,7 @@ impl OpResult for Minus<DBFloat, DBFloat>
 }
 
 impl<L:DBType, R:DBType> RegFunction for Minus<L, R>
-where L::InnerType:ops::Sub<
+where L::InnerType:CheckedArithmetic<
             R::InnerType,
             Output = <<Self as OpResult>::ResultType as DBType>::InnerType
         >,
@@ -165,7 +269,7 @@ where L::InnerType:ops::Sub<
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = *l - *r;
+            *d = l.checked_sub_op(*r)?;
         }
 
         Ok(())
@@ -183,7 +287,7 @@ impl MinusBuilder {
     pub fn new() -> MinusBuilder {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_minus)
     }
 }
 impl RegFunctionBuilder for MinusBuilder {
@@ -191,6 +295,10 @@ impl RegFunctionBuilder for MinusBuilder {
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
         assert_eq!(src.len(), 2);
+        if is_decimal_args(&src)
+        {
+            return DecimalArithmeticBuilder::new(DecimalOp::Minus).result_type(src);
+        }
         let err_str = format!("- operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -212,6 +320,10 @@ impl RegFunctionBuilder for MinusBuilder {
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
     {
         assert_eq!(src.len(), 2);
+        if is_decimal_args(&src)
+        {
+            return DecimalArithmeticBuilder::new(DecimalOp::Minus).build(src);
+        }
         let err_str = format!("- operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -260,9 +372,13 @@ impl OpResult for Multiply<DBFloat, DBFloat>
 {
     type ResultType = DBFloat;
 }
+impl OpResult for Multiply<DBUInt64, DBUInt64>
+{
+    type ResultType = DBUInt64;
+}
 
 impl<L:DBType, R:DBType> RegFunction for Multiply<L, R>
-where L::InnerType:ops::Mul<
+where L::InnerType:CheckedArithmetic<
             R::InnerType,
             Output = <<Self as OpResult>::ResultType as DBType>::InnerType
         >,
@@ -281,7 +397,7 @@ where L::InnerType:ops::Mul<
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = *l * *r;
+            *d = l.checked_mul_op(*r)?;
         }
 
         Ok(())
@@ -299,7 +415,7 @@ impl MultiplyBuilder {
     pub fn new() -> Self {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_numeric)
     }
 }
 impl RegFunctionBuilder for MultiplyBuilder {
@@ -307,6 +423,10 @@ impl RegFunctionBuilder for MultiplyBuilder {
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
         assert_eq!(src.len(), 2);
+        if is_decimal_args(&src)
+        {
+            return DecimalArithmeticBuilder::new(DecimalOp::Multiply).result_type(src);
+        }
         let err_str = format!("* operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -315,6 +435,12 @@ impl RegFunctionBuilder for MultiplyBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBUInt64),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(TypeName::DBFloat),
@@ -328,6 +454,10 @@ impl RegFunctionBuilder for MultiplyBuilder {
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
     {
         assert_eq!(src.len(), 2);
+        if is_decimal_args(&src)
+        {
+            return DecimalArithmeticBuilder::new(DecimalOp::Multiply).build(src);
+        }
         let err_str = format!("* operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -336,6 +466,12 @@ impl RegFunctionBuilder for MultiplyBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(Multiply::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(Multiply::<DBFloat, DBFloat>::new())),
@@ -351,6 +487,45 @@ impl RegFunctionBuilder for MultiplyBuilder {
 
 /* ======= Divide =============== */
 
+//Int division by zero and i64::MIN / -1 return an error instead of panicking, Float follows IEEE 754
+pub trait CheckedDiv<R>
+{
+    type Output;
+    fn checked_div_op(self, r:R) -> DBResult<Self::Output>;
+}
+
+impl CheckedDiv<i64> for i64
+{
+    type Output = i64;
+    fn checked_div_op(self, r:i64) -> DBResult<i64>
+    {
+        self.checked_div(r).ok_or_else(|| int_division_error(self, r))
+    }
+}
+
+impl CheckedDiv<u64> for u64
+{
+    type Output = u64;
+    fn checked_div_op(self, r:u64) -> DBResult<u64>
+    {
+        self.checked_div(r).ok_or_else(|| "Division by zero".to_string())
+    }
+}
+
+impl CheckedDiv<f64> for f64
+{
+    type Output = f64;
+    fn checked_div_op(self, r:f64) -> DBResult<f64>
+    {
+        Ok(self / r)
+    }
+}
+
+pub fn int_division_error(l:i64, r:i64) -> String
+{
+    if r == 0 {"Division by zero".to_string()} else {format!("Integer overflow in division of {} by {}", l, r)}
+}
+
 pub struct Divide<L, R>
 {
     _l :std::marker::PhantomData<L>,
@@ -376,9 +551,13 @@ impl OpResult for Divide<DBFloat, DBFloat>
 {
     type ResultType = DBFloat;
 }
+impl OpResult for Divide<DBUInt64, DBUInt64>
+{
+    type ResultType = DBUInt64;
+}
 
 impl<L:DBType, R:DBType> RegFunction for Divide<L, R>
-where L::InnerType:ops::Div<
+where L::InnerType:CheckedDiv<
             R::InnerType,
             Output = <<Self as OpResult>::ResultType as DBType>::InnerType
         >,
@@ -397,7 +576,7 @@ where L::InnerType:ops::Div<
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = *l / *r;
+            *d = l.checked_div_op(*r)?;
         }
 
         Ok(())
@@ -415,7 +594,7 @@ impl DivideBuilder {
     pub fn new() -> Self {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_numeric)
     }
 }
 impl RegFunctionBuilder for DivideBuilder {
@@ -423,6 +602,10 @@ impl RegFunctionBuilder for DivideBuilder {
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
         assert_eq!(src.len(), 2);
+        if is_decimal_args(&src)
+        {
+            return DecimalArithmeticBuilder::new(DecimalOp::Divide).result_type(src);
+        }
         let err_str = format!("/ operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -431,6 +614,12 @@ impl RegFunctionBuilder for DivideBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBUInt64),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(TypeName::DBFloat),
@@ -444,6 +633,10 @@ impl RegFunctionBuilder for DivideBuilder {
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
     {
         assert_eq!(src.len(), 2);
+        if is_decimal_args(&src)
+        {
+            return DecimalArithmeticBuilder::new(DecimalOp::Divide).build(src);
+        }
         let err_str = format!("/ operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -452,6 +645,12 @@ impl RegFunctionBuilder for DivideBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(Divide::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(Divide::<DBFloat, DBFloat>::new())),
@@ -503,22 +702,57 @@ mod test {
         }
     }
 
+    #[test]
+    fn divide_by_zero()
+    {
+        let mut l = Column::new(ColumnHeader::new("l", TypeName::DBInt));
+        let mut r = Column::new(ColumnHeader::new("r", TypeName::DBInt));
+        let mut d = Column::new(ColumnHeader::new("d", TypeName::DBInt));
+        l.resize(2);
+        r.resize(2);
+        d.resize(2);
+        l.downcast_data_mut::<DBInt>().unwrap().data_mut().copy_from_slice(&[7, i64::MIN]);
+        r.downcast_data_mut::<DBInt>().unwrap().data_mut().copy_from_slice(&[2, -1]);
+        let op = Divide::<DBInt, DBInt>::new();
+        assert!(op.apply(vec![&l, &r], &mut d).is_err());
+        r.downcast_data_mut::<DBInt>().unwrap().data_mut()[1] = 0;
+        assert_eq!(op.apply(vec![&l, &r], &mut d), Err("Division by zero".to_string()));
+        r.downcast_data_mut::<DBInt>().unwrap().data_mut()[1] = 3;
+        op.apply(vec![&l, &r], &mut d).unwrap();
+        assert_eq!(*d.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, i64::MIN / 3]);
+    }
+
     #[test]
     fn plus_res_type()
     {
         assert_eq!(
-            PlusBuilder::new().result_type(
+            PlusBuilder::new_ref().result_type(
                 Vec::from([TypeName::DBInt, TypeName::DBInt])
             ), Ok(TypeName::DBInt)
         );
         assert_eq!(
-            PlusBuilder::new().result_type(
+            PlusBuilder::new_ref().result_type(
                 Vec::from([TypeName::DBFloat, TypeName::DBFloat])
             ), Ok(TypeName::DBFloat)
         );
-        assert!(
-            PlusBuilder::new().result_type(
+        assert_eq!(
+            PlusBuilder::new_ref().result_type(
                 Vec::from([TypeName::DBInt, TypeName::DBFloat])
+            ), Ok(TypeName::DBFloat)
+        );
+        assert_eq!(
+            PlusBuilder::new_ref().result_type(
+                Vec::from([TypeName::DBUInt8, TypeName::DBInt16])
+            ), Ok(TypeName::DBInt)
+        );
+        assert_eq!(
+            PlusBuilder::new_ref().result_type(
+                Vec::from([TypeName::DBUInt64, TypeName::DBFloat])
+            ), Ok(TypeName::DBFloat)
+        );
+        assert!(
+            PlusBuilder::new_ref().result_type(
+                Vec::from([TypeName::DBInt, TypeName::DBString])
             ).is_err()
 
         );
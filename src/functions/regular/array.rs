@@ -0,0 +1,435 @@
+use super::*;
+use crate::types::types::*;
+use crate::columns::data::{make_storage, downcast_storage_ref};
+use crate::columns::array::{downcast_array_ref, downcast_array_mut};
+use super::cast::Promoted;
+use super::conditional::unify_types;
+use crate::functions::registry::FunctionRegistry;
+
+//Element type of Array(T) argument, possibly Nullable
+fn element_type(t:&TypeName) -> Option<&TypeName>
+{
+    t.base().element()
+}
+
+/* ======== Array ====== */
+
+//array(x, ...) and the literal [x, ...]: the arguments are cast to their common type, [] is Array(Nothing)
+pub struct MakeArray {}
+
+impl RegFunction for MakeArray
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        let (rows, n) = (dest.len(), src.len());
+        if n == 0
+        {
+            return Ok(());
+        }
+        //The arguments one after another, then reordered by the rows
+        let mut values = make_storage(src[0].type_name());
+        values.resize(rows * n);
+        for (i, col) in src.iter().enumerate()
+        {
+            col.data_ref().copy_to(&mut values, i * rows);
+        }
+        let perms:Vec<usize> = (0..rows * n).map(|pos| (pos % n) * rows + pos / n).collect();
+        values.permute(&perms);
+        downcast_array_mut(dest.data_mut().as_mut()).unwrap().replace_rows(0, &vec![n; rows], &values);
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        format!("[{}]", src.join(", "))
+    }
+}
+
+pub struct ArrayBuilder {}
+
+impl ArrayBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+    fn element_type(&self, src:&[TypeName]) -> DBResult<TypeName>
+    {
+        unify_types(src).map_err(|_| {
+            let names:Vec<String> = src.iter().map(|t| t.to_string()).collect();
+            format!("Elements of array have incompatible types: {}", names.join(", "))
+        })
+    }
+}
+
+impl Default for ArrayBuilder
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl RegFunctionBuilder for ArrayBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        Ok(TypeName::DBArray(Box::new(self.element_type(&src)?)))
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        let elem = self.element_type(&src)?;
+        Ok(Promoted::new_ref(Box::new(MakeArray{}), vec![elem; src.len()]))
+    }
+    //NULL is an element of Array(Nullable(T))
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+}
+
+/* ======== Length ====== */
+
+//length(arr) is the number of the elements, other arguments go to the wrapped function (length of String)
+pub struct ArrayLength {}
+
+impl OpResult for ArrayLength
+{
+    type ResultType = DBInt;
+}
+
+impl RegFunction for ArrayLength
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        let arr = downcast_array_ref(src[0].data_ref().as_ref()).unwrap();
+        for (row, d) in dest.downcast_data_iter_mut::<DBInt>().unwrap().enumerate()
+        {
+            *d = arr.row_len(row) as i64;
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 1);
+        format!("length({})", src[0])
+    }
+}
+
+pub struct LengthBuilder
+{
+    other :RegFunctionBuilderRef,
+}
+
+impl LengthBuilder {
+    pub fn new(other:RegFunctionBuilderRef) -> Self {Self{other}}
+    pub fn new_ref(other:RegFunctionBuilderRef) -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new(other))
+    }
+}
+
+impl RegFunctionBuilder for LengthBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        match src.as_slice() {
+            [TypeName::DBArray(_)] => Ok(ArrayLength::RESULT_NAME),
+            _ => self.other.result_type(src)
+        }
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        match src.as_slice() {
+            [TypeName::DBArray(_)] => Ok(Box::new(ArrayLength{})),
+            _ => self.other.build(src)
+        }
+    }
+}
+
+/* ======== Has ====== */
+
+//has(arr, x): whether x is an element of arr, the elements are compared in the common type.
+//NULL element matches NULL x
+pub struct Has {}
+
+impl OpResult for Has
+{
+    type ResultType = DBBool;
+}
+
+impl RegFunction for Has
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 2);
+        let arr = downcast_array_ref(src[0].data_ref().as_ref()).unwrap();
+        //Floats are equal by value, so 0.0 matches -0.0
+        if let Some(values) = downcast_storage_ref::<DBFloat>(arr.values().as_ref())
+        {
+            let x = src[1].downcast_data_ref::<DBFloat>().unwrap().data_ref();
+            let (value_nulls, x_nulls) = (arr.values().nulls(), src[1].nulls());
+            let is_null = |nulls:Option<&Vec<bool>>, i:usize| nulls.is_some_and(|n| n[i]);
+            for (row, d) in dest.downcast_data_iter_mut::<DBBool>().unwrap().enumerate()
+            {
+                let x_null = is_null(x_nulls, row);
+                *d = arr.row_range(row).any(|i| {
+                    let elem_null = is_null(value_nulls, i);
+                    elem_null == x_null && (elem_null || values.data_ref()[i] == x[row])
+                });
+            }
+            return Ok(());
+        }
+        //Packed values of the other types are equal if the values are
+        let (mut x, mut elem) = (Vec::<u8>::new(), Vec::<u8>::new());
+        for (row, d) in dest.downcast_data_iter_mut::<DBBool>().unwrap().enumerate()
+        {
+            x.clear();
+            src[1].pack_value_to(row, &mut x);
+            *d = arr.row_range(row).any(|i| {
+                elem.clear();
+                arr.values().pack_value_to(i, &mut elem);
+                elem == x
+            });
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 2);
+        format!("has({}, {})", src[0], src[1])
+    }
+}
+
+pub struct HasBuilder {}
+
+impl HasBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+    fn common_type(&self, src:&[TypeName]) -> DBResult<TypeName>
+    {
+        let elem = match src {
+            [arr, _] => element_type(arr).ok_or_else(|| format!("Argument 1 of has must be Array, got {}", arr))?,
+            _ => return Err(format!("has expects 2 arguments, got {}", src.len()))
+        };
+        unify_types(&[elem.clone(), src[1].clone()]).map_err(|_| format!("has unsupported for {} and {}", src[0], src[1]))
+    }
+}
+
+impl Default for HasBuilder
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl RegFunctionBuilder for HasBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        self.common_type(&src)?;
+        Ok(Has::RESULT_NAME)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        let common = self.common_type(&src)?;
+        Ok(Promoted::new_ref(Box::new(Has{}), vec![TypeName::DBArray(Box::new(common.clone())), common]))
+    }
+    //NULL array has no elements
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+}
+
+/* ======== ArrayElement ====== */
+
+//arrayElement(arr, i) and arr[i]: 1-based index, negative one counts from the end.
+//Out of range index gives the default value, or NULL for Nullable elements. NULL argument gives NULL
+pub struct ArrayElement {}
+
+impl RegFunction for ArrayElement
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 2);
+        let arr = downcast_array_ref(src[0].data_ref().as_ref()).unwrap();
+        //The extra value after the elements is taken for out of range
+        let missing = arr.values().len();
+        let mut values = make_storage(arr.elem_type().clone());
+        values.resize(missing + 1);
+        arr.values().copy_to(&mut values, 0);
+        if let Some(nulls) = values.nulls_mut()
+        {
+            nulls[missing] = true;
+        }
+        let mut indices = Vec::<usize>::with_capacity(dest.len());
+        let mut null_rows = Vec::<usize>::new();
+        for (row, i) in src[1].downcast_data_iter::<DBInt>().unwrap().enumerate()
+        {
+            if src[0].is_null(row) || src[1].is_null(row)
+            {
+                null_rows.push(row);
+                indices.push(missing);
+                continue;
+            }
+            let range = arr.row_range(row);
+            let len = range.len() as i64;
+            let pos = if *i > 0 {*i - 1} else {len + *i};
+            indices.push(if *i != 0 && pos >= 0 && pos < len {range.start + pos as usize} else {missing});
+        }
+        values.copy_indexed_to(dest.data_mut(), &indices);
+        if let Some(nulls) = dest.nulls_mut()
+        {
+            for row in null_rows
+            {
+                nulls[row] = true;
+            }
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 2);
+        format!("{}[{}]", src[0], src[1])
+    }
+}
+
+pub struct ArrayElementBuilder {}
+
+impl ArrayElementBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl Default for ArrayElementBuilder
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl RegFunctionBuilder for ArrayElementBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        let (arr, index) = match src.as_slice() {
+            [arr, index] => (arr, index),
+            _ => return Err(format!("arrayElement expects 2 arguments, got {}", src.len()))
+        };
+        let elem = element_type(arr).ok_or_else(|| format!("Argument 1 of arrayElement must be Array, got {}", arr))?;
+        if !index.base().is_integer()
+        {
+            return Err(format!("Index of array must be integer, got {}", index));
+        }
+        Ok(if arr.is_nullable() || index.is_nullable() {elem.to_nullable()} else {elem.clone()})
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src.clone())?;
+        let index = if src[1].is_nullable() {TypeName::DBInt.to_nullable()} else {TypeName::DBInt};
+        Ok(Promoted::new_ref(Box::new(ArrayElement{}), vec![src[0].clone(), index]))
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+}
+
+pub fn register_functions(registry:&mut FunctionRegistry) -> DBResult<()>
+{
+    registry.register_function("array", ArrayBuilder::new_ref())?;
+    registry.register_function("has", HasBuilder::new_ref())?;
+    registry.register_function("arrayElement", ArrayElementBuilder::new_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+    use super::super::null::build_function;
+
+    fn int_column(values:&[i64]) -> Column
+    {
+        let mut col = Column::new(ColumnHeader::new("i", TypeName::DBInt));
+        col.resize(values.len());
+        col.downcast_data_mut::<DBInt>().unwrap().data_mut().clone_from_slice(values);
+        col
+    }
+
+    fn strings(col:&Column) -> Vec<String>
+    {
+        (0..col.len()).map(|i| col.data_ref().to_string_at(i)).collect()
+    }
+
+    fn apply(builder:&dyn RegFunctionBuilder, src:Vec<&Column>) -> DBResult<Column>
+    {
+        let (type_name, func) = build_function(builder, src.iter().map(|c| c.type_name()).collect())?;
+        let mut dest = Column::new(ColumnHeader::new("dest", type_name));
+        dest.resize(src[0].len());
+        func.apply(src, &mut dest)?;
+        Ok(dest)
+    }
+
+    #[test]
+    fn array_functions()
+    {
+        let a = int_column(&[1, 2, 3]);
+        let mut b = Column::new(ColumnHeader::new("f", TypeName::DBFloat));
+        b.resize(3);
+        b.downcast_data_mut::<DBFloat>().unwrap().data_mut()[0] = 0.5;
+        let arr = apply(&ArrayBuilder::new(), vec![&a, &b]).unwrap();
+        assert_eq!(arr.type_name(), TypeName::DBArray(Box::new(TypeName::DBFloat)));
+        assert_eq!(strings(&arr), vec!["[1, 0.5]", "[2, 0]", "[3, 0]"]);
+
+        let len = apply(&LengthBuilder::new(ArrayBuilder::new_ref()), vec![&arr]).unwrap();
+        assert_eq!(*len.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![2, 2, 2]);
+
+        let x = int_column(&[1, 0, 2]);
+        let has = apply(&HasBuilder::new(), vec![&arr, &x]).unwrap();
+        assert_eq!(*has.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![true, true, false]);
+
+        let mut z = Column::new(ColumnHeader::new("z", TypeName::DBFloat));
+        z.resize(3);
+        z.downcast_data_mut::<DBFloat>().unwrap().data_mut()[1] = -0.0;
+        let has = apply(&HasBuilder::new(), vec![&arr, &z]).unwrap();
+        assert_eq!(*has.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![false, true, true]);
+
+        let i = int_column(&[1, -1, 3]);
+        let elem = apply(&ArrayElementBuilder::new(), vec![&arr, &i]).unwrap();
+        assert_eq!(strings(&elem), vec!["1", "0", "0"]);
+
+        let mut s = Column::new(ColumnHeader::new("s", TypeName::DBString));
+        s.resize(3);
+        assert!(apply(&ArrayBuilder::new(), vec![&a, &s]).is_err());
+        assert!(apply(&HasBuilder::new(), vec![&arr, &s]).is_err());
+        assert!(apply(&ArrayElementBuilder::new(), vec![&arr, &b]).is_err());
+    }
+
+    #[test]
+    fn nullable_elements()
+    {
+        let mut a = Column::new(ColumnHeader::new("a", TypeName::DBInt.to_nullable()));
+        a.resize(2);
+        a.downcast_data_mut::<DBInt>().unwrap().data_mut()[0] = 7;
+        a.nulls_mut().unwrap()[1] = true;
+        let arr = apply(&ArrayBuilder::new(), vec![&a, &a]).unwrap();
+        assert_eq!(strings(&arr), vec!["[7, 7]", "[NULL, NULL]"]);
+
+        let i = int_column(&[3, 1]);
+        let elem = apply(&ArrayElementBuilder::new(), vec![&arr, &i]).unwrap();
+        assert_eq!(elem.type_name(), TypeName::DBInt.to_nullable());
+        assert_eq!(strings(&elem), vec!["NULL", "NULL"]);
+
+        let has = apply(&HasBuilder::new(), vec![&arr, &a]).unwrap();
+        assert_eq!(*has.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![true, true]);
+    }
+}
@@ -1,6 +1,67 @@
 use super::*;
 use crate::types::types::*;
+use super::cast::cast_column;
 use itertools::izip;
+
+//Three-valued logic: the absorbing value (false for and, true for or) wins over NULL,
+//otherwise NULL in any of the arguments gives NULL. Values under NULL are read as false
+fn apply_logical(src:Vec<&Column>, dest:&mut Column, absorbing:bool) -> DBResult<()>
+{
+    assert_eq!(src.len(), 2);
+    //NULL literal is Nullable(Bool) with all rows NULL
+    if let Some(pos) = src.iter().position(|col| *col.type_name().base() == TypeName::DBNothing)
+    {
+        let casted = cast_column(src[pos], TypeName::DBBool)?;
+        let mut args:Vec<&Column> = src.to_vec();
+        args[pos] = &casted;
+        return apply_logical(args, dest, absorbing);
+    }
+
+    let l_it = src[0].downcast_data_iter::<DBBool>().unwrap();
+    let r_it = src[1].downcast_data_iter::<DBBool>().unwrap();
+    let mut nulls = vec![false; dest.len()];
+
+    let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
+
+    for (row, (l, r, d)) in izip!(l_it, r_it, dest_it).enumerate()
+    {
+        let l_null = src[0].is_null(row);
+        let r_null = src[1].is_null(row);
+        if (!l_null && *l == absorbing) || (!r_null && *r == absorbing)
+        {
+            *d = absorbing;
+        }
+        else if l_null || r_null
+        {
+            *d = false;
+            nulls[row] = true;
+        }
+        else
+        {
+            *d = !absorbing;
+        }
+    }
+    if let Some(dest_nulls) = dest.nulls_mut()
+    {
+        *dest_nulls = nulls;
+    }
+
+    Ok(())
+}
+
+//Bool, Nullable(Bool) if any of the arguments is. NULL literal is accepted as Nullable(Bool)
+fn logical_type(name:&str, src:&[TypeName]) -> DBResult<TypeName>
+{
+    assert_eq!(src.len(), 2);
+    if src.iter().all(|t| *t.base() == TypeName::DBBool || *t.base() == TypeName::DBNothing)
+    {
+        Ok(if src.iter().any(|t| t.is_nullable()) {TypeName::DBBool.to_nullable()} else {TypeName::DBBool})
+    }
+    else
+    {
+        Err(format!("{} operation unsupported for {} and {}", name, src[0], src[1]))
+    }
+}
 /*============= And ===============*/
 pub struct And<L, R>
 {
@@ -21,26 +82,14 @@ impl<L, R> And<L, R>
 
 impl<L, R> OpResult for And<L, R>
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
-impl RegFunction for And<DBInt, DBInt>
+impl RegFunction for And<DBBool, DBBool>
 {
     fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
     {
-        assert_eq!(src.len(), 2);
-
-        let l_it = src[0].downcast_data_iter::<DBInt>().unwrap();
-        let r_it = src[1].downcast_data_iter::<DBInt>().unwrap();
-
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
-
-        for (l, r, d) in izip!(l_it, r_it, dest_it)
-        {
-            *d = if (*l == 1) && (*r == 1) {1} else {0};
-        }
-
-        Ok(())
+        apply_logical(src, dest, false)
     }
     fn to_string(&self, src:Vec<String>) -> String
     {
@@ -62,32 +111,16 @@ impl RegFunctionBuilder for AndBuilder {
 
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
-        assert_eq!(src.len(), 2);
-        let err_str = format!("and operation unsupported for {} and {}", src[0], src[1]);
-        match src[0] {
-            TypeName::DBInt => {
-                match src[1] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
-                    _ => Err(err_str)
-                }
-            },
-            _ => Err(err_str)
-        }
+        logical_type("and", &src)
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
     {
-        assert_eq!(src.len(), 2);
-        let err_str = format!("and operation unsupported for {} and {}", src[0], src[1]);
-        match src[0] {
-            TypeName::DBInt => {
-                match src[1] {
-                    TypeName::DBInt => Ok(Box::new(And::<DBInt, DBInt>::new())),
-                    _ => Err(err_str)
-                }
-            },
-            _ => Err(err_str)
-        }
-
+        logical_type("and", &src)?;
+        Ok(Box::new(And::<DBBool, DBBool>::new()))
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
     }
 }
 
@@ -111,26 +144,14 @@ impl<L, R> Or<L, R>
 
 impl<L, R> OpResult for Or<L, R>
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
-impl RegFunction for Or<DBInt, DBInt>
+impl RegFunction for Or<DBBool, DBBool>
 {
     fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
     {
-        assert_eq!(src.len(), 2);
-
-        let l_it = src[0].downcast_data_iter::<DBInt>().unwrap();
-        let r_it = src[1].downcast_data_iter::<DBInt>().unwrap();
-
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
-
-        for (l, r, d) in izip!(l_it, r_it, dest_it)
-        {
-            *d = if (*l == 1) || (*r == 1) {1} else {0};
-        }
-
-        Ok(())
+        apply_logical(src, dest, true)
     }
     fn to_string(&self, src:Vec<String>) -> String
     {
@@ -152,32 +173,16 @@ impl RegFunctionBuilder for OrBuilder {
 
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
-        assert_eq!(src.len(), 2);
-        let err_str = format!("or operation unsupported for {} and {}", src[0], src[1]);
-        match src[0] {
-            TypeName::DBInt => {
-                match src[1] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
-                    _ => Err(err_str)
-                }
-            },
-            _ => Err(err_str)
-        }
+        logical_type("or", &src)
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
     {
-        assert_eq!(src.len(), 2);
-        let err_str = format!("and operation unsupported for {} and {}", src[0], src[1]);
-        match src[0] {
-            TypeName::DBInt => {
-                match src[1] {
-                    TypeName::DBInt => Ok(Box::new(Or::<DBInt, DBInt>::new())),
-                    _ => Err(err_str)
-                }
-            },
-            _ => Err(err_str)
-        }
-
+        logical_type("or", &src)?;
+        Ok(Box::new(Or::<DBBool, DBBool>::new()))
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
     }
 }
 
@@ -197,7 +202,7 @@ impl Not
 
 impl OpResult for Not
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
 impl RegFunction for Not
@@ -206,13 +211,13 @@ impl RegFunction for Not
     {
         assert_eq!(src.len(), 1);
 
-        let it = src[0].downcast_data_iter::<DBInt>().unwrap();
+        let it = src[0].downcast_data_iter::<DBBool>().unwrap();
 
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
 
         for (s, d) in izip!(it, dest_it)
         {
-            *d = if *s == 0  {1} else {0};
+            *d = !*s;
         }
 
         Ok(())
@@ -240,9 +245,9 @@ impl RegFunctionBuilder for NotBuilder {
         assert_eq!(src.len(), 1);
         let err_str = format!("not operation unsupported for {}", src[0]);
         match src[0] {
-            TypeName::DBInt => {
+            TypeName::DBBool => {
                 match src[0] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
+                    TypeName::DBBool => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             },
@@ -254,7 +259,7 @@ impl RegFunctionBuilder for NotBuilder {
         assert_eq!(src.len(), 1);
         let err_str = format!("not operation unsupported for {}", src[0]);
         match src[0] {
-            TypeName::DBInt =>  Ok(Box::new(Not::new())),
+            TypeName::DBBool =>  Ok(Box::new(Not::new())),
             _ => Err(err_str)
         }
 
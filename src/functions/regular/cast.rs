@@ -0,0 +1,412 @@
+use super::*;
+use crate::types::types::*;
+use crate::columns::header::ColumnHeader;
+use itertools::izip;
+use std::str::FromStr;
+use crate::types::time::{Date, DateTime};
+use super::decimal::{cast_decimal, common_decimal};
+use crate::columns::low_cardinality::{downcast_low_cardinality_ref, downcast_low_cardinality_mut};
+use crate::columns::array::{downcast_array_ref, downcast_array_mut};
+
+fn convert<F:DBType, T:DBType>(src:&Column, dest:&mut Column, f:impl Fn(&F::InnerType) -> DBResult<T::InnerType>) -> DBResult<()>
+{
+    let src_it = src.downcast_data_iter::<F>().unwrap();
+    let dest_it = dest.downcast_data_iter_mut::<T>().unwrap();
+    //Values under NULL are left default
+    for (row, (s, d)) in izip!(src_it, dest_it).enumerate()
+    {
+        if !src.is_null(row)
+        {
+            *d = f(s)?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_string<V:FromStr>(s:&str, to:TypeName) -> DBResult<V>
+{
+    s.trim().parse::<V>().map_err(|_| format!("Can't cast '{}' to {}", s, to))
+}
+
+//Float is truncated to the integer type, i128 holds the values of all of them
+fn float_to_int<T:DBType>(src:&Column, dest:&mut Column) -> DBResult<()>
+where T::InnerType:TryFrom<i128>
+{
+    convert::<DBFloat, T>(src, dest, |v| {
+        let t = v.trunc();
+        let value = if t.is_finite() && t >= i128::MIN as f64 && t < i128::MAX as f64 {T::InnerType::try_from(t as i128).ok()} else {None};
+        value.ok_or_else(|| format!("Can't cast {} to {}: out of range", v, T::NAME))
+    })
+}
+
+fn widen<F:DBType>(src:&Column, dest:&mut Column) -> DBResult<()>
+where F::InnerType:Copy, i64:TryFrom<F::InnerType>
+{
+    convert::<F, DBInt>(src, dest, |v| i64::try_from(*v).map_err(|_| format!("Can't cast {} to Int: out of range", v.to_string())))
+}
+
+fn narrow<T:DBType>(src:&Column, dest:&mut Column) -> DBResult<()>
+where T::InnerType:TryFrom<i64>
+{
+    convert::<DBInt, T>(src, dest, |v| T::InnerType::try_from(*v).map_err(|_| format!("Can't cast {} to {}: out of range", v, T::NAME)))
+}
+
+//Int is the number of days for Date and of seconds for DateTime
+fn int_to_date(v:i64) -> DBResult<Date>
+{
+    i32::try_from(v).map(Date).map_err(|_| format!("Can't cast {} to Date: out of range", v))
+}
+
+//Writes the values of src converted to the type of dest, Float is truncated to Int, non zero Int is true.
+//NULL can be cast only to Nullable type
+pub fn cast_to(src:&Column, dest:&mut Column) -> DBResult<()>
+{
+    if !dest.type_name().is_nullable() && src.nulls().is_some_and(|nulls| nulls.contains(&true))
+    {
+        return Err(format!("Can't cast NULL to {}", dest.type_name()));
+    }
+    match (src.type_name().base().clone(), dest.type_name().base().clone()) {
+        (from, to) if from == to => {
+            src.copy_to(dest, 0);
+            return Ok(());
+        },
+        (TypeName::DBNothing, _) => Ok(()),
+        (TypeName::DBInt, TypeName::DBFloat) => convert::<DBInt, DBFloat>(src, dest, |v| Ok(*v as f64)),
+        (TypeName::DBFloat, TypeName::DBInt) => float_to_int::<DBInt>(src, dest),
+        (TypeName::DBFloat, TypeName::DBUInt64) => float_to_int::<DBUInt64>(src, dest),
+        (TypeName::DBUInt64, TypeName::DBFloat) => convert::<DBUInt64, DBFloat>(src, dest, |v| Ok(*v as f64)),
+        (TypeName::DBInt, TypeName::DBString) => convert::<DBInt, DBString>(src, dest, |v| Ok(v.to_string())),
+        (TypeName::DBFloat, TypeName::DBString) => convert::<DBFloat, DBString>(src, dest, |v| Ok(v.to_string())),
+        (TypeName::DBString, TypeName::DBInt) => convert::<DBString, DBInt>(src, dest, |v| parse_string(v, TypeName::DBInt)),
+        (TypeName::DBString, TypeName::DBFloat) => convert::<DBString, DBFloat>(src, dest, |v| parse_string(v, TypeName::DBFloat)),
+        (TypeName::DBBool, TypeName::DBInt) => convert::<DBBool, DBInt>(src, dest, |v| Ok(*v as i64)),
+        (TypeName::DBInt, TypeName::DBBool) => convert::<DBInt, DBBool>(src, dest, |v| Ok(*v != 0)),
+        (TypeName::DBBool, TypeName::DBString) => convert::<DBBool, DBString>(src, dest, |v| Ok(v.to_string())),
+        (TypeName::DBString, TypeName::DBBool) => convert::<DBString, DBBool>(src, dest, |v| parse_string(v, TypeName::DBBool)),
+        (TypeName::DBString, TypeName::DBDate) => convert::<DBString, DBDate>(src, dest, |v| parse_string(v, TypeName::DBDate)),
+        (TypeName::DBString, TypeName::DBDateTime) => convert::<DBString, DBDateTime>(src, dest, |v| parse_string(v, TypeName::DBDateTime)),
+        (TypeName::DBDate, TypeName::DBString) => convert::<DBDate, DBString>(src, dest, |v| Ok(v.to_string())),
+        (TypeName::DBDateTime, TypeName::DBString) => convert::<DBDateTime, DBString>(src, dest, |v| Ok(v.to_string())),
+        (TypeName::DBDate, TypeName::DBDateTime) => convert::<DBDate, DBDateTime>(src, dest, |v| Ok(DateTime::from_date(*v))),
+        (TypeName::DBDateTime, TypeName::DBDate) => convert::<DBDateTime, DBDate>(src, dest, |v| Ok(v.date())),
+        (TypeName::DBDate, TypeName::DBInt) => convert::<DBDate, DBInt>(src, dest, |v| Ok(v.0 as i64)),
+        (TypeName::DBInt, TypeName::DBDate) => convert::<DBInt, DBDate>(src, dest, |v| int_to_date(*v)),
+        (TypeName::DBDateTime, TypeName::DBInt) => convert::<DBDateTime, DBInt>(src, dest, |v| Ok(v.0)),
+        (TypeName::DBInt, TypeName::DBDateTime) => convert::<DBInt, DBDateTime>(src, dest, |v| Ok(DateTime(*v))),
+        (TypeName::DBInt8, TypeName::DBInt) => widen::<DBInt8>(src, dest),
+        (TypeName::DBInt16, TypeName::DBInt) => widen::<DBInt16>(src, dest),
+        (TypeName::DBInt32, TypeName::DBInt) => widen::<DBInt32>(src, dest),
+        (TypeName::DBUInt8, TypeName::DBInt) => widen::<DBUInt8>(src, dest),
+        (TypeName::DBUInt16, TypeName::DBInt) => widen::<DBUInt16>(src, dest),
+        (TypeName::DBUInt32, TypeName::DBInt) => widen::<DBUInt32>(src, dest),
+        (TypeName::DBUInt64, TypeName::DBInt) => widen::<DBUInt64>(src, dest),
+        (TypeName::DBInt, TypeName::DBInt8) => narrow::<DBInt8>(src, dest),
+        (TypeName::DBInt, TypeName::DBInt16) => narrow::<DBInt16>(src, dest),
+        (TypeName::DBInt, TypeName::DBInt32) => narrow::<DBInt32>(src, dest),
+        (TypeName::DBInt, TypeName::DBUInt8) => narrow::<DBUInt8>(src, dest),
+        (TypeName::DBInt, TypeName::DBUInt16) => narrow::<DBUInt16>(src, dest),
+        (TypeName::DBInt, TypeName::DBUInt32) => narrow::<DBUInt32>(src, dest),
+        (TypeName::DBInt, TypeName::DBUInt64) => narrow::<DBUInt64>(src, dest),
+        (TypeName::DBLowCardinality, TypeName::DBString) => {
+            let lc = downcast_low_cardinality_ref(src.data_ref().as_ref()).unwrap();
+            for (s, d) in izip!(lc.codes().iter(), dest.downcast_data_iter_mut::<DBString>().unwrap())
+            {
+                d.clone_from(&lc.dict()[*s as usize]);
+            }
+            Ok(())
+        },
+        (TypeName::DBString, TypeName::DBLowCardinality) => {
+            let lc = downcast_low_cardinality_mut(dest.data_mut().as_mut()).unwrap();
+            for (row, s) in src.downcast_data_iter::<DBString>().unwrap().enumerate()
+            {
+                lc.set_value(row, s);
+            }
+            Ok(())
+        },
+        (TypeName::DBArray(from), TypeName::DBArray(to)) => cast_array(src, dest, *from, *to),
+        //Other casts of LowCardinality go through String
+        (TypeName::DBLowCardinality, _) => return cast_to(&cast_column(src, TypeName::DBString)?, dest),
+        (_, TypeName::DBLowCardinality) => return cast_to(&cast_column(src, TypeName::DBString)?, dest),
+        (TypeName::DBDecimal(..), other) | (other, TypeName::DBDecimal(..)) if !other.is_integer() || other == TypeName::DBInt => cast_decimal(src, dest),
+        //Other casts of the narrower integers go through Int
+        (from, _) if from.is_integer() && from != TypeName::DBInt => return cast_to(&cast_column(src, TypeName::DBInt)?, dest),
+        (from, to) if to.is_integer() && from != TypeName::DBInt => return cast_to(&cast_column(src, TypeName::DBInt)?, dest),
+        (from, to) => Err(format!("Can't cast {} to {}", from, to))
+    }?;
+    if let Some(dest_nulls) = dest.nulls_mut()
+    {
+        *dest_nulls = src.nulls().cloned().unwrap_or_else(|| vec![false; src.len()]);
+    }
+    Ok(())
+}
+
+//The elements are cast, the arrays keep their lengths
+fn cast_array(src:&Column, dest:&mut Column, from:TypeName, to:TypeName) -> DBResult<()>
+{
+    let arr = downcast_array_ref(src.data_ref().as_ref()).unwrap();
+    let mut values = Column::new(ColumnHeader::new("", from));
+    values.resize(arr.values().len());
+    arr.values().copy_to(values.data_mut(), 0);
+    let mut casted = Column::new(ColumnHeader::new("", to));
+    casted.resize(values.len());
+    cast_to(&values, &mut casted)?;
+    let dest_arr = downcast_array_mut(dest.data_mut().as_mut()).unwrap();
+    dest_arr.offsets_mut().clone_from(arr.offsets());
+    std::mem::swap(dest_arr.values_mut(), casted.data_mut());
+    Ok(())
+}
+
+//Nullable column stays Nullable
+pub fn cast_column(col:&Column, to:TypeName) -> DBResult<Column>
+{
+    let to = if col.type_name().is_nullable() {to.to_nullable()} else {to};
+    let mut res = Column::new(ColumnHeader::new(col.name(), to));
+    res.resize(col.len());
+    cast_to(col, &mut res)?;
+    Ok(res)
+}
+
+//Int arguments mixed with Float ones are promoted to Float. The functions are implemented for Int, UInt64 and Float,
+//so the narrower integers are widened to Int, as is UInt64 mixed with signed ones (above the range of Int it fails to cast).
+//Decimals are promoted to Float only if mixed with Float, otherwise the decimal functions take them
+pub fn promote_numeric(src:&[TypeName]) -> Option<Vec<TypeName>>
+{
+    let is_decimal = |t:&TypeName| matches!(t, TypeName::DBDecimal(..));
+    if !src.iter().all(|t| t.is_integer() || is_decimal(t) || *t == TypeName::DBFloat)
+    {
+        return None;
+    }
+    if src.iter().any(is_decimal) && !src.contains(&TypeName::DBFloat)
+    {
+        return None;
+    }
+    let common = if src.contains(&TypeName::DBFloat)
+    {
+        TypeName::DBFloat
+    }
+    else if src.iter().all(|t| *t == TypeName::DBUInt64)
+    {
+        TypeName::DBUInt64
+    }
+    else
+    {
+        TypeName::DBInt
+    };
+    if src.iter().all(|t| *t == common)
+    {
+        None
+    }
+    else
+    {
+        Some(vec![common; src.len()])
+    }
+}
+
+//Comparable arguments of different types: numbers as above, decimals of different scales and integers as the common decimal,
+//and dates with each other or with strings, e.g. d < '2024-01-01' compares Dates
+pub fn promote_comparable(src:&[TypeName]) -> Option<Vec<TypeName>>
+{
+    if let Some(common) = common_decimal(src)
+    {
+        return if src.iter().all(|t| *t == common) {None} else {Some(vec![common; src.len()])};
+    }
+    let is_temporal = |t:&TypeName| *t == TypeName::DBDate || *t == TypeName::DBDateTime;
+    let temporal = src.iter().any(is_temporal) && src.iter().all(|t| is_temporal(t) || *t == TypeName::DBString);
+    if !temporal
+    {
+        return promote_numeric(src);
+    }
+    let common = if src.contains(&TypeName::DBDateTime) {TypeName::DBDateTime} else {TypeName::DBDate};
+    if src.iter().all(|t| *t == common)
+    {
+        None
+    }
+    else
+    {
+        Some(vec![common; src.len()])
+    }
+}
+
+/* ======== Promoted ====== */
+
+//Casts the arguments to the types the wrapped function is built for
+pub struct Promoted
+{
+    func :RegFunctionRef,
+    to :Vec<TypeName>,
+}
+
+impl Promoted
+{
+    pub fn new_ref(func:RegFunctionRef, to:Vec<TypeName>) -> RegFunctionRef
+    {
+        Box::new(Self{func, to})
+    }
+}
+
+impl RegFunction for Promoted
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), self.to.len());
+        let casted:Vec<Option<Column>> = src.iter().zip(self.to.iter()).map(
+            |(col, to)| {
+                if col.type_name() != *to {Some(cast_column(col, to.clone()))} else {None}
+            }.transpose()
+        ).collect::<DBResult<_>>()?;
+        let args:Vec<&Column> = src.iter().zip(casted.iter()).map(
+            |(col, c)| c.as_ref().unwrap_or(col)
+        ).collect();
+        self.func.apply(args, dest)
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        self.func.to_string(src)
+    }
+}
+
+//Builds the function of the wrapped builder for the arguments promoted to their common type, e.g. by promote_numeric
+pub struct PromotedBuilder
+{
+    inner :RegFunctionBuilderRef,
+    promote :fn(&[TypeName]) -> Option<Vec<TypeName>>,
+}
+
+impl PromotedBuilder
+{
+    pub fn new_ref(inner:RegFunctionBuilderRef, promote:fn(&[TypeName]) -> Option<Vec<TypeName>>) -> RegFunctionBuilderRef
+    {
+        Box::new(Self{inner, promote})
+    }
+}
+
+impl RegFunctionBuilder for PromotedBuilder
+{
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        match (self.promote)(&src) {
+            Some(promoted) => self.inner.result_type(promoted),
+            None => self.inner.result_type(src)
+        }
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<RegFunctionRef>
+    {
+        match (self.promote)(&src) {
+            Some(promoted) => Ok(Promoted::new_ref(self.inner.build(promoted.clone())?, promoted)),
+            None => self.inner.build(src)
+        }
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        self.inner.handles_nulls()
+    }
+    fn handles_low_cardinality(&self) -> bool
+    {
+        self.inner.handles_low_cardinality()
+    }
+}
+
+/* ======== Cast ====== */
+
+//CAST(x AS type) and x::type
+pub struct Cast
+{
+    to :TypeName,
+}
+
+impl RegFunction for Cast
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        cast_to(src[0], dest)
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 1);
+        format!("CAST({} AS {})", src[0], self.to)
+    }
+}
+
+pub struct CastBuilder
+{
+    to :TypeName,
+}
+
+impl CastBuilder {
+    pub fn new(to:TypeName) -> Self {Self{to}}
+    pub fn new_ref(to:TypeName) -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new(to))
+    }
+}
+
+impl RegFunctionBuilder for CastBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() != 1
+        {
+            return Err(format!("CAST expects 1 argument, got {}", src.len()));
+        }
+        Ok(self.to.clone())
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src)?;
+        Ok(Box::new(Cast{to:self.to.clone()}))
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+    fn handles_low_cardinality(&self) -> bool
+    {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cast_strings()
+    {
+        let mut src = Column::new(ColumnHeader::new("src", TypeName::DBString));
+        src.resize(3);
+        src.downcast_data_mut::<DBString>().unwrap().data_mut().clone_from_slice(&["1".to_string(), " 2 ".to_string(), "-3".to_string()]);
+
+        let ints = cast_column(&src, TypeName::DBInt).unwrap();
+        assert_eq!(*ints.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, 2, -3]);
+        let floats = cast_column(&ints, TypeName::DBFloat).unwrap();
+        assert_eq!(*floats.downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![1., 2., -3.]);
+        let strings = cast_column(&floats, TypeName::DBString).unwrap();
+        assert_eq!(*strings.downcast_data_ref::<DBString>().unwrap().data_ref(), vec!["1", "2", "-3"]);
+
+        src.downcast_data_mut::<DBString>().unwrap().data_mut()[1] = "abc".to_string();
+        assert_eq!(cast_column(&src, TypeName::DBInt).err(), Some("Can't cast 'abc' to Int".to_string()));
+
+        let mut big = Column::new(ColumnHeader::new("big", TypeName::DBFloat));
+        big.resize(1);
+        big.downcast_data_mut::<DBFloat>().unwrap().data_mut()[0] = 1e30;
+        assert!(cast_column(&big, TypeName::DBInt).is_err());
+        assert_eq!(cast_column(&big, TypeName::DBUInt64).err(), Some("Can't cast 1000000000000000000000000000000 to UInt64: out of range".to_string()));
+        big.downcast_data_mut::<DBFloat>().unwrap().data_mut()[0] = 1e19;
+        let unsigned = cast_column(&big, TypeName::DBUInt64).unwrap();
+        assert_eq!(*unsigned.downcast_data_ref::<DBUInt64>().unwrap().data_ref(), vec![10_000_000_000_000_000_000]);
+    }
+
+    #[test]
+    fn promotion()
+    {
+        assert_eq!(promote_numeric(&[TypeName::DBInt, TypeName::DBFloat]), Some(vec![TypeName::DBFloat, TypeName::DBFloat]));
+        assert_eq!(promote_numeric(&[TypeName::DBInt, TypeName::DBInt]), None);
+        assert_eq!(promote_numeric(&[TypeName::DBUInt64, TypeName::DBUInt64]), None);
+        assert_eq!(promote_numeric(&[TypeName::DBUInt64, TypeName::DBUInt8]), Some(vec![TypeName::DBInt, TypeName::DBInt]));
+        assert_eq!(promote_numeric(&[TypeName::DBString, TypeName::DBFloat]), None);
+        assert_eq!(promote_numeric(&[TypeName::DBDecimal(5, 2), TypeName::DBInt]), None);
+        assert_eq!(promote_numeric(&[TypeName::DBDecimal(5, 2), TypeName::DBFloat]), Some(vec![TypeName::DBFloat, TypeName::DBFloat]));
+        assert_eq!(promote_comparable(&[TypeName::DBDecimal(5, 2), TypeName::DBInt]), Some(vec![TypeName::DBDecimal(21, 2); 2]));
+    }
+}
@@ -1,6 +1,9 @@
 use super::*;
 use crate::types::DBType;
 use crate::types::types::*;
+use super::cast::{promote_comparable, PromotedBuilder};
+use super::low_cardinality::{is_low_cardinality_args, LowCardinalityEqualsBuilder};
+use crate::types::decimal::MAX_PRECISION_64;
 use itertools::izip;
 use std::cmp::*;
 /*============= Equal ===============*/
@@ -23,7 +26,7 @@ impl<L, R> Equal<L, R>
 
 impl<L, R> OpResult for Equal<L, R>
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
 impl<L:DBType, R:DBType> RegFunction for Equal<L, R>
@@ -36,11 +39,11 @@ where L::InnerType:PartialEq<R::InnerType>
         let l_it = src[0].downcast_data_iter::<L>().unwrap();
         let r_it = src[1].downcast_data_iter::<R>().unwrap();
 
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = if l == r {1} else {0};
+            *d = l == r;
         }
 
         Ok(())
@@ -58,7 +61,7 @@ impl EqualBuilder {
     pub fn new() -> EqualBuilder {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_comparable)
     }
 }
 impl RegFunctionBuilder for EqualBuilder {
@@ -66,32 +69,70 @@ impl RegFunctionBuilder for EqualBuilder {
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
         assert_eq!(src.len(), 2);
+        if is_low_cardinality_args(&src)
+        {
+            return LowCardinalityEqualsBuilder::new(false).result_type(src);
+        }
         let err_str = format!("== operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
                 match src[1] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
+                    TypeName::DBInt => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             }
             TypeName::DBFloat => {
                 match src[1] {
-                    TypeName::DBFloat => Ok(TypeName::DBInt),
+                    TypeName::DBFloat => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             },
             TypeName::DBString => {
                 match src[1] {
-                    TypeName::DBString => Ok(TypeName::DBInt),
+                    TypeName::DBString => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
-           }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(..) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
     {
         assert_eq!(src.len(), 2);
+        if is_low_cardinality_args(&src)
+        {
+            return LowCardinalityEqualsBuilder::new(false).build(src);
+        }
         let err_str = format!("== operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -100,6 +141,12 @@ impl RegFunctionBuilder for EqualBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(Equal::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(Equal::<DBFloat, DBFloat>::new())),
@@ -111,11 +158,40 @@ impl RegFunctionBuilder for EqualBuilder {
                     TypeName::DBString => Ok(Box::new(Equal::<DBString, DBString>::new())),
                     _ => Err(err_str)
                 }
-            }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(Box::new(Equal::<DBBool, DBBool>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(Box::new(Equal::<DBDate, DBDate>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(Box::new(Equal::<DBDateTime, DBDateTime>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(precision, _) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] && precision <= MAX_PRECISION_64 => Ok(Box::new(Equal::<DBDecimal64, DBDecimal64>::new())),
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(Box::new(Equal::<DBDecimal128, DBDecimal128>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
 
     }
+    fn handles_low_cardinality(&self) -> bool
+    {
+        true
+    }
 }
 
 /* ============ NotEqual ============= */
@@ -139,7 +215,7 @@ impl<L, R> NotEqual<L, R>
 
 impl<L, R> OpResult for NotEqual<L, R>
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
 impl<L:DBType, R:DBType> RegFunction for NotEqual<L, R>
@@ -152,11 +228,11 @@ where L::InnerType:PartialEq<R::InnerType>
         let l_it = src[0].downcast_data_iter::<L>().unwrap();
         let r_it = src[1].downcast_data_iter::<R>().unwrap();
 
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = if l != r {1} else {0};
+            *d = l != r;
         }
 
         Ok(())
@@ -174,7 +250,7 @@ impl NotEqualBuilder {
     pub fn new() -> Self {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_comparable)
     }
 }
 impl RegFunctionBuilder for NotEqualBuilder {
@@ -182,32 +258,70 @@ impl RegFunctionBuilder for NotEqualBuilder {
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
     {
         assert_eq!(src.len(), 2);
+        if is_low_cardinality_args(&src)
+        {
+            return LowCardinalityEqualsBuilder::new(true).result_type(src);
+        }
         let err_str = format!("!= operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
                 match src[1] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
+                    TypeName::DBInt => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             }
             TypeName::DBFloat => {
                 match src[1] {
-                    TypeName::DBFloat => Ok(TypeName::DBInt),
+                    TypeName::DBFloat => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             },
             TypeName::DBString => {
                 match src[1] {
-                    TypeName::DBString => Ok(TypeName::DBInt),
+                    TypeName::DBString => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
-           }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(..) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
     {
         assert_eq!(src.len(), 2);
+        if is_low_cardinality_args(&src)
+        {
+            return LowCardinalityEqualsBuilder::new(true).build(src);
+        }
         let err_str = format!("!= operation unsupported for {} and {}", src[0], src[1]);
         match src[0] {
             TypeName::DBInt => {
@@ -216,6 +330,12 @@ impl RegFunctionBuilder for NotEqualBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(NotEqual::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(NotEqual::<DBFloat, DBFloat>::new())),
@@ -227,11 +347,40 @@ impl RegFunctionBuilder for NotEqualBuilder {
                     TypeName::DBString => Ok(Box::new(NotEqual::<DBString, DBString>::new())),
                     _ => Err(err_str)
                 }
-            }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(Box::new(NotEqual::<DBBool, DBBool>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(Box::new(NotEqual::<DBDate, DBDate>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(Box::new(NotEqual::<DBDateTime, DBDateTime>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(precision, _) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] && precision <= MAX_PRECISION_64 => Ok(Box::new(NotEqual::<DBDecimal64, DBDecimal64>::new())),
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(Box::new(NotEqual::<DBDecimal128, DBDecimal128>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
 
     }
+    fn handles_low_cardinality(&self) -> bool
+    {
+        true
+    }
 }
 
 /* ======== Less ====== */
@@ -255,7 +404,7 @@ impl<L, R> Less<L, R>
 
 impl<L, R> OpResult for Less<L, R>
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
 impl<L:DBType, R:DBType> RegFunction for Less<L, R>
@@ -268,11 +417,11 @@ where L::InnerType:PartialOrd<R::InnerType>
         let l_it = src[0].downcast_data_iter::<L>().unwrap();
         let r_it = src[1].downcast_data_iter::<R>().unwrap();
 
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = if l < r {1} else {0};
+            *d = l < r;
         }
 
         Ok(())
@@ -290,7 +439,7 @@ impl LessBuilder {
     pub fn new() -> Self {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_comparable)
     }
 }
 impl RegFunctionBuilder for LessBuilder {
@@ -302,23 +451,53 @@ impl RegFunctionBuilder for LessBuilder {
         match src[0] {
             TypeName::DBInt => {
                 match src[1] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
+                    TypeName::DBInt => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             }
             TypeName::DBFloat => {
                 match src[1] {
-                    TypeName::DBFloat => Ok(TypeName::DBInt),
+                    TypeName::DBFloat => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             },
             TypeName::DBString => {
                 match src[1] {
-                    TypeName::DBString => Ok(TypeName::DBInt),
+                    TypeName::DBString => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
-           }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(..) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
@@ -332,6 +511,12 @@ impl RegFunctionBuilder for LessBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(Less::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(Less::<DBFloat, DBFloat>::new())),
@@ -343,8 +528,33 @@ impl RegFunctionBuilder for LessBuilder {
                     TypeName::DBString => Ok(Box::new(Less::<DBString, DBString>::new())),
                     _ => Err(err_str)
                 }
-            }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(Box::new(Less::<DBBool, DBBool>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(Box::new(Less::<DBDate, DBDate>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(Box::new(Less::<DBDateTime, DBDateTime>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(precision, _) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] && precision <= MAX_PRECISION_64 => Ok(Box::new(Less::<DBDecimal64, DBDecimal64>::new())),
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(Box::new(Less::<DBDecimal128, DBDecimal128>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
 
     }
@@ -371,7 +581,7 @@ impl<L, R> LessEqual<L, R>
 
 impl<L, R> OpResult for LessEqual<L, R>
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
 impl<L:DBType, R:DBType> RegFunction for LessEqual<L, R>
@@ -384,11 +594,11 @@ where L::InnerType:PartialOrd<R::InnerType>
         let l_it = src[0].downcast_data_iter::<L>().unwrap();
         let r_it = src[1].downcast_data_iter::<R>().unwrap();
 
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = if l <= r {1} else {0};
+            *d = l <= r;
         }
 
         Ok(())
@@ -406,7 +616,7 @@ impl LessEqualBuilder {
     pub fn new() -> Self {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_comparable)
     }
 }
 impl RegFunctionBuilder for LessEqualBuilder {
@@ -418,23 +628,53 @@ impl RegFunctionBuilder for LessEqualBuilder {
         match src[0] {
             TypeName::DBInt => {
                 match src[1] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
+                    TypeName::DBInt => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             }
             TypeName::DBFloat => {
                 match src[1] {
-                    TypeName::DBFloat => Ok(TypeName::DBInt),
+                    TypeName::DBFloat => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             },
             TypeName::DBString => {
                 match src[1] {
-                    TypeName::DBString => Ok(TypeName::DBInt),
+                    TypeName::DBString => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
-           }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(..) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
@@ -448,6 +688,12 @@ impl RegFunctionBuilder for LessEqualBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(LessEqual::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(LessEqual::<DBFloat, DBFloat>::new())),
@@ -459,8 +705,33 @@ impl RegFunctionBuilder for LessEqualBuilder {
                     TypeName::DBString => Ok(Box::new(LessEqual::<DBString, DBString>::new())),
                     _ => Err(err_str)
                 }
-            }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(Box::new(LessEqual::<DBBool, DBBool>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(Box::new(LessEqual::<DBDate, DBDate>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(Box::new(LessEqual::<DBDateTime, DBDateTime>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(precision, _) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] && precision <= MAX_PRECISION_64 => Ok(Box::new(LessEqual::<DBDecimal64, DBDecimal64>::new())),
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(Box::new(LessEqual::<DBDecimal128, DBDecimal128>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
 
     }
@@ -487,7 +758,7 @@ impl<L, R> Greater<L, R>
 
 impl<L, R> OpResult for Greater<L, R>
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
 impl<L:DBType, R:DBType> RegFunction for Greater<L, R>
@@ -500,11 +771,11 @@ where L::InnerType:PartialOrd<R::InnerType>
         let l_it = src[0].downcast_data_iter::<L>().unwrap();
         let r_it = src[1].downcast_data_iter::<R>().unwrap();
 
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = if l > r {1} else {0};
+            *d = l > r;
         }
 
         Ok(())
@@ -522,7 +793,7 @@ impl GreaterBuilder {
     pub fn new() -> Self {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_comparable)
     }
 }
 impl RegFunctionBuilder for GreaterBuilder {
@@ -534,23 +805,53 @@ impl RegFunctionBuilder for GreaterBuilder {
         match src[0] {
             TypeName::DBInt => {
                 match src[1] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
+                    TypeName::DBInt => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             }
             TypeName::DBFloat => {
                 match src[1] {
-                    TypeName::DBFloat => Ok(TypeName::DBInt),
+                    TypeName::DBFloat => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             },
             TypeName::DBString => {
                 match src[1] {
-                    TypeName::DBString => Ok(TypeName::DBInt),
+                    TypeName::DBString => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
-           }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(..) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
@@ -564,6 +865,12 @@ impl RegFunctionBuilder for GreaterBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(Greater::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(Greater::<DBFloat, DBFloat>::new())),
@@ -575,8 +882,33 @@ impl RegFunctionBuilder for GreaterBuilder {
                     TypeName::DBString => Ok(Box::new(Greater::<DBString, DBString>::new())),
                     _ => Err(err_str)
                 }
-            }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(Box::new(Greater::<DBBool, DBBool>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(Box::new(Greater::<DBDate, DBDate>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(Box::new(Greater::<DBDateTime, DBDateTime>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(precision, _) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] && precision <= MAX_PRECISION_64 => Ok(Box::new(Greater::<DBDecimal64, DBDecimal64>::new())),
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(Box::new(Greater::<DBDecimal128, DBDecimal128>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
 
     }
@@ -602,7 +934,7 @@ impl<L, R> GreaterEqual<L, R>
 
 impl<L, R> OpResult for GreaterEqual<L, R>
 {
-    type ResultType = DBInt;
+    type ResultType = DBBool;
 }
 
 impl<L:DBType, R:DBType> RegFunction for GreaterEqual<L, R>
@@ -615,11 +947,11 @@ where L::InnerType:PartialOrd<R::InnerType>
         let l_it = src[0].downcast_data_iter::<L>().unwrap();
         let r_it = src[1].downcast_data_iter::<R>().unwrap();
 
-        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
 
         for (l, r, d) in izip!(l_it, r_it, dest_it)
         {
-            *d = if l >= r {1} else {0};
+            *d = l >= r;
         }
 
         Ok(())
@@ -637,7 +969,7 @@ impl GreaterEqualBuilder {
     pub fn new() -> Self {Self{}}
     pub fn new_ref() -> Box<dyn RegFunctionBuilder>
     {
-        Box::new(Self::new())
+        PromotedBuilder::new_ref(Box::new(Self::new()), promote_comparable)
     }
 }
 impl RegFunctionBuilder for GreaterEqualBuilder {
@@ -649,23 +981,53 @@ impl RegFunctionBuilder for GreaterEqualBuilder {
         match src[0] {
             TypeName::DBInt => {
                 match src[1] {
-                    TypeName::DBInt => Ok(TypeName::DBInt),
+                    TypeName::DBInt => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             }
             TypeName::DBFloat => {
                 match src[1] {
-                    TypeName::DBFloat => Ok(TypeName::DBInt),
+                    TypeName::DBFloat => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
             },
             TypeName::DBString => {
                 match src[1] {
-                    TypeName::DBString => Ok(TypeName::DBInt),
+                    TypeName::DBString => Ok(TypeName::DBBool),
                     _ => Err(err_str)
                 }
-           }
-
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(..) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(TypeName::DBBool),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
         }
     }
     fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
@@ -679,6 +1041,12 @@ impl RegFunctionBuilder for GreaterEqualBuilder {
                     _ => Err(err_str)
                 }
             }
+            TypeName::DBUInt64 => {
+                match src[1] {
+                    TypeName::DBUInt64 => Ok(Box::new(GreaterEqual::<DBUInt64, DBUInt64>::new())),
+                    _ => Err(err_str)
+                }
+            }
             TypeName::DBFloat => {
                 match src[1] {
                     TypeName::DBFloat => Ok(Box::new(GreaterEqual::<DBFloat, DBFloat>::new())),
@@ -690,10 +1058,127 @@ impl RegFunctionBuilder for GreaterEqualBuilder {
                     TypeName::DBString => Ok(Box::new(GreaterEqual::<DBString, DBString>::new())),
                     _ => Err(err_str)
                 }
-            }
+            },
+            TypeName::DBBool => {
+                match src[1] {
+                    TypeName::DBBool => Ok(Box::new(GreaterEqual::<DBBool, DBBool>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDate => {
+                match src[1] {
+                    TypeName::DBDate => Ok(Box::new(GreaterEqual::<DBDate, DBDate>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDateTime => {
+                match src[1] {
+                    TypeName::DBDateTime => Ok(Box::new(GreaterEqual::<DBDateTime, DBDateTime>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            TypeName::DBDecimal(precision, _) => {
+                match src[1] {
+                    TypeName::DBDecimal(..) if src[1] == src[0] && precision <= MAX_PRECISION_64 => Ok(Box::new(GreaterEqual::<DBDecimal64, DBDecimal64>::new())),
+                    TypeName::DBDecimal(..) if src[1] == src[0] => Ok(Box::new(GreaterEqual::<DBDecimal128, DBDecimal128>::new())),
+                    _ => Err(err_str)
+                }
+            },
+            _ => Err(err_str)
+        }
+
+    }
+}
+
+/* ======== Between ====== */
+
+//low <= x <= high, all of the same type after the promotion of numbers and dates
+pub struct Between<T>
+{
+    negate :bool,
+    _t :std::marker::PhantomData<T>,
+}
 
+impl<T> Between<T>
+{
+    pub fn new(negate:bool) ->Self
+    {
+        Self{
+            negate,
+            _t:std::marker::PhantomData::<T>{},
         }
+    }
+}
 
+impl<T> OpResult for Between<T>
+{
+    type ResultType = DBBool;
+}
+
+impl<T:DBType> RegFunction for Between<T>
+where T::InnerType:PartialOrd
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 3);
+
+        let x_it = src[0].downcast_data_iter::<T>().unwrap();
+        let low_it = src[1].downcast_data_iter::<T>().unwrap();
+        let high_it = src[2].downcast_data_iter::<T>().unwrap();
+
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
+
+        for (x, low, high, d) in izip!(x_it, low_it, high_it, dest_it)
+        {
+            *d = (low <= x && x <= high) != self.negate;
+        }
+
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 3);
+        format!("{} {}BETWEEN {} AND {}", src[0], if self.negate {"NOT "} else {""}, src[1], src[2])
+    }
+}
+
+pub struct BetweenBuilder
+{
+    negate :bool,
+}
+
+impl BetweenBuilder {
+    pub fn new(negate:bool) -> Self {Self{negate}}
+    pub fn new_ref(negate:bool) -> Box<dyn RegFunctionBuilder>
+    {
+        PromotedBuilder::new_ref(Box::new(Self::new(negate)), promote_comparable)
+    }
+}
+impl RegFunctionBuilder for BetweenBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        assert_eq!(src.len(), 3);
+        if src[1] != src[0] || src[2] != src[0]
+        {
+            return Err(format!("BETWEEN operation unsupported for {}, {} and {}", src[0], src[1], src[2]));
+        }
+        Ok(TypeName::DBBool)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src.clone())?;
+        match &src[0] {
+            TypeName::DBInt => Ok(Box::new(Between::<DBInt>::new(self.negate))),
+            TypeName::DBFloat => Ok(Box::new(Between::<DBFloat>::new(self.negate))),
+            TypeName::DBString => Ok(Box::new(Between::<DBString>::new(self.negate))),
+            TypeName::DBBool => Ok(Box::new(Between::<DBBool>::new(self.negate))),
+            TypeName::DBDate => Ok(Box::new(Between::<DBDate>::new(self.negate))),
+            TypeName::DBDateTime => Ok(Box::new(Between::<DBDateTime>::new(self.negate))),
+            TypeName::DBDecimal(precision, _) if *precision <= MAX_PRECISION_64 => Ok(Box::new(Between::<DBDecimal64>::new(self.negate))),
+            TypeName::DBDecimal(..) => Ok(Box::new(Between::<DBDecimal128>::new(self.negate))),
+            other => Err(format!("BETWEEN operation unsupported for {}", other))
+        }
     }
 }
 
@@ -733,15 +1218,15 @@ mod test {
         let op = EqualBuilder::new().build(args).unwrap();
         op.apply(Vec::from([&r, &l]), &mut d).unwrap();
 
-        for (i, v) in d.downcast_data_iter::<DBInt>().unwrap().enumerate()
+        for (i, v) in d.downcast_data_iter::<DBBool>().unwrap().enumerate()
         {
             if i % 2 == 0
             {
-                assert_eq!(*v, 1);
+                assert!(*v);
             }
             else
             {
-                assert_eq!(*v, 0);
+                assert!(!*v);
             }
         }
     }
@@ -0,0 +1,456 @@
+use super::*;
+use crate::types::types::*;
+use super::cast::cast_column;
+use crate::columns::header::ColumnHeader;
+use super::decimal::common_decimal;
+use crate::types::decimal::MAX_PRECISION_64;
+
+//Common type of the branches: the same type, Int for different integers, the common decimal of decimals and integers,
+//or Float if numbers and Float are mixed. NULL fits any type, the result is Nullable if any of the branches is
+pub fn unify_types(types:&[TypeName]) -> DBResult<TypeName>
+{
+    let bases:Vec<&TypeName> = types.iter().map(|t| t.base()).filter(|t| **t != TypeName::DBNothing).collect();
+    let common = if bases.is_empty()
+    {
+        TypeName::DBNothing
+    }
+    else if bases.iter().all(|t| *t == bases[0])
+    {
+        bases[0].clone()
+    }
+    else if bases.iter().all(|t| t.is_integer())
+    {
+        TypeName::DBInt
+    }
+    else if bases.iter().all(|t| t.element().is_some())
+    {
+        //Arrays of the common element type, e.g. [] is Array(Nothing)
+        let elems:Vec<TypeName> = bases.iter().map(|t| t.element().unwrap().clone()).collect();
+        TypeName::DBArray(Box::new(unify_types(&elems)?))
+    }
+    else if let Some(common) = common_decimal(&bases.iter().map(|t| (*t).clone()).collect::<Vec<_>>())
+    {
+        common
+    }
+    else if bases.iter().all(|t| t.is_integer() || matches!(t, TypeName::DBDecimal(..)) || **t == TypeName::DBFloat)
+    {
+        TypeName::DBFloat
+    }
+    else
+    {
+        let names:Vec<String> = types.iter().map(|t| t.to_string()).collect();
+        return Err(format!("Branches have incompatible types: {}", names.join(", ")));
+    };
+    Ok(if types.iter().any(|t| t.is_nullable()) {common.to_nullable()} else {common})
+}
+
+/* ======== MultiIf ====== */
+
+//multiIf(cond1, then1, cond2, then2, ..., else): the value of the first branch whose condition is true,
+//NULL condition is false. Without else the rows matching no condition get the default value of the type
+pub struct MultiIf<T>
+{
+    _t :std::marker::PhantomData<T>,
+}
+
+impl<T> MultiIf<T>
+{
+    pub fn new() ->Self
+    {
+        Self{
+            _t:std::marker::PhantomData::<T>{},
+        }
+    }
+}
+
+impl<T:DBType> OpResult for MultiIf<T>
+{
+    type ResultType = T;
+}
+
+impl<T:DBType> RegFunction for MultiIf<T>
+where T::InnerType:Default
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert!(src.len() >= 2);
+
+        let is_value = |i:usize| i % 2 == 1 || i == src.len() - 1;
+        //NULL literals have no values to cast. The type of dest and not T::NAME, since decimals differ by the scale
+        let to = dest.type_name().base().clone();
+        let promoted:Vec<Option<Column>> = src.iter().enumerate().map(
+            |(i, col)| {
+                let base = col.type_name().base().clone();
+                if is_value(i) && base != to && base != TypeName::DBNothing {Some(cast_column(col, to.clone()))} else {None}
+            }.transpose()
+        ).collect::<DBResult<_>>()?;
+        let cols:Vec<&Column> = src.iter().zip(promoted.iter()).map(
+            |(col, p)| p.as_ref().unwrap_or(col)
+        ).collect();
+
+        let branches = cols.len() / 2;
+        let conds:Vec<&Vec<bool>> = (0..branches).map(
+            |b| cols[b * 2].downcast_data_ref::<DBBool>().unwrap().data_ref()
+        ).collect();
+        //Indices of the value columns of the branches and of else
+        let value_cols:Vec<usize> = (0..cols.len()).filter(|i| is_value(*i)).collect();
+        let values:Vec<Option<&Vec<T::InnerType>>> = value_cols.iter().map(
+            |i| cols[*i].downcast_data_ref::<T>().map(|d| d.data_ref())
+        ).collect();
+
+        let mut nulls = vec![false; dest.len()];
+        let dest_data = dest.downcast_data_mut::<T>().unwrap().data_mut();
+        for (row, d) in dest_data.iter_mut().enumerate()
+        {
+            //The first matching branch wins
+            let branch = (0..branches).find(|b| conds[*b][row]).or(
+                if value_cols.len() > branches {Some(branches)} else {None}
+            );
+            *d = match branch.map(|b| (values[b], src[value_cols[b]])) {
+                Some((Some(vals), col)) => {
+                    nulls[row] = col.is_null(row);
+                    vals[row].clone()
+                },
+                Some((None, _)) => {
+                    nulls[row] = true;
+                    T::InnerType::default()
+                },
+                None => T::InnerType::default()
+            };
+        }
+        if let Some(dest_nulls) = dest.nulls_mut()
+        {
+            *dest_nulls = nulls;
+        }
+
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        format!("multiIf({})", src.join(", "))
+    }
+}
+
+//multiIf for the types without the typed implementation, e.g. Array: the values are copied packed row by row
+pub struct PackedMultiIf {}
+
+impl RegFunction for PackedMultiIf
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert!(src.len() >= 2);
+
+        let is_value = |i:usize| i % 2 == 1 || i == src.len() - 1;
+        //The values are packed as dest, so they are cast to its type including Nullable
+        let to = dest.type_name();
+        let promoted:Vec<Option<Column>> = src.iter().enumerate().map(
+            |(i, col)| {
+                if is_value(i) && col.type_name() != to {Some(cast_column(col, to.clone()))} else {None}
+            }.transpose()
+        ).collect::<DBResult<_>>()?;
+        let cols:Vec<&Column> = src.iter().zip(promoted.iter()).map(
+            |(col, p)| p.as_ref().unwrap_or(col)
+        ).collect();
+
+        let branches = cols.len() / 2;
+        let value_cols:Vec<usize> = (0..cols.len()).filter(|i| is_value(*i)).collect();
+        let mut default = Column::new(ColumnHeader::new("default", to));
+        default.resize(1);
+        let mut packed = Vec::<u8>::new();
+        for row in 0..dest.len()
+        {
+            let branch = (0..branches).find(|b| cols[b * 2].downcast_data_ref::<DBBool>().unwrap()[row] && !cols[b * 2].is_null(row)).or(
+                if value_cols.len() > branches {Some(branches)} else {None}
+            );
+            packed.clear();
+            match branch {
+                Some(b) => cols[value_cols[b]].pack_value_to(row, &mut packed),
+                None => default.pack_value_to(0, &mut packed)
+            }
+            dest.unpack_value_from(row, &mut packed.as_slice());
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        format!("multiIf({})", src.join(", "))
+    }
+}
+
+pub struct MultiIfBuilder {}
+
+impl MultiIfBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl RegFunctionBuilder for MultiIfBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() < 2
+        {
+            return Err(format!("multiIf expects at least 2 arguments, got {}", src.len()));
+        }
+        let mut values = Vec::<TypeName>::new();
+        for (i, t) in src.iter().enumerate()
+        {
+            if i % 2 == 1 || i == src.len() - 1
+            {
+                values.push(t.clone());
+            }
+            else if *t.base() != TypeName::DBBool
+            {
+                return Err(format!("Condition of multiIf must be Bool, got {}", t));
+            }
+        }
+        unify_types(&values)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        match self.result_type(src)?.base() {
+            TypeName::DBInt => Ok(Box::new(MultiIf::<DBInt>::new())),
+            TypeName::DBInt8 => Ok(Box::new(MultiIf::<DBInt8>::new())),
+            TypeName::DBInt16 => Ok(Box::new(MultiIf::<DBInt16>::new())),
+            TypeName::DBInt32 => Ok(Box::new(MultiIf::<DBInt32>::new())),
+            TypeName::DBUInt8 => Ok(Box::new(MultiIf::<DBUInt8>::new())),
+            TypeName::DBUInt16 => Ok(Box::new(MultiIf::<DBUInt16>::new())),
+            TypeName::DBUInt32 => Ok(Box::new(MultiIf::<DBUInt32>::new())),
+            TypeName::DBUInt64 => Ok(Box::new(MultiIf::<DBUInt64>::new())),
+            TypeName::DBFloat => Ok(Box::new(MultiIf::<DBFloat>::new())),
+            TypeName::DBString => Ok(Box::new(MultiIf::<DBString>::new())),
+            TypeName::DBBool => Ok(Box::new(MultiIf::<DBBool>::new())),
+            TypeName::DBDate => Ok(Box::new(MultiIf::<DBDate>::new())),
+            TypeName::DBDateTime => Ok(Box::new(MultiIf::<DBDateTime>::new())),
+            TypeName::DBNothing => Ok(Box::new(MultiIf::<DBNothing>::new())),
+            TypeName::DBDecimal(precision, _) if *precision <= MAX_PRECISION_64 => Ok(Box::new(MultiIf::<DBDecimal64>::new())),
+            TypeName::DBDecimal(..) => Ok(Box::new(MultiIf::<DBDecimal128>::new())),
+            TypeName::DBArray(_) => Ok(Box::new(PackedMultiIf{})),
+            other => Err(format!("multiIf unsupported for {}", other))
+        }
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+}
+
+//if(cond, then, else) is multiIf with a single branch
+pub struct IfBuilder {}
+
+impl IfBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl RegFunctionBuilder for IfBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() != 3
+        {
+            return Err(format!("if expects 3 arguments, got {}", src.len()));
+        }
+        MultiIfBuilder::new().result_type(src)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src.clone())?;
+        MultiIfBuilder::new().build(src)
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+}
+
+/* ======== Coalesce ====== */
+
+//The first argument which isn't NULL, the arguments are unified like the branches of if
+pub struct Coalesce<T>
+{
+    _t :std::marker::PhantomData<T>,
+}
+
+impl<T:DBType> OpResult for Coalesce<T>
+{
+    type ResultType = T;
+}
+
+impl<T:DBType> RegFunction for Coalesce<T>
+where T::InnerType:Default
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        //NULL literals have no values to cast
+        let to = dest.type_name().base().clone();
+        let promoted:Vec<Option<Column>> = src.iter().map(
+            |col| {
+                let base = col.type_name().base().clone();
+                if base != to && base != TypeName::DBNothing {Some(cast_column(col, to.clone()))} else {None}
+            }.transpose()
+        ).collect::<DBResult<_>>()?;
+        let values:Vec<Option<&Vec<T::InnerType>>> = src.iter().zip(promoted.iter()).map(
+            |(col, p)| p.as_ref().unwrap_or(col).downcast_data_ref::<T>().map(|d| d.data_ref())
+        ).collect();
+
+        let mut nulls = vec![false; dest.len()];
+        for (row, d) in dest.downcast_data_iter_mut::<T>().unwrap().enumerate()
+        {
+            let found = src.iter().zip(values.iter()).find_map(
+                |(col, vals)| vals.filter(|_| !col.is_null(row))
+            );
+            *d = match found {
+                Some(vals) => vals[row].clone(),
+                None => {
+                    nulls[row] = true;
+                    T::InnerType::default()
+                }
+            };
+        }
+        if let Some(dest_nulls) = dest.nulls_mut()
+        {
+            *dest_nulls = nulls;
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        format!("coalesce({})", src.join(", "))
+    }
+}
+
+#[derive(Default)]
+pub struct CoalesceBuilder {}
+
+impl CoalesceBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl RegFunctionBuilder for CoalesceBuilder {
+
+    //Nullable only if all the arguments are
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.is_empty()
+        {
+            return Err("coalesce expects at least 1 argument".to_string());
+        }
+        let common = unify_types(&src)?;
+        if src.iter().all(|t| t.is_nullable())
+        {
+            Ok(common)
+        }
+        else
+        {
+            Ok(common.base().clone())
+        }
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        match self.result_type(src)?.base() {
+            TypeName::DBInt => Ok(Box::new(Coalesce::<DBInt>{_t:std::marker::PhantomData})),
+            TypeName::DBInt8 => Ok(Box::new(Coalesce::<DBInt8>{_t:std::marker::PhantomData})),
+            TypeName::DBInt16 => Ok(Box::new(Coalesce::<DBInt16>{_t:std::marker::PhantomData})),
+            TypeName::DBInt32 => Ok(Box::new(Coalesce::<DBInt32>{_t:std::marker::PhantomData})),
+            TypeName::DBUInt8 => Ok(Box::new(Coalesce::<DBUInt8>{_t:std::marker::PhantomData})),
+            TypeName::DBUInt16 => Ok(Box::new(Coalesce::<DBUInt16>{_t:std::marker::PhantomData})),
+            TypeName::DBUInt32 => Ok(Box::new(Coalesce::<DBUInt32>{_t:std::marker::PhantomData})),
+            TypeName::DBUInt64 => Ok(Box::new(Coalesce::<DBUInt64>{_t:std::marker::PhantomData})),
+            TypeName::DBFloat => Ok(Box::new(Coalesce::<DBFloat>{_t:std::marker::PhantomData})),
+            TypeName::DBString => Ok(Box::new(Coalesce::<DBString>{_t:std::marker::PhantomData})),
+            TypeName::DBBool => Ok(Box::new(Coalesce::<DBBool>{_t:std::marker::PhantomData})),
+            TypeName::DBDate => Ok(Box::new(Coalesce::<DBDate>{_t:std::marker::PhantomData})),
+            TypeName::DBDateTime => Ok(Box::new(Coalesce::<DBDateTime>{_t:std::marker::PhantomData})),
+            TypeName::DBNothing => Ok(Box::new(Coalesce::<DBNothing>{_t:std::marker::PhantomData})),
+            TypeName::DBDecimal(precision, _) if *precision <= MAX_PRECISION_64 => Ok(Box::new(Coalesce::<DBDecimal64>{_t:std::marker::PhantomData})),
+            TypeName::DBDecimal(..) => Ok(Box::new(Coalesce::<DBDecimal128>{_t:std::marker::PhantomData})),
+            other => Err(format!("coalesce unsupported for {}", other))
+        }
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+
+    fn int_column(name:&str, values:&[i64]) -> Column
+    {
+        let mut col = Column::new(ColumnHeader::new(name, TypeName::DBInt));
+        col.resize(values.len());
+        col.downcast_data_mut::<DBInt>().unwrap().data_mut().copy_from_slice(values);
+        col
+    }
+
+    fn bool_column(name:&str, values:&[bool]) -> Column
+    {
+        let mut col = Column::new(ColumnHeader::new(name, TypeName::DBBool));
+        col.resize(values.len());
+        col.downcast_data_mut::<DBBool>().unwrap().data_mut().copy_from_slice(values);
+        col
+    }
+
+    #[test]
+    fn multi_if()
+    {
+        let c1 = bool_column("c1", &[true, false, false, true]);
+        let v1 = int_column("v1", &[10, 10, 10, 10]);
+        let c2 = bool_column("c2", &[true, true, false, false]);
+        let v2 = int_column("v2", &[20, 20, 20, 20]);
+        let mut other = Column::new(ColumnHeader::new("other", TypeName::DBFloat));
+        other.resize(4);
+        other.downcast_data_mut::<DBFloat>().unwrap().data_mut().copy_from_slice(&[0.5, 0.5, 0.5, 0.5]);
+
+        let types = vec![TypeName::DBBool, TypeName::DBInt, TypeName::DBBool, TypeName::DBInt, TypeName::DBFloat];
+        assert_eq!(MultiIfBuilder::new().result_type(types.clone()).unwrap(), TypeName::DBFloat);
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBFloat));
+        dest.resize(4);
+        MultiIfBuilder::new().build(types).unwrap().apply(vec![&c1, &v1, &c2, &v2, &other], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![10., 20., 0.5, 10.]);
+
+        let types = vec![TypeName::DBBool, TypeName::DBInt, TypeName::DBBool, TypeName::DBInt];
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBInt));
+        dest.resize(4);
+        MultiIfBuilder::new().build(types).unwrap().apply(vec![&c1, &v1, &c2, &v2], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![10, 20, 0, 10]);
+
+        assert!(MultiIfBuilder::new().result_type(vec![TypeName::DBString, TypeName::DBInt, TypeName::DBInt]).is_err());
+        assert!(MultiIfBuilder::new().result_type(vec![TypeName::DBInt, TypeName::DBInt, TypeName::DBInt]).is_err());
+        assert!(IfBuilder::new().result_type(vec![TypeName::DBBool, TypeName::DBInt, TypeName::DBString]).is_err());
+    }
+
+    #[test]
+    fn coalesce()
+    {
+        let a = int_column("a", &[1, 2]);
+        let mut b = Column::new(ColumnHeader::new("b", TypeName::DBFloat));
+        b.resize(2);
+        b.downcast_data_mut::<DBFloat>().unwrap().data_mut().copy_from_slice(&[0.5, 0.5]);
+
+        //Without NULLs the first argument is taken
+        let types = vec![TypeName::DBInt, TypeName::DBFloat];
+        assert_eq!(CoalesceBuilder::new().result_type(types.clone()).unwrap(), TypeName::DBFloat);
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBFloat));
+        dest.resize(2);
+        CoalesceBuilder::new().build(types).unwrap().apply(vec![&a, &b], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![1., 2.]);
+
+        assert!(CoalesceBuilder::new().result_type(vec![]).is_err());
+        assert!(CoalesceBuilder::new().result_type(vec![TypeName::DBInt, TypeName::DBString]).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+use super::*;
+use crate::types::types::*;
+use crate::types::time::DateTime;
+use super::cast::{promote_comparable, Promoted};
+use crate::functions::registry::FunctionRegistry;
+use itertools::izip;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/* ======== DatePart ====== */
+
+//Row function of a single Date or DateTime argument
+pub struct DatePart<F:DBType, T:DBType>
+{
+    name :&'static str,
+    func :fn(&F::InnerType) -> T::InnerType,
+}
+
+impl<F:DBType, T:DBType> RegFunction for DatePart<F, T>
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        let src_it = src[0].downcast_data_iter::<F>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<T>().unwrap();
+        for (s, d) in izip!(src_it, dest_it)
+        {
+            *d = (self.func)(s);
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        format!("{}({})", self.name, src.join(", "))
+    }
+}
+
+type MakeFunction = Box<dyn Fn() -> RegFunctionRef>;
+
+//Implementations for each argument type: the argument type, the result type and the implementation
+pub struct DatePartBuilder
+{
+    name :&'static str,
+    overloads :Vec<(TypeName, TypeName, MakeFunction)>,
+}
+
+impl DatePartBuilder
+{
+    pub fn new(name:&'static str) -> Self
+    {
+        Self{name, overloads:Vec::new()}
+    }
+    pub fn with<F:DBType, T:DBType>(mut self, func:fn(&F::InnerType) -> T::InnerType) -> Self
+    {
+        let name = self.name;
+        self.overloads.push((F::NAME, T::NAME, Box::new(move || Box::new(DatePart::<F, T>{name, func}))));
+        self
+    }
+
+    fn overload(&self, src:&[TypeName]) -> DBResult<&(TypeName, TypeName, MakeFunction)>
+    {
+        if src.len() != 1
+        {
+            return Err(format!("{} expects 1 argument, got {}", self.name, src.len()));
+        }
+        self.overloads.iter().find(|(from, _, _)| *from == src[0]).ok_or_else(
+            || format!("{} unsupported for {}", self.name, src[0])
+        )
+    }
+}
+
+impl RegFunctionBuilder for DatePartBuilder
+{
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        Ok(self.overload(&src)?.1.clone())
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        Ok((self.overload(&src)?.2)())
+    }
+}
+
+/* ======== DateDiff ====== */
+
+//Number of the unit boundaries between the dates, e.g. dateDiff('month', '2024-01-31', '2024-02-01') is 1
+fn date_diff(unit:&str, from:&DateTime, to:&DateTime) -> DBResult<i64>
+{
+    let months = |t:&DateTime| {
+        let (year, month, _) = t.date().ymd();
+        year * 12 + month as i64
+    };
+    //1970-01-01 is Thursday, weeks start on Monday
+    let weeks = |t:&DateTime| (t.date().0 as i64 + 3).div_euclid(7);
+    Ok(match unit {
+        "second" => to.0 - from.0,
+        "minute" => to.0.div_euclid(60) - from.0.div_euclid(60),
+        "hour" => to.0.div_euclid(3600) - from.0.div_euclid(3600),
+        "day" => to.date().0 as i64 - from.date().0 as i64,
+        "week" => weeks(to) - weeks(from),
+        "month" => months(to) - months(from),
+        "quarter" => (months(to) - 1).div_euclid(3) - (months(from) - 1).div_euclid(3),
+        "year" => to.date().ymd().0 - from.date().ymd().0,
+        other => return Err(format!("Unknown unit '{}' of dateDiff", other))
+    })
+}
+
+//dateDiff(unit, from, to), Date arguments are taken at midnight
+pub struct DateDiff<T:DBType>
+{
+    to_date_time :fn(&T::InnerType) -> DateTime,
+}
+
+impl<T:DBType> OpResult for DateDiff<T>
+{
+    type ResultType = DBInt;
+}
+
+impl<T:DBType> RegFunction for DateDiff<T>
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 3);
+        let unit_it = src[0].downcast_data_iter::<DBString>().unwrap();
+        let from_it = src[1].downcast_data_iter::<T>().unwrap();
+        let to_it = src[2].downcast_data_iter::<T>().unwrap();
+        let dest_it = dest.downcast_data_iter_mut::<DBInt>().unwrap();
+        for (unit, from, to, d) in izip!(unit_it, from_it, to_it, dest_it)
+        {
+            *d = date_diff(unit, &(self.to_date_time)(from), &(self.to_date_time)(to))?;
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        format!("dateDiff({})", src.join(", "))
+    }
+}
+
+#[derive(Default)]
+pub struct DateDiffBuilder {}
+
+impl DateDiffBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl RegFunctionBuilder for DateDiffBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() != 3
+        {
+            return Err(format!("dateDiff expects 3 arguments, got {}", src.len()));
+        }
+        let dates = promote_comparable(&src[1..]).unwrap_or_else(|| src[1..].to_vec());
+        match (&src[0], &dates[0], &dates[1]) {
+            (TypeName::DBString, TypeName::DBDate, TypeName::DBDate)
+            | (TypeName::DBString, TypeName::DBDateTime, TypeName::DBDateTime) => Ok(TypeName::DBInt),
+            _ => Err(format!("dateDiff unsupported for {}, {} and {}", src[0], src[1], src[2]))
+        }
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src.clone())?;
+        if let Some(dates) = promote_comparable(&src[1..])
+        {
+            let promoted = vec![TypeName::DBString, dates[0].clone(), dates[1].clone()];
+            return Ok(Promoted::new_ref(self.build(promoted.clone())?, promoted));
+        }
+        match &src[1] {
+            TypeName::DBDate => Ok(Box::new(DateDiff::<DBDate>{to_date_time:|d| DateTime::from_date(*d)})),
+            _ => Ok(Box::new(DateDiff::<DBDateTime>{to_date_time:|t| *t}))
+        }
+    }
+}
+
+/* ======== Now ====== */
+
+//Current time, read once when the function is built, so it is the same for the whole query
+pub struct Now
+{
+    time :DateTime,
+}
+
+impl OpResult for Now
+{
+    type ResultType = DBDateTime;
+}
+
+impl RegFunction for Now
+{
+    fn apply(&self, _src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        dest.downcast_data_mut::<DBDateTime>().unwrap().fill(self.time);
+        Ok(())
+    }
+    fn to_string(&self, _src:Vec<String>) -> String
+    {
+        "now()".to_string()
+    }
+}
+
+#[derive(Default)]
+pub struct NowBuilder {}
+
+impl NowBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl RegFunctionBuilder for NowBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if !src.is_empty()
+        {
+            return Err(format!("now expects no arguments, got {}", src.len()));
+        }
+        Ok(TypeName::DBDateTime)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src)?;
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?.as_secs();
+        Ok(Box::new(Now{time:DateTime(secs as i64)}))
+    }
+}
+
+/* ======== Library ====== */
+
+pub fn register_functions(registry:&mut FunctionRegistry) -> DBResult<()>
+{
+    let functions = [
+        DatePartBuilder::new("toYear")
+            .with::<DBDate, DBInt>(|d| d.ymd().0)
+            .with::<DBDateTime, DBInt>(|t| t.date().ymd().0),
+        DatePartBuilder::new("toMonth")
+            .with::<DBDate, DBInt>(|d| d.ymd().1 as i64)
+            .with::<DBDateTime, DBInt>(|t| t.date().ymd().1 as i64),
+        DatePartBuilder::new("toDayOfMonth")
+            .with::<DBDate, DBInt>(|d| d.ymd().2 as i64)
+            .with::<DBDateTime, DBInt>(|t| t.date().ymd().2 as i64),
+        DatePartBuilder::new("toHour").with::<DBDateTime, DBInt>(|t| t.hms().0 as i64),
+        DatePartBuilder::new("toMinute").with::<DBDateTime, DBInt>(|t| t.hms().1 as i64),
+        DatePartBuilder::new("toSecond").with::<DBDateTime, DBInt>(|t| t.hms().2 as i64),
+        DatePartBuilder::new("toDate")
+            .with::<DBDate, DBDate>(|d| *d)
+            .with::<DBDateTime, DBDate>(|t| t.date()),
+        DatePartBuilder::new("toStartOfMonth")
+            .with::<DBDate, DBDate>(|d| d.start_of_month())
+            .with::<DBDateTime, DBDate>(|t| t.date().start_of_month()),
+        DatePartBuilder::new("toStartOfDay")
+            .with::<DBDate, DBDateTime>(|d| DateTime::from_date(*d))
+            .with::<DBDateTime, DBDateTime>(|t| DateTime::from_date(t.date())),
+        DatePartBuilder::new("toStartOfHour").with::<DBDateTime, DBDateTime>(|t| t.start_of_hour()),
+    ];
+    for builder in functions
+    {
+        registry.register_function(builder.name, Box::new(builder))?;
+    }
+    registry.register_function("dateDiff", DateDiffBuilder::new_ref())?;
+    registry.register_alias("date_diff", "dateDiff")?;
+    registry.register_function("now", NowBuilder::new_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+    use std::str::FromStr;
+
+    #[test]
+    fn date_diff_units()
+    {
+        let from = DateTime::from_str("2024-01-31 23:59:59").unwrap();
+        let to = DateTime::from_str("2024-02-01 00:00:00").unwrap();
+        assert_eq!(date_diff("second", &from, &to).unwrap(), 1);
+        assert_eq!(date_diff("hour", &from, &to).unwrap(), 1);
+        assert_eq!(date_diff("day", &from, &to).unwrap(), 1);
+        assert_eq!(date_diff("month", &from, &to).unwrap(), 1);
+        assert_eq!(date_diff("quarter", &from, &to).unwrap(), 0);
+        assert_eq!(date_diff("year", &to, &from).unwrap(), 0);
+        //2024-01-28 is Sunday
+        let sunday = DateTime::from_str("2024-01-28").unwrap();
+        assert_eq!(date_diff("week", &sunday, &to).unwrap(), 1);
+        assert!(date_diff("fortnight", &from, &to).is_err());
+    }
+
+    #[test]
+    fn date_functions()
+    {
+        let mut d = Column::new(ColumnHeader::new("d", TypeName::DBDateTime));
+        d.resize(2);
+        d.downcast_data_mut::<DBDateTime>().unwrap().data_mut().copy_from_slice(&[
+            DateTime::from_str("2024-02-29 13:45:00").unwrap(), DateTime::from_str("1999-12-31 00:10:00").unwrap()
+        ]);
+
+        let registry = FunctionRegistry::new();
+        let builder = registry.function("toStartOfMonth").unwrap();
+        assert_eq!(builder.result_type(vec![TypeName::DBDateTime]), Ok(TypeName::DBDate));
+        assert!(builder.result_type(vec![TypeName::DBInt]).is_err());
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBDate));
+        dest.resize(2);
+        builder.build(vec![TypeName::DBDateTime]).unwrap().apply(vec![&d], &mut dest).unwrap();
+        assert_eq!(dest.data_ref().to_string_at(0), "2024-02-01");
+        assert_eq!(dest.data_ref().to_string_at(1), "1999-12-01");
+
+        let builder = registry.function("toYear").unwrap();
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBInt));
+        dest.resize(2);
+        builder.build(vec![TypeName::DBDateTime]).unwrap().apply(vec![&d], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![2024, 1999]);
+
+        assert!(registry.function("toStartOfHour").unwrap().result_type(vec![TypeName::DBDate]).is_err());
+        assert!(registry.function("dateDiff").unwrap().result_type(vec![TypeName::DBString, TypeName::DBDate, TypeName::DBDateTime]).is_ok());
+        assert!(registry.function("dateDiff").unwrap().result_type(vec![TypeName::DBString, TypeName::DBInt, TypeName::DBDate]).is_err());
+    }
+}
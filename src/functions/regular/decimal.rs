@@ -0,0 +1,343 @@
+use super::*;
+use crate::types::types::*;
+use crate::types::decimal::*;
+use super::cast::Promoted;
+use itertools::izip;
+
+//Values of Decimal column as i128 whatever the storage is
+pub fn decimal_values(col:&Column) -> Vec<i128>
+{
+    match col.downcast_data_ref::<DBDecimal64>() {
+        Some(d) => d.iter().map(|v| *v as i128).collect(),
+        None => col.downcast_data_ref::<DBDecimal128>().unwrap().data_ref().clone()
+    }
+}
+
+//Writes the values of the scale of dest, they must fit its precision
+pub fn set_decimal_values(dest:&mut Column, values:impl Iterator<Item = DBResult<i128>>) -> DBResult<()>
+{
+    let to = dest.type_name().base().clone();
+    let TypeName::DBDecimal(precision, scale) = to else {
+        return Err(format!("{} isn't Decimal", to));
+    };
+    let checked = values.map(|v| v.and_then(
+        |v| if fits_precision(v, precision) {Ok(v)} else {Err(format!("{} is out of range of {}", format_decimal(v, scale), to))}
+    ));
+    if let Some(d) = dest.downcast_data_mut::<DBDecimal64>()
+    {
+        for (v, d) in izip!(checked, d.iter_mut())
+        {
+            *d = v? as i64;
+        }
+        return Ok(());
+    }
+    for (v, d) in izip!(checked, dest.downcast_data_iter_mut::<DBDecimal128>().unwrap())
+    {
+        *d = v?;
+    }
+    Ok(())
+}
+
+//Precision and scale of Decimal, integers are decimals of scale 0 with the digits of their range
+pub fn decimal_params(t:&TypeName) -> Option<(u8, u8)>
+{
+    match t {
+        TypeName::DBDecimal(precision, scale) => Some((*precision, *scale)),
+        TypeName::DBInt8 | TypeName::DBUInt8 => Some((3, 0)),
+        TypeName::DBInt16 | TypeName::DBUInt16 => Some((5, 0)),
+        TypeName::DBInt32 | TypeName::DBUInt32 => Some((10, 0)),
+        TypeName::DBInt => Some((19, 0)),
+        TypeName::DBUInt64 => Some((20, 0)),
+        _ => None
+    }
+}
+
+//Decimals possibly mixed with integers, Float makes the arguments Float (see cast::promote_numeric)
+pub fn is_decimal_args(src:&[TypeName]) -> bool
+{
+    src.iter().any(|t| matches!(t, TypeName::DBDecimal(..))) && src.iter().all(|t| decimal_params(t).is_some())
+}
+
+//Decimal holding the values of all the arguments, used to compare and to unify them
+pub fn common_decimal(src:&[TypeName]) -> Option<TypeName>
+{
+    if !is_decimal_args(src)
+    {
+        return None;
+    }
+    let params:Vec<(u8, u8)> = src.iter().map(|t| decimal_params(t).unwrap()).collect();
+    let scale = params.iter().map(|(_, s)| *s).max().unwrap();
+    let int_digits = params.iter().map(|(p, s)| p - s).max().unwrap();
+    Some(TypeName::DBDecimal((int_digits + scale).min(MAX_PRECISION), scale))
+}
+
+/* ======== Cast ====== */
+
+fn set_values<T:DBType>(dest:&mut Column, values:impl Iterator<Item = DBResult<T::InnerType>>) -> DBResult<()>
+{
+    for (v, d) in izip!(values, dest.downcast_data_iter_mut::<T>().unwrap())
+    {
+        *d = v?;
+    }
+    Ok(())
+}
+
+//Casts from and to Decimal: the extra fraction digits are truncated, except Float which is rounded
+pub fn cast_decimal(src:&Column, dest:&mut Column) -> DBResult<()>
+{
+    let from = src.type_name().base().clone();
+    let to = dest.type_name().base().clone();
+    let out_of_range = |v:&dyn ToString| format!("Can't cast {} to {}: out of range", v.to_string(), to);
+    match (&from, &to) {
+        (TypeName::DBDecimal(_, from_scale), TypeName::DBDecimal(_, to_scale)) => set_decimal_values(
+            dest,
+            decimal_values(src).into_iter().map(|v| rescale(v, *from_scale, *to_scale).ok_or_else(|| out_of_range(&format_decimal(v, *from_scale))))
+        ),
+        (TypeName::DBInt, TypeName::DBDecimal(_, scale)) => set_decimal_values(
+            dest,
+            src.downcast_data_iter::<DBInt>().unwrap().map(|v| rescale(*v as i128, 0, *scale).ok_or_else(|| out_of_range(v)))
+        ),
+        (TypeName::DBFloat, TypeName::DBDecimal(_, scale)) => set_decimal_values(
+            dest,
+            src.downcast_data_iter::<DBFloat>().unwrap().map(|v| {
+                let scaled = (v * pow10(*scale) as f64).round();
+                if scaled.is_finite() && scaled.abs() < i128::MAX as f64 {Ok(scaled as i128)} else {Err(out_of_range(v))}
+            })
+        ),
+        //Values under NULL are empty strings
+        (TypeName::DBString, TypeName::DBDecimal(_, scale)) => set_decimal_values(
+            dest,
+            src.downcast_data_iter::<DBString>().unwrap().enumerate().map(
+                |(row, v)| if src.is_null(row) {Ok(0)} else {parse_decimal(v, *scale).ok_or_else(|| format!("Can't cast '{}' to {}", v, to))}
+            )
+        ),
+        (TypeName::DBDecimal(_, scale), TypeName::DBInt) => set_values::<DBInt>(
+            dest,
+            decimal_values(src).into_iter().map(|v| i64::try_from(rescale(v, *scale, 0).unwrap()).map_err(|_| out_of_range(&format_decimal(v, *scale))))
+        ),
+        (TypeName::DBDecimal(_, scale), TypeName::DBFloat) => set_values::<DBFloat>(
+            dest,
+            decimal_values(src).into_iter().map(|v| Ok(v as f64 / pow10(*scale) as f64))
+        ),
+        (TypeName::DBDecimal(_, scale), TypeName::DBString) => set_values::<DBString>(
+            dest,
+            decimal_values(src).into_iter().map(|v| Ok(format_decimal(v, *scale)))
+        ),
+        _ => Err(format!("Can't cast {} to {}", from, to))
+    }
+}
+
+/* ======== Arithmetic ====== */
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecimalOp
+{
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+}
+
+impl DecimalOp
+{
+    fn sign(&self) -> &'static str
+    {
+        match self {
+            DecimalOp::Plus => "+",
+            DecimalOp::Minus => "-",
+            DecimalOp::Multiply => "*",
+            DecimalOp::Divide => "/",
+        }
+    }
+}
+
+//The result is exact except the division, which truncates the quotient to the scale of the dividend
+pub struct DecimalArithmetic
+{
+    op :DecimalOp,
+    l_scale :u8,
+    r_scale :u8,
+    res_scale :u8,
+}
+
+impl DecimalArithmetic
+{
+    fn calc(&self, l:i128, r:i128) -> DBResult<i128>
+    {
+        let overflow = || format!("Decimal overflow in {} {} {}", format_decimal(l, self.l_scale), self.op.sign(), format_decimal(r, self.r_scale));
+        let res = match self.op {
+            DecimalOp::Plus => rescale(l, self.l_scale, self.res_scale).zip(rescale(r, self.r_scale, self.res_scale)).and_then(|(l, r)| l.checked_add(r)),
+            DecimalOp::Minus => rescale(l, self.l_scale, self.res_scale).zip(rescale(r, self.r_scale, self.res_scale)).and_then(|(l, r)| l.checked_sub(r)),
+            DecimalOp::Multiply => l.checked_mul(r),
+            DecimalOp::Divide if r == 0 => return Err("Division by zero".to_string()),
+            DecimalOp::Divide => l.checked_mul(pow10(self.r_scale)).map(|l| l / r),
+        };
+        res.ok_or_else(overflow)
+    }
+}
+
+impl RegFunction for DecimalArithmetic
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 2);
+        let l = decimal_values(src[0]);
+        let r = decimal_values(src[1]);
+        set_decimal_values(dest, izip!(l, r).map(|(l, r)| self.calc(l, r)))
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 2);
+        format!("{} {} {}", src[0], self.op.sign(), src[1])
+    }
+}
+
+//Used by the arithmetic builders for the decimal arguments, integers are cast to Decimal(P, 0)
+pub struct DecimalArithmeticBuilder
+{
+    op :DecimalOp,
+}
+
+impl DecimalArithmeticBuilder {
+    pub fn new(op:DecimalOp) -> Self {Self{op}}
+}
+
+impl RegFunctionBuilder for DecimalArithmeticBuilder {
+
+    //+, -: the larger scale and the integer part grows by a digit. *: the sum of the scales.
+    ///: the scale of the dividend, the integer part grows by the scale of the divisor
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        assert_eq!(src.len(), 2);
+        let err_str = format!("{} operation unsupported for {} and {}", self.op.sign(), src[0], src[1]);
+        let ((l_precision, l_scale), (r_precision, r_scale)) = match (decimal_params(&src[0]), decimal_params(&src[1])) {
+            (Some(l), Some(r)) => (l, r),
+            _ => return Err(err_str)
+        };
+        let (precision, scale) = match self.op {
+            DecimalOp::Plus | DecimalOp::Minus => {
+                let scale = l_scale.max(r_scale);
+                ((l_precision - l_scale).max(r_precision - r_scale) + scale + 1, scale)
+            },
+            DecimalOp::Multiply => (l_precision + r_precision, l_scale + r_scale),
+            DecimalOp::Divide => (l_precision + r_scale, l_scale),
+        };
+        if scale > MAX_PRECISION
+        {
+            return Err(format!("{}: the scale of the result is above {}", err_str, MAX_PRECISION));
+        }
+        Ok(TypeName::DBDecimal(precision.min(MAX_PRECISION), scale))
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        let res_type = self.result_type(src.clone())?;
+        if src.iter().any(|t| t.is_integer())
+        {
+            let promoted:Vec<TypeName> = src.iter().map(
+                |t| if t.is_integer() {TypeName::DBDecimal(decimal_params(t).unwrap().0, 0)} else {t.clone()}
+            ).collect();
+            return Ok(Promoted::new_ref(self.build(promoted.clone())?, promoted));
+        }
+        let (_, l_scale) = decimal_params(&src[0]).unwrap();
+        let (_, r_scale) = decimal_params(&src[1]).unwrap();
+        let (_, res_scale) = decimal_params(&res_type).unwrap();
+        Ok(Box::new(DecimalArithmetic{op:self.op, l_scale, r_scale, res_scale}))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+    use super::super::cast::cast_column;
+
+    fn decimals(values:&[&str], t:TypeName) -> Column
+    {
+        let mut src = Column::new(ColumnHeader::new("src", TypeName::DBString));
+        src.resize(values.len());
+        for (v, d) in izip!(values.iter(), src.downcast_data_iter_mut::<DBString>().unwrap())
+        {
+            *d = v.to_string();
+        }
+        cast_column(&src, t).unwrap()
+    }
+
+    fn strings(col:&Column) -> Vec<String>
+    {
+        (0..col.len()).map(|i| col.data_ref().to_string_at(i)).collect()
+    }
+
+    fn calc(op:DecimalOp, l:&Column, r:&Column) -> DBResult<Column>
+    {
+        let builder = DecimalArithmeticBuilder::new(op);
+        let types = vec![l.type_name(), r.type_name()];
+        let mut res = Column::new(ColumnHeader::new("res", builder.result_type(types.clone())?));
+        res.resize(l.len());
+        builder.build(types)?.apply(vec![l, r], &mut res)?;
+        Ok(res)
+    }
+
+    #[test]
+    fn arithmetic()
+    {
+        let a = decimals(&["10.25", "-0.01", "3"], TypeName::DBDecimal(6, 2));
+        let b = decimals(&["0.125", "1", "-7"], TypeName::DBDecimal(5, 3));
+
+        let sum = calc(DecimalOp::Plus, &a, &b).unwrap();
+        assert_eq!(sum.type_name(), TypeName::DBDecimal(8, 3));
+        assert_eq!(strings(&sum), vec!["10.375", "0.990", "-4.000"]);
+        assert_eq!(strings(&calc(DecimalOp::Minus, &a, &b).unwrap()), vec!["10.125", "-1.010", "10.000"]);
+
+        let product = calc(DecimalOp::Multiply, &a, &b).unwrap();
+        assert_eq!(product.type_name(), TypeName::DBDecimal(11, 5));
+        assert_eq!(strings(&product), vec!["1.28125", "-0.01000", "-21.00000"]);
+
+        let quotient = calc(DecimalOp::Divide, &a, &b).unwrap();
+        assert_eq!(quotient.type_name(), TypeName::DBDecimal(9, 2));
+        assert_eq!(strings(&quotient), vec!["82.00", "-0.01", "-0.42"]);
+
+        let zero = decimals(&["1", "0", "1"], TypeName::DBDecimal(5, 3));
+        assert_eq!(calc(DecimalOp::Divide, &a, &zero).err(), Some("Division by zero".to_string()));
+
+        let ints = cast_column(&decimals(&["2", "-1", "0"], TypeName::DBDecimal(3, 0)), TypeName::DBInt).unwrap();
+        let res = calc(DecimalOp::Multiply, &a, &ints).unwrap();
+        assert_eq!(res.type_name(), TypeName::DBDecimal(25, 2));
+        assert_eq!(strings(&res), vec!["20.50", "0.01", "0.00"]);
+    }
+
+    #[test]
+    fn casts()
+    {
+        let a = decimals(&["1.999", "-2.5", " 12 "], TypeName::DBDecimal(5, 3));
+        assert_eq!(strings(&a), vec!["1.999", "-2.500", "12.000"]);
+        assert_eq!(strings(&cast_column(&a, TypeName::DBDecimal(4, 1)).unwrap()), vec!["1.9", "-2.5", "12.0"]);
+        assert_eq!(*cast_column(&a, TypeName::DBInt).unwrap().downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![1, -2, 12]);
+        assert_eq!(*cast_column(&a, TypeName::DBFloat).unwrap().downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![1.999, -2.5, 12.]);
+
+        let wide = cast_column(&a, TypeName::DBDecimal(30, 10)).unwrap();
+        assert_eq!(strings(&wide)[1], "-2.5000000000");
+        assert_eq!(
+            cast_column(&a, TypeName::DBDecimal(3, 2)).err(),
+            Some("12.00 is out of range of Decimal(3, 2)".to_string())
+        );
+
+        let mut floats = Column::new(ColumnHeader::new("f", TypeName::DBFloat));
+        floats.resize(2);
+        floats.downcast_data_mut::<DBFloat>().unwrap().data_mut().clone_from_slice(&[0.125, -1.005]);
+        assert_eq!(strings(&cast_column(&floats, TypeName::DBDecimal(5, 2)).unwrap()), vec!["0.13", "-1.00"]);
+
+        let mut bad = Column::new(ColumnHeader::new("s", TypeName::DBString));
+        bad.resize(1);
+        bad.downcast_data_mut::<DBString>().unwrap().data_mut()[0] = "1e5".to_string();
+        assert_eq!(cast_column(&bad, TypeName::DBDecimal(5, 2)).err(), Some("Can't cast '1e5' to Decimal(5, 2)".to_string()));
+    }
+
+    #[test]
+    fn common_types()
+    {
+        assert_eq!(common_decimal(&[TypeName::DBDecimal(10, 2), TypeName::DBDecimal(5, 4)]), Some(TypeName::DBDecimal(12, 4)));
+        assert_eq!(common_decimal(&[TypeName::DBDecimal(10, 2), TypeName::DBInt]), Some(TypeName::DBDecimal(21, 2)));
+        assert_eq!(common_decimal(&[TypeName::DBInt, TypeName::DBInt]), None);
+        assert_eq!(common_decimal(&[TypeName::DBDecimal(10, 2), TypeName::DBFloat]), None);
+    }
+}
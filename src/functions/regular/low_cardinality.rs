@@ -0,0 +1,264 @@
+use super::*;
+use crate::types::types::*;
+use crate::columns::low_cardinality::downcast_low_cardinality_ref;
+use crate::functions::registry::FunctionRegistry;
+use itertools::izip;
+
+//The functions which don't handle LowCardinality get it as String (see null::build_function)
+pub fn materialize_low_cardinality(t:&TypeName) -> TypeName
+{
+    match t.base() {
+        TypeName::DBLowCardinality if t.is_nullable() => TypeName::DBString.to_nullable(),
+        TypeName::DBLowCardinality => TypeName::DBString,
+        _ => t.clone()
+    }
+}
+
+pub fn is_low_cardinality_args(src:&[TypeName]) -> bool
+{
+    src.iter().any(|t| *t.base() == TypeName::DBLowCardinality)
+}
+
+/* ======== LowCardinalityEquals ====== */
+
+//== and != of LowCardinality with String or LowCardinality compare the codes, the strings of the rows aren't materialized.
+//With a string literal (see expr::low_cardinality_literal) the code of the literal is looked up once per block
+pub struct LowCardinalityEquals
+{
+    negate :bool,
+    value :Option<String>,
+}
+
+impl OpResult for LowCardinalityEquals
+{
+    type ResultType = DBBool;
+}
+
+impl LowCardinalityEquals
+{
+    fn lc_with_string(&self, lc:&Column, s:&Column, dest:&mut Column)
+    {
+        let lc = downcast_low_cardinality_ref(lc.data_ref().as_ref()).unwrap();
+        //Runs of the same string look up the code once
+        let mut last:Option<(&String, Option<u32>)> = None;
+        for (c, s, d) in izip!(lc.codes().iter(), s.downcast_data_iter::<DBString>().unwrap(), dest.downcast_data_iter_mut::<DBBool>().unwrap())
+        {
+            let code = match last {
+                Some((last_s, code)) if last_s == s => code,
+                _ => {
+                    let code = lc.find_code(s);
+                    last = Some((s, code));
+                    code
+                }
+            };
+            *d = (code == Some(*c)) != self.negate;
+        }
+    }
+    fn lc_with_value(&self, lc:&Column, value:&str, dest:&mut Column)
+    {
+        let lc = downcast_low_cardinality_ref(lc.data_ref().as_ref()).unwrap();
+        let code = lc.find_code(value);
+        for (c, d) in izip!(lc.codes().iter(), dest.downcast_data_iter_mut::<DBBool>().unwrap())
+        {
+            *d = (code == Some(*c)) != self.negate;
+        }
+    }
+    fn lc_with_lc(&self, l:&Column, r:&Column, dest:&mut Column)
+    {
+        let l = downcast_low_cardinality_ref(l.data_ref().as_ref()).unwrap();
+        let r = downcast_low_cardinality_ref(r.data_ref().as_ref()).unwrap();
+        let codes:Vec<Option<u32>> = l.dict().iter().map(|s| r.find_code(s)).collect();
+        for (l, r, d) in izip!(l.codes().iter(), r.codes().iter(), dest.downcast_data_iter_mut::<DBBool>().unwrap())
+        {
+            *d = (codes[*l as usize] == Some(*r)) != self.negate;
+        }
+    }
+}
+
+impl RegFunction for LowCardinalityEquals
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        if let Some(value) = &self.value
+        {
+            assert_eq!(src.len(), 1);
+            self.lc_with_value(src[0], value, dest);
+            return Ok(());
+        }
+        assert_eq!(src.len(), 2);
+        match (src[0].type_name().base(), src[1].type_name().base()) {
+            (TypeName::DBLowCardinality, TypeName::DBLowCardinality) => self.lc_with_lc(src[0], src[1], dest),
+            (TypeName::DBLowCardinality, _) => self.lc_with_string(src[0], src[1], dest),
+            _ => self.lc_with_string(src[1], src[0], dest)
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        let op = if self.negate {"!="} else {"=="};
+        match &self.value {
+            Some(value) => format!("{} {} '{}'", src[0], op, value),
+            None => format!("{} {} {}", src[0], op, src[1])
+        }
+    }
+}
+
+pub struct LowCardinalityEqualsBuilder
+{
+    negate :bool,
+    value :Option<String>,
+}
+
+impl LowCardinalityEqualsBuilder {
+    pub fn new(negate:bool) -> Self {Self{negate, value:None}}
+    pub fn new_ref(negate:bool) -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new(negate))
+    }
+    //Compares the only argument with the literal
+    pub fn with_value(negate:bool, value:String) -> Self
+    {
+        Self{negate, value:Some(value)}
+    }
+}
+
+impl RegFunctionBuilder for LowCardinalityEqualsBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        assert_eq!(src.len(), if self.value.is_some() {1} else {2});
+        let is_string = |t:&TypeName| *t == TypeName::DBString || *t == TypeName::DBLowCardinality;
+        if self.value.is_some() && src[0] != TypeName::DBLowCardinality
+        {
+            return Err(format!("{} operation unsupported for {} and String", if self.negate {"!="} else {"=="}, src[0]));
+        }
+        if !src.iter().all(is_string)
+        {
+            return Err(format!("{} operation unsupported for {} and {}", if self.negate {"!="} else {"=="}, src[0], src[1]));
+        }
+        Ok(TypeName::DBBool)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src)?;
+        Ok(Box::new(LowCardinalityEquals{negate:self.negate, value:self.value.clone()}))
+    }
+    fn handles_low_cardinality(&self) -> bool
+    {
+        true
+    }
+}
+
+/* ======== ToLowCardinality ====== */
+
+//toLowCardinality(s)
+pub struct ToLowCardinalityBuilder {}
+
+impl ToLowCardinalityBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl Default for ToLowCardinalityBuilder
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl RegFunctionBuilder for ToLowCardinalityBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        match src.as_slice() {
+            [TypeName::DBString] | [TypeName::DBLowCardinality] => Ok(TypeName::DBLowCardinality),
+            [other] => Err(format!("toLowCardinality unsupported for {}", other)),
+            _ => Err(format!("toLowCardinality expects 1 argument, got {}", src.len()))
+        }
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src)?;
+        Ok(Box::new(ToLowCardinality{}))
+    }
+    fn handles_low_cardinality(&self) -> bool
+    {
+        true
+    }
+}
+
+pub struct ToLowCardinality {}
+
+impl RegFunction for ToLowCardinality
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        super::cast::cast_to(src[0], dest)
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 1);
+        format!("toLowCardinality({})", src[0])
+    }
+}
+
+pub fn register_functions(registry:&mut FunctionRegistry) -> DBResult<()>
+{
+    registry.register_function("toLowCardinality", ToLowCardinalityBuilder::new_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+    use crate::columns::low_cardinality::downcast_low_cardinality_mut;
+
+    fn lc_column(values:&[&str]) -> Column
+    {
+        let mut col = Column::new(ColumnHeader::new("lc", TypeName::DBLowCardinality));
+        col.resize(values.len());
+        let lc = downcast_low_cardinality_mut(col.data_mut().as_mut()).unwrap();
+        for (row, s) in values.iter().enumerate()
+        {
+            lc.set_value(row, s);
+        }
+        col
+    }
+
+    #[test]
+    fn equals()
+    {
+        let a = lc_column(&["ios", "web", "ios", "tv"]);
+        let b = lc_column(&["web", "web", "ios", "ios"]);
+        let mut s = Column::new(ColumnHeader::new("s", TypeName::DBString));
+        s.resize(4);
+        s.downcast_data_mut::<DBString>().unwrap().data_mut().fill("ios".to_string());
+
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBBool));
+        dest.resize(4);
+        let eq = LowCardinalityEqualsBuilder::new(false).build(vec![TypeName::DBLowCardinality, TypeName::DBString]).unwrap();
+        eq.apply(vec![&a, &s], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![true, false, true, false]);
+        eq.apply(vec![&s, &b], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![false, false, true, true]);
+
+        let ne = LowCardinalityEqualsBuilder::new(true).build(vec![TypeName::DBLowCardinality; 2]).unwrap();
+        ne.apply(vec![&a, &b], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![true, false, false, true]);
+
+        let ne = LowCardinalityEqualsBuilder::with_value(true, "web".to_string()).build(vec![TypeName::DBLowCardinality]).unwrap();
+        ne.apply(vec![&a], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![true, false, true, true]);
+        let eq = LowCardinalityEqualsBuilder::with_value(false, "android".to_string()).build(vec![TypeName::DBLowCardinality]).unwrap();
+        eq.apply(vec![&a], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![false; 4]);
+
+        assert!(LowCardinalityEqualsBuilder::new(false).result_type(vec![TypeName::DBLowCardinality, TypeName::DBInt]).is_err());
+        assert!(LowCardinalityEqualsBuilder::with_value(false, "ios".to_string()).result_type(vec![TypeName::DBString]).is_err());
+    }
+}
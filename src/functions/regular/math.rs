@@ -0,0 +1,291 @@
+use super::*;
+use crate::types::types::*;
+use super::arithmetic::int_division_error;
+use super::cast::{promote_numeric, Promoted};
+use crate::functions::registry::FunctionRegistry;
+
+pub type MathFn<T> = fn(&[<T as DBType>::InnerType]) -> DBResult<<T as DBType>::InnerType>;
+
+/* ======== Numeric ====== */
+
+//Row function which gets all the arguments converted to the same type
+pub struct Numeric<T:DBType>
+{
+    name :String,
+    func :MathFn<T>,
+}
+
+impl<T:DBType> RegFunction for Numeric<T>
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        let cols:Vec<&Vec<T::InnerType>> = src.iter().map(
+            |c| c.downcast_data_ref::<T>().unwrap().data_ref()
+        ).collect();
+
+        let mut args = Vec::<T::InnerType>::with_capacity(cols.len());
+        for (row, d) in dest.downcast_data_iter_mut::<T>().unwrap().enumerate()
+        {
+            args.clear();
+            args.extend(cols.iter().map(|col| col[row].clone()));
+            *d = (self.func)(&args)?;
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        //Operators are printed as operators
+        if self.name.chars().any(|c| c.is_alphanumeric())
+        {
+            format!("{}({})", self.name, src.join(", "))
+        }
+        else if src.len() == 1
+        {
+            format!("{}{}", self.name, src[0])
+        }
+        else
+        {
+            src.join(&format!(" {} ", self.name))
+        }
+    }
+}
+
+//Implementations for each type, Int arguments of the function without Int one are promoted to Float
+pub struct MathBuilder
+{
+    name :String,
+    min_args :usize,
+    //None if the number of arguments isn't limited
+    max_args :Option<usize>,
+    int :Option<MathFn<DBInt>>,
+    float :Option<MathFn<DBFloat>>,
+    string :Option<MathFn<DBString>>,
+}
+
+impl MathBuilder
+{
+    pub fn new(name:&str, min_args:usize, max_args:Option<usize>, int:Option<MathFn<DBInt>>, float:Option<MathFn<DBFloat>>, string:Option<MathFn<DBString>>) -> Self
+    {
+        Self{name:name.to_string(), min_args, max_args, int, float, string}
+    }
+
+    fn args_type(&self, src:&[TypeName]) -> DBResult<TypeName>
+    {
+        if src.len() < self.min_args || self.max_args.is_some_and(|max| src.len() > max)
+        {
+            return Err(format!("Wrong number of arguments of {}: {}", self.name, src.len()));
+        }
+        let err = || {
+            let names:Vec<String> = src.iter().map(|t| t.to_string()).collect();
+            format!("{} unsupported for {}", self.name, names.join(", "))
+        };
+        let common = match promote_numeric(src) {
+            Some(promoted) => promoted[0].clone(),
+            None if src.iter().all(|t| *t == src[0]) => src[0].clone(),
+            None => return Err(err())
+        };
+        match common {
+            TypeName::DBInt | TypeName::DBUInt64 if self.int.is_some() => Ok(TypeName::DBInt),
+            TypeName::DBInt | TypeName::DBUInt64 | TypeName::DBFloat if self.float.is_some() => Ok(TypeName::DBFloat),
+            TypeName::DBString if self.string.is_some() => Ok(TypeName::DBString),
+            _ => Err(err())
+        }
+    }
+}
+
+impl RegFunctionBuilder for MathBuilder
+{
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        self.args_type(&src)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        let args_type = self.args_type(&src)?;
+        let name = self.name.clone();
+        let func:RegFunctionRef = match args_type {
+            TypeName::DBInt => Box::new(Numeric::<DBInt>{name, func:self.int.unwrap()}),
+            TypeName::DBFloat => Box::new(Numeric::<DBFloat>{name, func:self.float.unwrap()}),
+            TypeName::DBString => Box::new(Numeric::<DBString>{name, func:self.string.unwrap()}),
+            other => return Err(format!("{} unsupported for {}", name, other))
+        };
+        if src.iter().all(|t| *t == args_type)
+        {
+            Ok(func)
+        }
+        else
+        {
+            Ok(Promoted::new_ref(func, vec![args_type; src.len()]))
+        }
+    }
+}
+
+/* ======== Library ====== */
+
+fn int_overflow(name:&str, args:&[i64]) -> String
+{
+    format!("Integer overflow in {} of {:?}", name, args)
+}
+
+//Rounds to 10^-n for negative n, half away from zero
+fn round_int(x:i64, n:i64) -> DBResult<i64>
+{
+    if n >= 0
+    {
+        return Ok(x);
+    }
+    let p = match 10i64.checked_pow(n.unsigned_abs().min(19) as u32) {
+        Some(p) => p,
+        None => return Ok(0)
+    };
+    let rest = x % p;
+    if rest.abs() * 2 >= p
+    {
+        (x - rest).checked_add(p * rest.signum()).ok_or_else(|| int_overflow("round", &[x, n]))
+    }
+    else
+    {
+        Ok(x - rest)
+    }
+}
+
+fn round_float(x:f64, n:f64) -> f64
+{
+    let p = 10f64.powi(n.clamp(-400., 400.) as i32);
+    (x * p).round() / p
+}
+
+fn max_of<T:Clone + PartialOrd>(args:&[T]) -> DBResult<T>
+{
+    let mut res = &args[0];
+    for a in args.iter().skip(1)
+    {
+        if a > res {res = a;}
+    }
+    Ok(res.clone())
+}
+
+fn min_of<T:Clone + PartialOrd>(args:&[T]) -> DBResult<T>
+{
+    let mut res = &args[0];
+    for a in args.iter().skip(1)
+    {
+        if a < res {res = a;}
+    }
+    Ok(res.clone())
+}
+
+//Functions and operators with arguments of the same type, operators are printed by their symbols
+pub fn register_functions(registry:&mut FunctionRegistry) -> DBResult<()>
+{
+    let functions = [
+        MathBuilder::new(
+            "%", 2, Some(2),
+            Some(|a| a[0].checked_rem(a[1]).ok_or_else(|| int_division_error(a[0], a[1]))),
+            Some(|a| Ok(a[0] % a[1])),
+            None
+        ),
+        MathBuilder::new(
+            "intDiv", 2, Some(2),
+            Some(|a| a[0].checked_div(a[1]).ok_or_else(|| int_division_error(a[0], a[1]))),
+            None, None
+        ),
+        MathBuilder::new("&", 2, Some(2), Some(|a| Ok(a[0] & a[1])), None, None),
+        MathBuilder::new("|", 2, Some(2), Some(|a| Ok(a[0] | a[1])), None, None),
+        MathBuilder::new("^", 2, Some(2), Some(|a| Ok(a[0] ^ a[1])), None, None),
+        MathBuilder::new(
+            "-", 1, Some(1),
+            Some(|a| a[0].checked_neg().ok_or_else(|| int_overflow("negate", a))),
+            Some(|a| Ok(-a[0])),
+            None
+        ),
+        MathBuilder::new(
+            "abs", 1, Some(1),
+            Some(|a| a[0].checked_abs().ok_or_else(|| int_overflow("abs", a))),
+            Some(|a| Ok(a[0].abs())),
+            None
+        ),
+        MathBuilder::new(
+            "round", 1, Some(2),
+            Some(|a| round_int(a[0], a.get(1).copied().unwrap_or(0))),
+            Some(|a| Ok(round_float(a[0], a.get(1).copied().unwrap_or(0.)))),
+            None
+        ),
+        MathBuilder::new("floor", 1, Some(1), Some(|a| Ok(a[0])), Some(|a| Ok(a[0].floor())), None),
+        MathBuilder::new("ceil", 1, Some(1), Some(|a| Ok(a[0])), Some(|a| Ok(a[0].ceil())), None),
+        MathBuilder::new("sqrt", 1, Some(1), None, Some(|a| Ok(a[0].sqrt())), None),
+        MathBuilder::new("exp", 1, Some(1), None, Some(|a| Ok(a[0].exp())), None),
+        MathBuilder::new("ln", 1, Some(1), None, Some(|a| Ok(a[0].ln())), None),
+        MathBuilder::new("log10", 1, Some(1), None, Some(|a| Ok(a[0].log10())), None),
+        MathBuilder::new("pow", 2, Some(2), None, Some(|a| Ok(a[0].powf(a[1]))), None),
+        MathBuilder::new("greatest", 1, None, Some(max_of), Some(max_of), Some(max_of)),
+        MathBuilder::new("least", 1, None, Some(min_of), Some(min_of), Some(min_of)),
+    ];
+    for builder in functions
+    {
+        let name = match builder.name.as_ref() {
+            "%" => "modulo".to_string(),
+            "&" => "bitAnd".to_string(),
+            "|" => "bitOr".to_string(),
+            "^" => "bitXor".to_string(),
+            "-" => "negate".to_string(),
+            other => other.to_string()
+        };
+        registry.register_function(&name, Box::new(builder))?;
+    }
+    registry.register_alias("power", "pow")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+
+    #[test]
+    fn rounding()
+    {
+        assert_eq!(round_int(1234, 2).unwrap(), 1234);
+        assert_eq!(round_int(1250, -2).unwrap(), 1300);
+        assert_eq!(round_int(-1250, -2).unwrap(), -1300);
+        assert_eq!(round_int(1249, -2).unwrap(), 1200);
+        assert_eq!(round_int(1249, -30).unwrap(), 0);
+        assert!(round_int(i64::MAX, -1).is_err());
+        assert_eq!(round_float(2.346, 2.), 2.35);
+        assert_eq!(round_float(2.5, 0.), 3.);
+        assert_eq!(round_float(1250., -2.), 1300.);
+    }
+
+    #[test]
+    fn math_builder()
+    {
+        let registry = FunctionRegistry::new();
+        let builder = registry.function("sqrt").unwrap();
+        assert_eq!(builder.result_type(vec![TypeName::DBInt]), Ok(TypeName::DBFloat));
+        assert!(builder.result_type(vec![TypeName::DBString]).is_err());
+        assert!(builder.result_type(vec![TypeName::DBInt, TypeName::DBInt]).is_err());
+
+        let builder = registry.function("greatest").unwrap();
+        assert_eq!(builder.result_type(vec![TypeName::DBInt, TypeName::DBFloat, TypeName::DBInt]), Ok(TypeName::DBFloat));
+        assert_eq!(builder.result_type(vec![TypeName::DBString, TypeName::DBString]), Ok(TypeName::DBString));
+        assert!(builder.result_type(vec![TypeName::DBString, TypeName::DBInt]).is_err());
+
+        let mut a = Column::new(ColumnHeader::new("a", TypeName::DBInt));
+        let mut b = Column::new(ColumnHeader::new("b", TypeName::DBFloat));
+        a.resize(3);
+        b.resize(3);
+        a.downcast_data_mut::<DBInt>().unwrap().data_mut().copy_from_slice(&[1, 5, -2]);
+        b.downcast_data_mut::<DBFloat>().unwrap().data_mut().copy_from_slice(&[2.5, 2.5, -2.5]);
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBFloat));
+        dest.resize(3);
+        builder.build(vec![TypeName::DBInt, TypeName::DBFloat]).unwrap().apply(vec![&a, &b], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBFloat>().unwrap().data_ref(), vec![2.5, 5., -2.]);
+
+        let modulo = registry.function("modulo").unwrap().build(vec![TypeName::DBInt, TypeName::DBInt]).unwrap();
+        assert_eq!(modulo.to_string(vec!["a".to_string(), "b".to_string()]), "a % b");
+        let mut zero = Column::new(ColumnHeader::new("zero", TypeName::DBInt));
+        zero.resize(3);
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBInt));
+        dest.resize(3);
+        assert_eq!(modulo.apply(vec![&a, &zero], &mut dest), Err("Division by zero".to_string()));
+    }
+}
@@ -1,11 +1,23 @@
 pub mod arithmetic;
 pub mod cmp;
 pub mod boolean;
+pub mod set;
+pub mod pattern;
+pub mod conditional;
+pub mod cast;
+pub mod string;
+pub mod math;
+pub mod null;
+pub mod date;
+pub mod decimal;
+pub mod low_cardinality;
+pub mod array;
 
 use crate::DBResult;
 use crate::columns::Column;
 //use crate::blocks::ColumnBlock;
 use crate::types::{TypeName, DBType};
+use crate::functions::registry::FunctionRegistry;
 
 pub trait OpResult
 {
@@ -25,4 +37,50 @@ pub trait RegFunctionBuilder
 {
     fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>;
     fn build(&self, src:Vec<TypeName>) -> DBResult<RegFunctionRef>;
-}
\ No newline at end of file
+    //Functions which don't handle NULLs get the rows without NULLs in the arguments,
+    //the result is NULL if any of the arguments is NULL (see null::build_function)
+    fn handles_nulls(&self) -> bool
+    {
+        false
+    }
+    //Functions which don't handle LowCardinality get its values as String
+    fn handles_low_cardinality(&self) -> bool
+    {
+        false
+    }
+}
+
+pub type RegFunctionBuilderRef = Box<dyn RegFunctionBuilder>;
+
+//Builtin regular functions. Operators are registered by their names (plus, less, and...)
+pub fn register_functions(registry:&mut FunctionRegistry) -> DBResult<()>
+{
+    registry.register_function("plus", arithmetic::PlusBuilder::new_ref())?;
+    registry.register_function("minus", arithmetic::MinusBuilder::new_ref())?;
+    registry.register_function("multiply", arithmetic::MultiplyBuilder::new_ref())?;
+    registry.register_function("divide", arithmetic::DivideBuilder::new_ref())?;
+    registry.register_function("equals", cmp::EqualBuilder::new_ref())?;
+    registry.register_function("notEquals", cmp::NotEqualBuilder::new_ref())?;
+    registry.register_function("less", cmp::LessBuilder::new_ref())?;
+    registry.register_function("lessOrEquals", cmp::LessEqualBuilder::new_ref())?;
+    registry.register_function("greater", cmp::GreaterBuilder::new_ref())?;
+    registry.register_function("greaterOrEquals", cmp::GreaterEqualBuilder::new_ref())?;
+    registry.register_function("and", boolean::AndBuilder::new_ref())?;
+    registry.register_function("or", boolean::OrBuilder::new_ref())?;
+    registry.register_function("not", boolean::NotBuilder::new_ref())?;
+    registry.register_function("match", pattern::MatchBuilder::new_ref())?;
+    registry.register_function("like", pattern::LikeBuilder::new_ref(false, false))?;
+    registry.register_function("notLike", pattern::LikeBuilder::new_ref(false, true))?;
+    registry.register_function("ilike", pattern::LikeBuilder::new_ref(true, false))?;
+    registry.register_function("notILike", pattern::LikeBuilder::new_ref(true, true))?;
+    registry.register_function("if", conditional::IfBuilder::new_ref())?;
+    registry.register_function("multiIf", conditional::MultiIfBuilder::new_ref())?;
+    registry.register_function("coalesce", conditional::CoalesceBuilder::new_ref())?;
+    registry.register_alias("ifNull", "coalesce")?;
+    string::register_functions(registry)?;
+    math::register_functions(registry)?;
+    date::register_functions(registry)?;
+    null::register_functions(registry)?;
+    low_cardinality::register_functions(registry)?;
+    array::register_functions(registry)
+}
@@ -0,0 +1,294 @@
+use super::*;
+use crate::types::types::*;
+use crate::columns::header::ColumnHeader;
+use super::cast::Promoted;
+use super::low_cardinality::{is_low_cardinality_args, materialize_low_cardinality};
+use crate::functions::registry::FunctionRegistry;
+
+//Result type and function for the arguments: the functions which don't handle NULLs
+//are built for the types without Nullable and get NULL result for NULL argument,
+//LowCardinality arguments are cast to String for the functions which don't handle it.
+//NULL literal (Nothing) fits any type, the result is NULL in all rows
+pub fn build_function(builder:&dyn RegFunctionBuilder, src:Vec<TypeName>) -> DBResult<(TypeName, RegFunctionRef)>
+{
+    if !builder.handles_low_cardinality() && is_low_cardinality_args(&src)
+    {
+        let materialized:Vec<TypeName> = src.iter().map(materialize_low_cardinality).collect();
+        let (type_name, func) = build_function(builder, materialized.clone())?;
+        return Ok((type_name, Promoted::new_ref(func, materialized)));
+    }
+    if builder.handles_nulls() || !src.iter().any(|t| t.is_nullable())
+    {
+        return Ok((builder.result_type(src.clone())?, builder.build(src)?));
+    }
+    let bases:Vec<TypeName> = src.iter().map(|t| t.base().clone()).collect();
+    if bases.contains(&TypeName::DBNothing)
+    {
+        return Ok(null_result(builder, &bases));
+    }
+    let type_name = builder.result_type(bases.clone())?.to_nullable();
+    Ok((type_name, SkipNulls::new_ref(builder.build(bases)?)))
+}
+
+//NULL arguments take the type of the first other argument, e.g. id + NULL is Nullable(Int).
+//If there are no other arguments or the function doesn't accept the type, the result is Nullable(Nothing)
+fn null_result(builder:&dyn RegFunctionBuilder, bases:&[TypeName]) -> (TypeName, RegFunctionRef)
+{
+    let substitute = bases.iter().find(|t| **t != TypeName::DBNothing).cloned();
+    let typed = substitute.and_then(|sub| {
+        let args:Vec<TypeName> = bases.iter().map(|t| if *t == TypeName::DBNothing {sub.clone()} else {t.clone()}).collect();
+        Some((builder.result_type(args.clone()).ok()?, builder.build(args).ok()?))
+    });
+    match typed {
+        Some((type_name, func)) => (type_name.to_nullable(), NullResult::new_ref(Some(func))),
+        None => (TypeName::DBNothing.to_nullable(), NullResult::new_ref(None))
+    }
+}
+
+/* ======== NullResult ====== */
+
+//Result of the function with NULL literal argument, the function is kept for its string only
+pub struct NullResult
+{
+    func :Option<RegFunctionRef>,
+}
+
+impl NullResult
+{
+    pub fn new_ref(func:Option<RegFunctionRef>) -> RegFunctionRef
+    {
+        Box::new(Self{func})
+    }
+}
+
+impl RegFunction for NullResult
+{
+    fn apply(&self, _src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        dest.nulls_mut().unwrap().fill(true);
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        match &self.func {
+            Some(func) => func.to_string(src),
+            None => "NULL".to_string()
+        }
+    }
+}
+
+/* ======== SkipNulls ====== */
+
+//Applies the function to the rows without NULLs only, so e.g. division by the default value isn't made
+pub struct SkipNulls
+{
+    func :RegFunctionRef,
+}
+
+impl SkipNulls
+{
+    pub fn new_ref(func:RegFunctionRef) -> RegFunctionRef
+    {
+        Box::new(Self{func})
+    }
+}
+
+impl RegFunction for SkipNulls
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        let rows = dest.len();
+        let mut filter = Column::new(ColumnHeader::new("not null", TypeName::DBBool));
+        filter.resize(rows);
+        let valid = filter.downcast_data_mut::<DBBool>().unwrap().data_mut();
+        valid.fill(true);
+        for nulls in src.iter().filter_map(|col| col.nulls())
+        {
+            for (v, n) in valid.iter_mut().zip(nulls.iter())
+            {
+                *v = *v && !*n;
+            }
+        }
+        let valid = filter.downcast_data_ref::<DBBool>().unwrap().data_ref();
+        let valid_rows = valid.iter().filter(|v| **v).count();
+
+        //Arguments and the result without NULLs, the extra row of the result is the default value for NULLs
+        let args:Vec<Column> = src.iter().map(|col| {
+            let mut arg = Column::new(ColumnHeader::new(col.name(), col.type_name().base().clone()));
+            arg.resize(valid_rows);
+            col.copy_filtered_to(&mut arg, 0, &filter);
+            arg
+        }).collect();
+        let mut res = Column::new(ColumnHeader::new(dest.name(), dest.type_name().base().clone()));
+        res.resize(valid_rows);
+        if valid_rows > 0
+        {
+            self.func.apply(args.iter().collect(), &mut res)?;
+        }
+        res.resize(valid_rows + 1);
+
+        let mut indices = Vec::<usize>::with_capacity(rows);
+        let mut next = 0;
+        for v in valid.iter()
+        {
+            if *v
+            {
+                indices.push(next);
+                next += 1;
+            }
+            else
+            {
+                indices.push(valid_rows);
+            }
+        }
+        res.copy_indexed_to(dest, &indices);
+        if let Some(nulls) = dest.nulls_mut()
+        {
+            for (n, v) in nulls.iter_mut().zip(valid.iter())
+            {
+                *n = !*v;
+            }
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        self.func.to_string(src)
+    }
+}
+
+/* ======== IsNull ====== */
+
+//x IS NULL, x IS NOT NULL
+pub struct IsNull
+{
+    negate :bool,
+}
+
+impl OpResult for IsNull
+{
+    type ResultType = DBBool;
+}
+
+impl RegFunction for IsNull
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        for (row, d) in dest.downcast_data_iter_mut::<DBBool>().unwrap().enumerate()
+        {
+            *d = src[0].is_null(row) != self.negate;
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 1);
+        format!("{} IS {}NULL", src[0], if self.negate {"NOT "} else {""})
+    }
+}
+
+pub struct IsNullBuilder
+{
+    negate :bool,
+}
+
+impl IsNullBuilder {
+    pub fn new(negate:bool) -> Self {Self{negate}}
+    pub fn new_ref(negate:bool) -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new(negate))
+    }
+}
+
+impl RegFunctionBuilder for IsNullBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() != 1
+        {
+            return Err(format!("IS NULL expects 1 argument, got {}", src.len()));
+        }
+        Ok(TypeName::DBBool)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src)?;
+        Ok(Box::new(IsNull{negate:self.negate}))
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+    fn handles_low_cardinality(&self) -> bool
+    {
+        true
+    }
+}
+
+pub fn register_functions(registry:&mut FunctionRegistry) -> DBResult<()>
+{
+    registry.register_function("isNull", IsNullBuilder::new_ref(false))?;
+    registry.register_function("isNotNull", IsNullBuilder::new_ref(true))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::functions::regular::arithmetic::DivideBuilder;
+    use crate::functions::regular::conditional::CoalesceBuilder;
+
+    fn nullable_int_column(name:&str, values:&[Option<i64>]) -> Column
+    {
+        let mut col = Column::new(ColumnHeader::new(name, TypeName::DBInt.to_nullable()));
+        col.resize(values.len());
+        for (v, d) in values.iter().zip(col.downcast_data_iter_mut::<DBInt>().unwrap())
+        {
+            *d = v.unwrap_or_default();
+        }
+        for (v, n) in values.iter().zip(col.nulls_mut().unwrap().iter_mut())
+        {
+            *n = v.is_none();
+        }
+        col
+    }
+
+    #[test]
+    fn skip_nulls()
+    {
+        let a = nullable_int_column("a", &[Some(6), None, Some(9), Some(1)]);
+        let b = nullable_int_column("b", &[Some(2), Some(0), None, Some(1)]);
+        let types = vec![a.type_name(), b.type_name()];
+        //Division by zero of the NULL row isn't made
+        let (type_name, func) = build_function(&DivideBuilder::new(), types).unwrap();
+        assert_eq!(type_name, TypeName::DBInt.to_nullable());
+        let mut dest = Column::new(ColumnHeader::new("dest", type_name));
+        dest.resize(4);
+        func.apply(vec![&a, &b], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![3, 0, 0, 1]);
+        assert_eq!(*dest.nulls().unwrap(), vec![false, true, true, false]);
+    }
+
+    #[test]
+    fn coalesce()
+    {
+        let a = nullable_int_column("a", &[Some(6), None, None]);
+        let b = nullable_int_column("b", &[Some(2), Some(0), None]);
+        let builder = CoalesceBuilder::new();
+        let types = vec![a.type_name(), b.type_name()];
+        assert_eq!(builder.result_type(types.clone()).unwrap(), TypeName::DBInt.to_nullable());
+        assert_eq!(builder.result_type(vec![a.type_name(), TypeName::DBFloat]).unwrap(), TypeName::DBFloat);
+        assert!(builder.result_type(vec![a.type_name(), TypeName::DBString]).is_err());
+
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBInt.to_nullable()));
+        dest.resize(3);
+        builder.build(types).unwrap().apply(vec![&a, &b], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBInt>().unwrap().data_ref(), vec![6, 0, 0]);
+        assert_eq!(*dest.nulls().unwrap(), vec![false, false, true]);
+
+        let mut is_null = Column::new(ColumnHeader::new("is_null", TypeName::DBBool));
+        is_null.resize(3);
+        IsNullBuilder::new(false).build(vec![dest.type_name()]).unwrap().apply(vec![&dest], &mut is_null).unwrap();
+        assert_eq!(*is_null.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![false, false, true]);
+    }
+}
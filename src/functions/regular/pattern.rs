@@ -0,0 +1,254 @@
+use super::*;
+use crate::types::types::*;
+use itertools::izip;
+use regex::Regex;
+use std::cell::RefCell;
+
+//Keeps the regex of the last seen pattern, so a constant pattern is compiled once per query
+struct PatternCache
+{
+    last :RefCell<Option<(String, Regex)>>,
+}
+
+impl PatternCache
+{
+    fn new() -> Self
+    {
+        Self{last:RefCell::new(None)}
+    }
+
+    fn is_match(&self, text:&str, pattern:&str, to_regex:&dyn Fn(&str) -> String) -> DBResult<bool>
+    {
+        let is_cached = matches!(&*self.last.borrow(), Some((p, _)) if p == pattern);
+        if !is_cached
+        {
+            let re = Regex::new(&to_regex(pattern)).map_err(
+                |e| format!("Wrong pattern {}: {}", pattern, e)
+            )?;
+            self.last.replace(Some((pattern.to_string(), re)));
+        }
+        Ok(self.last.borrow().as_ref().unwrap().1.is_match(text))
+    }
+
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column, negate:bool, to_regex:&dyn Fn(&str) -> String) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 2);
+
+        let text_it = src[0].downcast_data_iter::<DBString>().unwrap();
+        let pattern_it = src[1].downcast_data_iter::<DBString>().unwrap();
+
+        let dest_it = dest.downcast_data_iter_mut::<DBBool>().unwrap();
+
+        for (text, pattern, d) in izip!(text_it, pattern_it, dest_it)
+        {
+            *d = self.is_match(text, pattern, to_regex)? != negate;
+        }
+        Ok(())
+    }
+}
+
+fn string_pattern_type(name:&str, src:&[TypeName]) -> DBResult<TypeName>
+{
+    assert_eq!(src.len(), 2);
+    match (&src[0], &src[1]) {
+        (TypeName::DBString, TypeName::DBString) => Ok(TypeName::DBBool),
+        _ => Err(format!("{} unsupported for {} and {}", name, src[0], src[1]))
+    }
+}
+
+/* ======== Like ====== */
+
+//% matches any sequence of chars, _ matches any single char, \ escapes them
+pub fn like_to_regex(pattern:&str, case_insensitive:bool) -> String
+{
+    let mut res = String::from(if case_insensitive {"(?is)^"} else {"(?s)^"});
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next()
+    {
+        match c {
+            '%' => res.push_str(".*"),
+            '_' => res.push('.'),
+            '\\' => {
+                if let Some(escaped) = chars.next()
+                {
+                    res.push_str(&regex::escape(&escaped.to_string()));
+                }
+            },
+            other => res.push_str(&regex::escape(&other.to_string()))
+        }
+    }
+    res.push('$');
+    res
+}
+
+pub struct Like
+{
+    case_insensitive :bool,
+    negate :bool,
+    cache :PatternCache,
+}
+
+impl Like
+{
+    pub fn new(case_insensitive:bool, negate:bool) -> Self
+    {
+        Self{case_insensitive, negate, cache:PatternCache::new()}
+    }
+}
+
+impl OpResult for Like
+{
+    type ResultType = DBBool;
+}
+
+impl RegFunction for Like
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        let case_insensitive = self.case_insensitive;
+        self.cache.apply(src, dest, self.negate, &|p| like_to_regex(p, case_insensitive))
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 2);
+        format!(
+            "{} {}{} {}",
+            src[0],
+            if self.negate {"NOT "} else {""},
+            if self.case_insensitive {"ILIKE"} else {"LIKE"},
+            src[1]
+        )
+    }
+}
+
+pub struct LikeBuilder
+{
+    case_insensitive :bool,
+    negate :bool,
+}
+
+impl LikeBuilder {
+    pub fn new(case_insensitive:bool, negate:bool) -> Self {Self{case_insensitive, negate}}
+    pub fn new_ref(case_insensitive:bool, negate:bool) -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new(case_insensitive, negate))
+    }
+}
+
+impl RegFunctionBuilder for LikeBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        string_pattern_type(if self.case_insensitive {"ILIKE"} else {"LIKE"}, &src)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src)?;
+        Ok(Box::new(Like::new(self.case_insensitive, self.negate)))
+    }
+}
+
+/* ======== Match ====== */
+
+//match(s, 'regex'): 1 if the regex matches any part of the string
+pub struct Match
+{
+    cache :PatternCache,
+}
+
+impl Match
+{
+    pub fn new() -> Self
+    {
+        Self{cache:PatternCache::new()}
+    }
+}
+
+impl OpResult for Match
+{
+    type ResultType = DBBool;
+}
+
+impl RegFunction for Match
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        self.cache.apply(src, dest, false, &|p| p.to_string())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 2);
+        format!("match({}, {})", src[0], src[1])
+    }
+}
+
+pub struct MatchBuilder {}
+
+impl MatchBuilder {
+    pub fn new() -> Self {Self{}}
+    pub fn new_ref() -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new())
+    }
+}
+
+impl RegFunctionBuilder for MatchBuilder {
+
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        if src.len() != 2
+        {
+            return Err(format!("match expects 2 arguments, got {}", src.len()));
+        }
+        string_pattern_type("match", &src)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src)?;
+        Ok(Box::new(Match::new()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+
+    fn apply_to_strings(builder:&dyn RegFunctionBuilder, texts:&[&str], pattern:&str) -> Vec<i64>
+    {
+        let mut text = Column::new(ColumnHeader::new("text", TypeName::DBString));
+        let mut pat = Column::new(ColumnHeader::new("pattern", TypeName::DBString));
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBBool));
+        text.resize(texts.len());
+        pat.resize(texts.len());
+        dest.resize(texts.len());
+        for (t, p, s) in izip!(text.downcast_data_iter_mut::<DBString>().unwrap(), pat.downcast_data_iter_mut::<DBString>().unwrap(), texts.iter())
+        {
+            *t = s.to_string();
+            *p = pattern.to_string();
+        }
+        let func = builder.build(vec![TypeName::DBString, TypeName::DBString]).unwrap();
+        func.apply(vec![&text, &pat], &mut dest).unwrap();
+        dest.downcast_data_iter::<DBBool>().unwrap().map(|v| *v as i64).collect()
+    }
+
+    #[test]
+    fn like()
+    {
+        let texts = ["Female", "Male", "male", "M.le", "Mile"];
+        assert_eq!(apply_to_strings(&LikeBuilder::new(false, false), &texts, "M%"), vec![0, 1, 0, 1, 1]);
+        assert_eq!(apply_to_strings(&LikeBuilder::new(false, false), &texts, "M_le"), vec![0, 1, 0, 1, 1]);
+        assert_eq!(apply_to_strings(&LikeBuilder::new(false, false), &texts, "M.le"), vec![0, 0, 0, 1, 0]);
+        assert_eq!(apply_to_strings(&LikeBuilder::new(true, false), &texts, "%MALE"), vec![1, 1, 1, 0, 0]);
+        assert_eq!(apply_to_strings(&LikeBuilder::new(false, true), &texts, "%ale"), vec![0, 0, 0, 1, 1]);
+        assert!(LikeBuilder::new(false, false).result_type(vec![TypeName::DBInt, TypeName::DBString]).is_err());
+    }
+
+    #[test]
+    fn regex_match()
+    {
+        let texts = ["Female", "Male", "male", "M.le"];
+        assert_eq!(apply_to_strings(&MatchBuilder::new(), &texts, "^[Mm]a"), vec![0, 1, 1, 0]);
+        assert_eq!(apply_to_strings(&MatchBuilder::new(), &texts, "ale"), vec![1, 1, 1, 0]);
+    }
+}
@@ -0,0 +1,208 @@
+use super::*;
+use crate::blocks::BlockRef;
+use crate::types::types::*;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use super::cast::{Promoted, cast_column, promote_comparable};
+use super::low_cardinality::materialize_low_cardinality;
+
+/* ============ In ============= */
+
+//Values of the set are taken from a column of the block: the literals of IN list or the result of a subquery.
+//The block may be filled by the previous steps of the plan, so the set is built on the first apply.
+//The values are cast to the type they are compared in.
+//NULL argument gives NULL, so does the argument not found in the set with NULL as in SQL
+pub struct In
+{
+    block :BlockRef,
+    col :String,
+    set_type :TypeName,
+    negate :bool,
+    //The values of the set and whether it has NULL
+    set :RefCell<Option<(HashSet<Vec<u8>>, bool)>>,
+}
+
+impl In
+{
+    pub fn new(block:BlockRef, col:&str, set_type:TypeName, negate:bool) -> Self
+    {
+        Self{block, col:col.to_string(), set_type, negate, set:RefCell::new(None)}
+    }
+
+    fn build_set(&self) -> DBResult<(HashSet<Vec<u8>>, bool)>
+    {
+        let block = self.block.borrow();
+        let col = block.col_at(&self.col);
+        let casted = if *col.type_name().base() != self.set_type {Some(cast_column(col, self.set_type.clone())?)} else {None};
+        let col = casted.as_ref().unwrap_or(col);
+        let mut set = HashSet::<Vec<u8>>::new();
+        let mut has_null = false;
+        for row in 0..col.len()
+        {
+            if col.is_null(row)
+            {
+                has_null = true;
+                continue;
+            }
+            let mut key = Vec::<u8>::new();
+            pack_value(col, row, &mut key);
+            set.insert(key);
+        }
+        Ok((set, has_null))
+    }
+}
+
+//Values of Nullable columns are packed without the null flag to match the values of the other side
+fn pack_value(col:&Column, row:usize, key:&mut Vec<u8>)
+{
+    match col.data_ref().inner() {
+        Some(values) => values.pack_value_to(row, key),
+        None => col.pack_value_to(row, key)
+    }
+}
+
+impl OpResult for In
+{
+    type ResultType = DBBool;
+}
+
+impl RegFunction for In
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        assert_eq!(src.len(), 1);
+        if self.set.borrow().is_none()
+        {
+            self.set.replace(Some(self.build_set()?));
+        }
+        let set_ref = self.set.borrow();
+        let (set, has_null) = set_ref.as_ref().unwrap();
+
+        let mut key = Vec::<u8>::new();
+        let mut nulls = vec![false; dest.len()];
+        for ((row, d), n) in dest.downcast_data_iter_mut::<DBBool>().unwrap().enumerate().zip(nulls.iter_mut())
+        {
+            //NULL rows are false, so filters skip them
+            if src[0].is_null(row)
+            {
+                *n = true;
+                *d = false;
+                continue;
+            }
+            key.clear();
+            pack_value(src[0], row, &mut key);
+            let found = set.contains(&key);
+            *n = !found && *has_null;
+            *d = !*n && found != self.negate;
+        }
+        if let Some(dest_nulls) = dest.nulls_mut()
+        {
+            *dest_nulls = nulls;
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        assert_eq!(src.len(), 1);
+        format!("{} {}IN ({})", src[0], if self.negate {"NOT "} else {""}, self.col)
+    }
+}
+
+pub struct InBuilder
+{
+    block :BlockRef,
+    col :String,
+    negate :bool,
+}
+
+impl InBuilder
+{
+    pub fn new(block:BlockRef, col:&str, negate:bool) -> InBuilder
+    {
+        Self{block, col:col.to_string(), negate}
+    }
+    pub fn new_ref(block:BlockRef, col:&str, negate:bool) -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new(block, col, negate))
+    }
+    fn set_type(&self) -> TypeName
+    {
+        self.block.borrow().col_at(&self.col).type_name()
+    }
+    //The argument and the set are compared in the common type of their bases as by the comparison operators,
+    //e.g. Float and set of Int. The argument is LowCardinality as String (see null::build_function), so is the set
+    fn common_type(&self, src:&TypeName) -> DBResult<TypeName>
+    {
+        let set_type = materialize_low_cardinality(self.set_type().base());
+        let common = match (src.base(), promote_comparable(&[src.base().clone(), set_type.clone()])) {
+            (_, Some(promoted)) => promoted[0].clone(),
+            (src, None) if *src == set_type || *src == TypeName::DBNothing => set_type.clone(),
+            _ => TypeName::DBNothing
+        };
+        match common {
+            TypeName::DBInt | TypeName::DBUInt64 | TypeName::DBFloat | TypeName::DBDecimal(..) | TypeName::DBString
+                | TypeName::DBBool | TypeName::DBDate | TypeName::DBDateTime => Ok(common),
+            _ => Err(format!("IN operation unsupported for {} and set of {}", src, self.set_type()))
+        }
+    }
+}
+
+impl RegFunctionBuilder for InBuilder
+{
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        assert_eq!(src.len(), 1);
+        self.common_type(&src[0])?;
+        Ok(if src[0].is_nullable() || self.set_type().is_nullable() {In::RESULT_NAME.to_nullable()} else {In::RESULT_NAME})
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        assert_eq!(src.len(), 1);
+        let common = self.common_type(&src[0])?;
+        let func = Box::new(In::new(self.block.clone(), &self.col, common.clone(), self.negate));
+        if *src[0].base() != common
+        {
+            let to = if src[0].is_nullable() {common.to_nullable()} else {common};
+            return Ok(Promoted::new_ref(func, vec![to]));
+        }
+        Ok(func)
+    }
+    fn handles_nulls(&self) -> bool
+    {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::blocks::ColumnBlock;
+    use crate::blocks::source::DontTouchSource;
+    use crate::columns::header::ColumnHeader;
+    use std::rc::Rc;
+
+    #[test]
+    fn in_strings()
+    {
+        let mut set_col = Column::new(ColumnHeader::new("set", TypeName::DBString));
+        set_col.resize(2);
+        set_col.downcast_data_mut::<DBString>().unwrap().data_mut().clone_from_slice(&["a".to_string(), "c".to_string()]);
+        let mut set_block = ColumnBlock::new();
+        set_block.add(set_col, DontTouchSource::new_ref());
+        let set_block = Rc::new(RefCell::new(set_block));
+
+        let mut src = Column::new(ColumnHeader::new("src", TypeName::DBString));
+        src.resize(3);
+        src.downcast_data_mut::<DBString>().unwrap().data_mut().clone_from_slice(&["a".to_string(), "b".to_string(), "c".to_string()]);
+        let mut dest = Column::new(ColumnHeader::new("dest", TypeName::DBBool));
+        dest.resize(3);
+
+        let builder = InBuilder::new(set_block.clone(), "set", false);
+        assert!(builder.result_type(vec![TypeName::DBInt]).is_err());
+        builder.build(vec![TypeName::DBString]).unwrap().apply(vec![&src], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![true, false, true]);
+
+        InBuilder::new(set_block, "set", true).build(vec![TypeName::DBString]).unwrap().apply(vec![&src], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBBool>().unwrap().data_ref(), vec![false, true, false]);
+    }
+}
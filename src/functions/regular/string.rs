@@ -0,0 +1,230 @@
+use super::*;
+use crate::types::types::*;
+use crate::functions::registry::FunctionRegistry;
+
+//Row function of the string library: gets the String and the Int arguments of the row in their order
+pub type StringFn<T> = fn(&[&str], &[i64]) -> DBResult<<T as DBType>::InnerType>;
+
+/* ======== StringFunction ====== */
+
+pub struct StringFunction<T:DBType>
+{
+    name :&'static str,
+    func :StringFn<T>,
+}
+
+impl<T:DBType> RegFunction for StringFunction<T>
+{
+    fn apply(&self, src:Vec<&Column>, dest:&mut Column) -> DBResult<()>
+    {
+        let strings:Vec<&Vec<String>> = src.iter().filter(|c| c.type_name() == TypeName::DBString).map(
+            |c| c.downcast_data_ref::<DBString>().unwrap().data_ref()
+        ).collect();
+        let ints:Vec<&Vec<i64>> = src.iter().filter(|c| c.type_name() == TypeName::DBInt).map(
+            |c| c.downcast_data_ref::<DBInt>().unwrap().data_ref()
+        ).collect();
+
+        let mut str_args = Vec::<&str>::with_capacity(strings.len());
+        let mut int_args = Vec::<i64>::with_capacity(ints.len());
+        for (row, d) in dest.downcast_data_iter_mut::<T>().unwrap().enumerate()
+        {
+            str_args.clear();
+            str_args.extend(strings.iter().map(|col| col[row].as_str()));
+            int_args.clear();
+            int_args.extend(ints.iter().map(|col| col[row]));
+            *d = (self.func)(&str_args, &int_args)?;
+        }
+        Ok(())
+    }
+    fn to_string(&self, src:Vec<String>) -> String
+    {
+        format!("{}({})", self.name, src.join(", "))
+    }
+}
+
+pub struct StringFunctionBuilder<T:DBType>
+{
+    name :&'static str,
+    args :&'static [TypeName],
+    //Trailing args which may be omitted
+    optional :usize,
+    //The last arg may be repeated
+    variadic :bool,
+    func :StringFn<T>,
+}
+
+impl<T:DBType> StringFunctionBuilder<T>
+{
+    pub fn new(name:&'static str, args:&'static [TypeName], optional:usize, variadic:bool, func:StringFn<T>) -> Self
+    {
+        Self{name, args, optional, variadic, func}
+    }
+    pub fn new_ref(name:&'static str, args:&'static [TypeName], optional:usize, variadic:bool, func:StringFn<T>) -> Box<dyn RegFunctionBuilder>
+    {
+        Box::new(Self::new(name, args, optional, variadic, func))
+    }
+}
+
+impl<T:DBType> RegFunctionBuilder for StringFunctionBuilder<T>
+{
+    fn result_type(&self, src:Vec<TypeName>) -> DBResult<TypeName>
+    {
+        let min_args = self.args.len() - self.optional;
+        if src.len() < min_args || (src.len() > self.args.len() && !self.variadic)
+        {
+            return Err(format!("{} expects {} arguments, got {}", self.name, self.args.len(), src.len()));
+        }
+        for (i, t) in src.iter().enumerate()
+        {
+            let expected = &self.args[i.min(self.args.len() - 1)];
+            if t != expected
+            {
+                return Err(format!("Argument {} of {} must be {}, got {}", i + 1, self.name, expected, t));
+            }
+        }
+        Ok(T::NAME)
+    }
+    fn build(&self, src:Vec<TypeName>) -> DBResult<Box<dyn RegFunction>>
+    {
+        self.result_type(src)?;
+        Ok(Box::new(StringFunction::<T>{name:self.name, func:self.func}))
+    }
+}
+
+/* ======== Library ====== */
+
+const S :TypeName = TypeName::DBString;
+const I :TypeName = TypeName::DBInt;
+
+//SQL substring: chars from 1-based offset, the part before the first char is cut off the length
+fn substring(s:&str, offset:i64, length:Option<i64>) -> DBResult<String>
+{
+    let chars = s.chars().count() as i64;
+    let end = match length {
+        Some(l) if l < 0 => return Err(format!("Negative substring length {}", l)),
+        Some(l) => offset.saturating_add(l).min(chars + 1),
+        None => chars + 1
+    };
+    let start = offset.max(1);
+    if end <= start
+    {
+        return Ok(String::new());
+    }
+    Ok(s.chars().skip(start as usize - 1).take((end - start) as usize).collect())
+}
+
+fn trim_chars(s:&str, chars:Option<&str>, start:bool, end:bool) -> String
+{
+    let is_trimmed = |c:char| match chars {
+        Some(set) => set.contains(c),
+        None => c.is_whitespace()
+    };
+    let mut res = s;
+    if start {res = res.trim_start_matches(is_trimmed);}
+    if end {res = res.trim_end_matches(is_trimmed);}
+    res.to_string()
+}
+
+//1-based position of the first entry in chars, 0 if there is none
+fn position(s:&str, needle:&str) -> i64
+{
+    match s.find(needle) {
+        Some(at) => s[..at].chars().count() as i64 + 1,
+        None => 0
+    }
+}
+
+//n-th (1-based) part of the string split by the separator char, empty if there are less parts
+fn split_by_char(sep:&str, s:&str, n:i64) -> DBResult<String>
+{
+    let mut sep_chars = sep.chars();
+    let c = match (sep_chars.next(), sep_chars.next()) {
+        (Some(c), None) => c,
+        _ => return Err(format!("Separator of splitByChar must be a single char, got '{}'", sep))
+    };
+    if n < 1
+    {
+        return Ok(String::new());
+    }
+    Ok(s.split(c).nth(n as usize - 1).unwrap_or("").to_string())
+}
+
+fn add<T:DBType>(registry:&mut FunctionRegistry, name:&'static str, args:&'static [TypeName], optional:usize, variadic:bool, func:StringFn<T>) -> DBResult<()>
+{
+    registry.register_function(name, StringFunctionBuilder::<T>::new_ref(name, args, optional, variadic, func))
+}
+
+pub fn register_functions(registry:&mut FunctionRegistry) -> DBResult<()>
+{
+    //length of Array is handled by the wrapper
+    let length = StringFunctionBuilder::<DBInt>::new_ref("length", &[S], 0, false, |s, _| Ok(s[0].chars().count() as i64));
+    registry.register_function("length", super::array::LengthBuilder::new_ref(length))?;
+    add::<DBString>(registry, "lower", &[S], 0, false, |s, _| Ok(s[0].to_lowercase()))?;
+    add::<DBString>(registry, "upper", &[S], 0, false, |s, _| Ok(s[0].to_uppercase()))?;
+    add::<DBString>(registry, "concat", &[S], 0, true, |s, _| Ok(s.concat()))?;
+    add::<DBString>(registry, "substring", &[S, I, I], 1, false, |s, i| substring(s[0], i[0], i.get(1).copied()))?;
+    registry.register_alias("substr", "substring")?;
+    add::<DBString>(registry, "trim", &[S, S], 1, false, |s, _| Ok(trim_chars(s[0], s.get(1).copied(), true, true)))?;
+    add::<DBString>(registry, "ltrim", &[S, S], 1, false, |s, _| Ok(trim_chars(s[0], s.get(1).copied(), true, false)))?;
+    add::<DBString>(registry, "rtrim", &[S, S], 1, false, |s, _| Ok(trim_chars(s[0], s.get(1).copied(), false, true)))?;
+    add::<DBString>(registry, "replace", &[S, S, S], 0, false, |s, _| Ok(if s[1].is_empty() {s[0].to_string()} else {s[0].replace(s[1], s[2])}))?;
+    add::<DBInt>(registry, "position", &[S, S], 0, false, |s, _| Ok(position(s[0], s[1])))?;
+    add::<DBBool>(registry, "startsWith", &[S, S], 0, false, |s, _| Ok(s[0].starts_with(s[1])))?;
+    registry.register_alias("starts_with", "startsWith")?;
+    add::<DBBool>(registry, "endsWith", &[S, S], 0, false, |s, _| Ok(s[0].ends_with(s[1])))?;
+    registry.register_alias("ends_with", "endsWith")?;
+    add::<DBString>(registry, "splitByChar", &[S, S, I], 0, false, |s, i| split_by_char(s[0], s[1], i[0]))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::columns::header::ColumnHeader;
+
+    #[test]
+    fn string_helpers()
+    {
+        assert_eq!(substring("hello", 2, Some(3)).unwrap(), "ell");
+        assert_eq!(substring("hello", 4, None).unwrap(), "lo");
+        assert_eq!(substring("hello", 0, Some(2)).unwrap(), "h");
+        assert_eq!(substring("hello", 10, Some(2)).unwrap(), "");
+        assert_eq!(substring("привет", 2, Some(2)).unwrap(), "ри");
+        assert!(substring("hello", 1, Some(-1)).is_err());
+
+        assert_eq!(trim_chars("  a b  ", None, true, true), "a b");
+        assert_eq!(trim_chars("xxaxx", Some("x"), true, false), "axx");
+        assert_eq!(position("привет", "ве"), 4);
+        assert_eq!(position("abc", "d"), 0);
+        assert_eq!(split_by_char(",", "a,b,,c", 2).unwrap(), "b");
+        assert_eq!(split_by_char(",", "a,b,,c", 3).unwrap(), "");
+        assert_eq!(split_by_char(",", "a,b,,c", 5).unwrap(), "");
+        assert!(split_by_char(",,", "a", 1).is_err());
+    }
+
+    #[test]
+    fn string_function()
+    {
+        let mut s = Column::new(ColumnHeader::new("s", TypeName::DBString));
+        let mut n = Column::new(ColumnHeader::new("n", TypeName::DBInt));
+        s.resize(2);
+        n.resize(2);
+        s.downcast_data_mut::<DBString>().unwrap().data_mut().clone_from_slice(&["Male".to_string(), "Female".to_string()]);
+        n.downcast_data_mut::<DBInt>().unwrap().data_mut().copy_from_slice(&[1, 3]);
+
+        let registry = FunctionRegistry::new();
+        let builder = registry.function("substring").unwrap();
+        assert!(builder.result_type(vec![TypeName::DBString]).is_err());
+        assert!(builder.result_type(vec![TypeName::DBInt, TypeName::DBInt]).is_err());
+        let args = vec![TypeName::DBString, TypeName::DBInt, TypeName::DBInt];
+        let mut dest = Column::new(ColumnHeader::new("dest", builder.result_type(args.clone()).unwrap()));
+        dest.resize(2);
+        builder.build(args).unwrap().apply(vec![&s, &n, &n], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBString>().unwrap().data_ref(), vec!["M", "mal"]);
+
+        let builder = registry.function("concat").unwrap();
+        let args = vec![TypeName::DBString; 3];
+        builder.build(args).unwrap().apply(vec![&s, &s, &s], &mut dest).unwrap();
+        assert_eq!(*dest.downcast_data_ref::<DBString>().unwrap().data_ref(), vec!["MaleMaleMale", "FemaleFemaleFemale"]);
+    }
+}
@@ -3,10 +3,15 @@ mod native;
 
 use crate::types::{DBType, TypeName};
 use crate::types::types::*;
+use crate::types::decimal::MAX_PRECISION_64;
 use super::serialize::ByteSerialize;
 use native::{ChunkWriter, ChunkReader};
 use std::io::{Read, Write};
 use crate::columns::data::{StoragePtr};
+use crate::columns::low_cardinality::{downcast_low_cardinality_ref, downcast_low_cardinality_mut};
+use crate::columns::array::{downcast_array_ref, downcast_array_mut};
+use std::rc::Rc;
+use std::cell::RefCell;
 
 pub trait ColDataWriter {
     fn write_col(&mut self, col_data:&StoragePtr) -> std::io::Result<()>;
@@ -35,6 +40,54 @@ pub fn make_col_writer<W:Write + 'static>(name:TypeName, dest:W) -> ColWriterPtr
             TypeName::DBString => Box::new(
                     ChunkWriter::<DBString, W>::new(dest)
             ) as ColWriterPtr,
+            TypeName::DBBool => Box::new(
+                    ChunkWriter::<DBBool, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBInt8 => Box::new(
+                    ChunkWriter::<DBInt8, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBInt16 => Box::new(
+                    ChunkWriter::<DBInt16, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBInt32 => Box::new(
+                    ChunkWriter::<DBInt32, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBUInt8 => Box::new(
+                    ChunkWriter::<DBUInt8, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBUInt16 => Box::new(
+                    ChunkWriter::<DBUInt16, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBUInt32 => Box::new(
+                    ChunkWriter::<DBUInt32, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBUInt64 => Box::new(
+                    ChunkWriter::<DBUInt64, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBDate => Box::new(
+                    ChunkWriter::<DBDate, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBDateTime => Box::new(
+                    ChunkWriter::<DBDateTime, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBNothing => Box::new(
+                    ChunkWriter::<DBNothing, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBLowCardinality => Box::new(
+                    LowCardinalityWriter::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBDecimal(precision, _) if precision <= MAX_PRECISION_64 => Box::new(
+                    ChunkWriter::<DBDecimal64, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBDecimal(..) => Box::new(
+                    ChunkWriter::<DBDecimal128, W>::new(dest)
+            ) as ColWriterPtr,
+            TypeName::DBNullable(inner) => Box::new(
+                    NullableWriter::new(*inner, dest)
+            ) as ColWriterPtr,
+            TypeName::DBArray(elem) => Box::new(
+                    ArrayWriter::new(*elem, dest)
+            ) as ColWriterPtr,
         }
 }
 
@@ -65,11 +118,298 @@ pub fn make_col_reader<R:Read + 'static>(name:TypeName, src:R) -> ColReaderPtr {
             TypeName::DBString => Box::new(
                     ChunkReader::<DBString, R>::new(src)
             ) as ColReaderPtr,
+            TypeName::DBBool => Box::new(
+                    ChunkReader::<DBBool, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBInt8 => Box::new(
+                    ChunkReader::<DBInt8, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBInt16 => Box::new(
+                    ChunkReader::<DBInt16, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBInt32 => Box::new(
+                    ChunkReader::<DBInt32, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBUInt8 => Box::new(
+                    ChunkReader::<DBUInt8, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBUInt16 => Box::new(
+                    ChunkReader::<DBUInt16, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBUInt32 => Box::new(
+                    ChunkReader::<DBUInt32, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBUInt64 => Box::new(
+                    ChunkReader::<DBUInt64, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBDate => Box::new(
+                    ChunkReader::<DBDate, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBDateTime => Box::new(
+                    ChunkReader::<DBDateTime, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBNothing => Box::new(
+                    ChunkReader::<DBNothing, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBLowCardinality => Box::new(
+                    LowCardinalityReader::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBDecimal(precision, _) if precision <= MAX_PRECISION_64 => Box::new(
+                    ChunkReader::<DBDecimal64, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBDecimal(..) => Box::new(
+                    ChunkReader::<DBDecimal128, R>::new(src)
+            ) as ColReaderPtr,
+            TypeName::DBNullable(inner) => Box::new(
+                    NullableReader::new(*inner, src)
+            ) as ColReaderPtr,
+            TypeName::DBArray(elem) => Box::new(
+                    ArrayReader::new(*elem, src)
+            ) as ColReaderPtr,
         }
 }
 
+/* ======== Nullable ====== */
+
+//Stream shared by the chunk writers (readers) of the null map and of the values
+struct SharedWriter(Rc<RefCell<Box<dyn Write>>>);
+
+impl Write for SharedWriter
+{
+    fn write(&mut self, buf:&[u8]) -> std::io::Result<usize>
+    {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()>
+    {
+        self.0.borrow_mut().flush()
+    }
+}
+
+struct SharedReader(Rc<RefCell<Box<dyn Read>>>);
 
+impl Read for SharedReader
+{
+    fn read(&mut self, buf:&mut [u8]) -> std::io::Result<usize>
+    {
+        self.0.borrow_mut().read(buf)
+    }
+}
 
+//The chunk of the null bitmap, 8 rows per byte, followed by the chunk of the values
+pub struct NullableWriter
+{
+    nulls :ChunkWriter<DBUInt8, SharedWriter>,
+    values :ColWriterPtr,
+}
+
+impl NullableWriter
+{
+    pub fn new(inner:TypeName, dest:impl Write + 'static) -> Self
+    {
+        let dest:Rc<RefCell<Box<dyn Write>>> = Rc::new(RefCell::new(Box::new(dest)));
+        Self{
+            nulls:ChunkWriter::new(SharedWriter(dest.clone())),
+            values:make_col_writer(inner, SharedWriter(dest))
+        }
+    }
+}
+
+impl ColDataWriter for NullableWriter
+{
+    fn write_col(&mut self, col_data:&StoragePtr) -> std::io::Result<()>
+    {
+        self.nulls.write(&pack_bitmap(col_data.nulls().unwrap()))?;
+        self.values.write_col(col_data)
+    }
+}
+
+pub struct NullableReader
+{
+    nulls :ChunkReader<DBUInt8, SharedReader>,
+    values :ColReaderPtr,
+}
+
+impl NullableReader
+{
+    pub fn new(inner:TypeName, src:impl Read + 'static) -> Self
+    {
+        let src:Rc<RefCell<Box<dyn Read>>> = Rc::new(RefCell::new(Box::new(src)));
+        Self{
+            nulls:ChunkReader::new(SharedReader(src.clone())),
+            values:make_col_reader(inner, SharedReader(src))
+        }
+    }
+}
+
+impl ColDataReader for NullableReader
+{
+    fn read_col(&mut self, col_data:&mut StoragePtr) -> std::io::Result<()>
+    {
+        let rows = col_data.len();
+        let mut bitmap = vec![0u8; rows.div_ceil(8)];
+        self.nulls.read(&mut bitmap)?;
+        self.values.read_col(col_data)?;
+        *col_data.nulls_mut().unwrap() = (0..rows).map(|row| bitmap[row / 8] & (1 << (row % 8)) != 0).collect();
+        Ok(())
+    }
+}
+
+fn pack_bitmap(nulls:&[bool]) -> Vec<u8>
+{
+    let mut res = vec![0u8; nulls.len().div_ceil(8)];
+    for (row, _) in nulls.iter().enumerate().filter(|(_, n)| **n)
+    {
+        res[row / 8] |= 1 << (row % 8);
+    }
+    res
+}
+
+/* ======== LowCardinality ====== */
+
+//Each chunk of LowCardinality(String) is the chunk of the dictionary followed by the chunk of the codes.
+//Only the strings used by the chunk get to its dictionary
+pub struct LowCardinalityWriter
+{
+    dict :ChunkWriter<DBString, SharedWriter>,
+    codes :ChunkWriter<DBUInt32, SharedWriter>,
+}
+
+impl LowCardinalityWriter
+{
+    pub fn new(dest:impl Write + 'static) -> Self
+    {
+        let dest:Rc<RefCell<Box<dyn Write>>> = Rc::new(RefCell::new(Box::new(dest)));
+        Self{
+            dict:ChunkWriter::new(SharedWriter(dest.clone())),
+            codes:ChunkWriter::new(SharedWriter(dest))
+        }
+    }
+}
+
+impl ColDataWriter for LowCardinalityWriter
+{
+    fn write_col(&mut self, col_data:&StoragePtr) -> std::io::Result<()>
+    {
+        let lc = downcast_low_cardinality_ref(col_data.as_ref()).unwrap();
+        let mut chunk_codes = vec![None; lc.dict().len()];
+        let mut dict = Vec::<String>::new();
+        let codes:Vec<u32> = lc.codes().iter().map(
+            |c| *chunk_codes[*c as usize].get_or_insert_with(|| {
+                dict.push(lc.dict()[*c as usize].clone());
+                dict.len() as u32 - 1
+            })
+        ).collect();
+        self.dict.write(&dict)?;
+        self.codes.write(&codes)
+    }
+}
+
+pub struct LowCardinalityReader
+{
+    dict :ChunkReader<DBString, SharedReader>,
+    codes :ChunkReader<DBUInt32, SharedReader>,
+}
+
+impl LowCardinalityReader
+{
+    pub fn new(src:impl Read + 'static) -> Self
+    {
+        let src:Rc<RefCell<Box<dyn Read>>> = Rc::new(RefCell::new(Box::new(src)));
+        Self{
+            dict:ChunkReader::new(SharedReader(src.clone())),
+            codes:ChunkReader::new(SharedReader(src))
+        }
+    }
+}
+
+impl ColDataReader for LowCardinalityReader
+{
+    fn read_col(&mut self, col_data:&mut StoragePtr) -> std::io::Result<()>
+    {
+        let mut dict = Vec::<String>::new();
+        self.dict.read_resized(&mut dict)?;
+        let lc = downcast_low_cardinality_mut(col_data.as_mut()).unwrap();
+        self.codes.read(lc.codes_mut())?;
+        if lc.codes().iter().any(|c| *c as usize >= dict.len())
+        {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "code is out of the dictionary"));
+        }
+        //The dictionary of the storage is replaced by the one of the chunk, so it doesn't grow over the chunks read into it
+        lc.set_dict(&dict);
+        let codes:Vec<u32> = dict.iter().map(|s| lc.find_code(s).unwrap()).collect();
+        for c in lc.codes_mut().iter_mut()
+        {
+            *c = codes[*c as usize];
+        }
+        Ok(())
+    }
+}
+
+/* ======== Array ====== */
+
+//Each chunk of Array(T) is the chunk of the end offsets of the rows followed by the chunk of all their elements
+pub struct ArrayWriter
+{
+    offsets :ChunkWriter<DBUInt64, SharedWriter>,
+    values :ColWriterPtr,
+}
+
+impl ArrayWriter
+{
+    pub fn new(elem:TypeName, dest:impl Write + 'static) -> Self
+    {
+        let dest:Rc<RefCell<Box<dyn Write>>> = Rc::new(RefCell::new(Box::new(dest)));
+        Self{
+            offsets:ChunkWriter::new(SharedWriter(dest.clone())),
+            values:make_col_writer(elem, SharedWriter(dest))
+        }
+    }
+}
+
+impl ColDataWriter for ArrayWriter
+{
+    fn write_col(&mut self, col_data:&StoragePtr) -> std::io::Result<()>
+    {
+        let arr = downcast_array_ref(col_data.as_ref()).unwrap();
+        self.offsets.write(arr.offsets())?;
+        self.values.write_col(arr.values())
+    }
+}
+
+pub struct ArrayReader
+{
+    offsets :ChunkReader<DBUInt64, SharedReader>,
+    values :ColReaderPtr,
+}
+
+impl ArrayReader
+{
+    pub fn new(elem:TypeName, src:impl Read + 'static) -> Self
+    {
+        let src:Rc<RefCell<Box<dyn Read>>> = Rc::new(RefCell::new(Box::new(src)));
+        Self{
+            offsets:ChunkReader::new(SharedReader(src.clone())),
+            values:make_col_reader(elem, SharedReader(src))
+        }
+    }
+}
+
+impl ColDataReader for ArrayReader
+{
+    fn read_col(&mut self, col_data:&mut StoragePtr) -> std::io::Result<()>
+    {
+        let arr = downcast_array_mut(col_data.as_mut()).unwrap();
+        self.offsets.read(arr.offsets_mut())?;
+        if arr.offsets().windows(2).any(|w| w[0] > w[1])
+        {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "array offsets aren't sorted"));
+        }
+        let values_len = arr.offsets().last().copied().unwrap_or(0) as usize;
+        arr.values_mut().resize(values_len);
+        self.values.read_col(arr.values_mut())
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -113,4 +453,100 @@ mod test {
 
         cleanup_file("./test.col");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn write_read_nullable()
+    {
+        cleanup_file("./test_nullable.col");
+        let type_name = TypeName::DBString.to_nullable();
+        let mut writer = make_col_writer(type_name.clone(), File::create("./test_nullable.col").unwrap());
+        let mut c = make_storage(type_name.clone());
+        c.resize(10);
+        downcast_storage_mut::<DBString>(c.as_mut()).unwrap().data_mut()[0] = "a".to_string();
+        c.nulls_mut().unwrap()[1] = true;
+        c.nulls_mut().unwrap()[9] = true;
+        writer.write_col(&c).unwrap();
+        writer.write_col(&c).unwrap();
+        drop(writer);
+
+        let mut reader = make_col_reader(type_name.clone(), File::open("./test_nullable.col").unwrap());
+        for _ in 0..2
+        {
+            let mut res = make_storage(type_name.clone());
+            res.resize(10);
+            reader.read_col(&mut res).unwrap();
+            assert_eq!(*res.nulls().unwrap(), (0..10).map(|row| row == 1 || row == 9).collect::<Vec<bool>>());
+            assert_eq!(res.to_string_at(0), "a");
+            assert_eq!(res.to_string_at(1), "NULL");
+        }
+        cleanup_file("./test_nullable.col");
+    }
+
+    #[test]
+    fn write_read_low_cardinality()
+    {
+        cleanup_file("./test_low_cardinality.col");
+        let type_name = TypeName::DBLowCardinality.to_nullable();
+        let mut writer = make_col_writer(type_name.clone(), File::create("./test_low_cardinality.col").unwrap());
+        let mut c = make_storage(type_name.clone());
+        c.resize(3);
+        let lc = downcast_low_cardinality_mut(c.as_mut()).unwrap();
+        lc.set_value(0, "unused");
+        lc.set_value(0, "ios");
+        lc.set_value(2, "ios");
+        c.nulls_mut().unwrap()[1] = true;
+        writer.write_col(&c).unwrap();
+        downcast_low_cardinality_mut(c.as_mut()).unwrap().set_value(0, "web");
+        writer.write_col(&c).unwrap();
+        downcast_low_cardinality_mut(c.as_mut()).unwrap().set_value(2, "tv");
+        writer.write_col(&c).unwrap();
+        drop(writer);
+
+        let mut reader = make_col_reader(type_name.clone(), File::open("./test_low_cardinality.col").unwrap());
+        let mut res = make_storage(type_name.clone());
+        res.resize(3);
+        reader.read_col(&mut res).unwrap();
+        assert_eq!((0..3).map(|i| res.to_string_at(i)).collect::<Vec<_>>(), vec!["ios", "NULL", "ios"]);
+        reader.read_col(&mut res).unwrap();
+        assert_eq!((0..3).map(|i| res.to_string_at(i)).collect::<Vec<_>>(), vec!["web", "NULL", "ios"]);
+        //The strings which aren't used by the chunks aren't stored
+        let lc = downcast_low_cardinality_ref(res.as_ref()).unwrap();
+        assert!(lc.find_code("unused").is_none());
+        //Only the dictionary of the last chunk is kept
+        reader.read_col(&mut res).unwrap();
+        assert_eq!((0..3).map(|i| res.to_string_at(i)).collect::<Vec<_>>(), vec!["web", "NULL", "tv"]);
+        let lc = downcast_low_cardinality_ref(res.as_ref()).unwrap();
+        assert!(lc.find_code("ios").is_none());
+        assert_eq!(lc.dict().len(), 3);
+        cleanup_file("./test_low_cardinality.col");
+    }
+
+    #[test]
+    fn write_read_array()
+    {
+        cleanup_file("./test_array.col");
+        let type_name = TypeName::DBArray(Box::new(TypeName::DBString.to_nullable()));
+        let mut writer = make_col_writer(type_name.clone(), File::create("./test_array.col").unwrap());
+        let mut c = make_storage(type_name.clone());
+        c.resize(3);
+        let mut values = make_storage(TypeName::DBString.to_nullable());
+        values.resize(3);
+        downcast_storage_mut::<DBString>(values.as_mut()).unwrap().data_mut()[0] = "a".to_string();
+        values.nulls_mut().unwrap()[1] = true;
+        downcast_array_mut(c.as_mut()).unwrap().replace_rows(0, &[2, 0, 1], &values);
+        writer.write_col(&c).unwrap();
+        c.resize(1);
+        writer.write_col(&c).unwrap();
+        drop(writer);
+
+        let mut reader = make_col_reader(type_name.clone(), File::open("./test_array.col").unwrap());
+        let mut res = make_storage(type_name.clone());
+        res.resize(3);
+        reader.read_col(&mut res).unwrap();
+        assert_eq!((0..3).map(|i| res.to_string_at(i)).collect::<Vec<_>>(), vec!["['a', NULL]", "[]", "['']"]);
+        res.resize(1);
+        reader.read_col(&mut res).unwrap();
+        assert_eq!(res.to_string_at(0), "['a', NULL]");
+        cleanup_file("./test_array.col");
+    }
+}
@@ -80,6 +80,17 @@ impl<T:DBType, R:Read> ChunkReader<T, R>
         }
     }
     pub fn read(&mut self, data:&mut Vec<T::InnerType>) -> std::io::Result<()>
+    {
+        self.read_chunk(data, false)
+    }
+
+    //Reads the chunk of any size, data is resized to it
+    pub fn read_resized(&mut self, data:&mut Vec<T::InnerType>) -> std::io::Result<()>
+    {
+        self.read_chunk(data, true)
+    }
+
+    fn read_chunk(&mut self, data:&mut Vec<T::InnerType>, resize:bool) -> std::io::Result<()>
     {
 
         let mut chunk_size:u32 = 0;
@@ -88,6 +99,10 @@ impl<T:DBType, R:Read> ChunkReader<T, R>
         chunk_size.from_byte(&mut self.src)?;
         uncompressed_size.from_byte(&mut self.src)?;
         compressed_size.from_byte(&mut self.src)?;
+        if resize
+        {
+            data.resize(chunk_size as usize, Default::default());
+        }
         if chunk_size != data.len() as u32
         {
             return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "chunk don't match dest data size"))
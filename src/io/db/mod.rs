@@ -5,6 +5,7 @@ use crate::columns::header::ColumnHeader;
 use crate::types::TypeName;
 use std::path::*;
 use std::fs::*;
+use std::io::Write;
 
 pub fn read_schema(p: impl AsRef<Path>) -> std::io::Result<Schema> {
     let mut f = File::open(p)?;
@@ -115,6 +116,23 @@ pub fn table_size_iterator(table:&Table) -> std::io::Result<BlockSizeIter>
     Ok(BlockSizeIter::new(file))
 }
 
+//The new file is written aside and renamed over the old one, so readers see either the old sizes or all of the new ones.
+//The chunks of the new blocks must be written to the column files before
+pub fn append_table_sizes(table:&Table, sizes:&[u32]) -> std::io::Result<()>
+{
+    let path = table.sizes_file_path();
+    let mut content = if path.exists() {read(&path)?} else {Vec::new()};
+    for size in sizes
+    {
+        size.to_byte(&mut content)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    let mut tmp = File::create(&tmp_path)?;
+    tmp.write_all(&content)?;
+    tmp.sync_all()?;
+    rename(&tmp_path, &path)
+}
+
 
 #[cfg(test)]
 mod test
@@ -195,4 +213,18 @@ mod test
 
         remove_dir_all("test_db2").unwrap_or_default();
     }
+
+    #[test]
+    fn append_sizes_test()
+    {
+        let sch = Schema::from(vec![ColumnHeader::new("f", TypeName::DBInt)]);
+        make_test_table_dir("test_db3", &Table::new("", "test_tb", sch)).unwrap();
+        let tb = open_table("test_db3", "test_tb").unwrap();
+        append_table_sizes(&tb, &[6, 4]).unwrap();
+        append_table_sizes(&tb, &[3]).unwrap();
+        assert_eq!(table_size_iterator(&tb).unwrap().collect::<Vec<u32>>(), vec![6, 4, 3]);
+        assert!(!tb.path().join("_sizes.tmp").exists());
+
+        remove_dir_all("test_db3").unwrap_or_default();
+    }
 }
\ No newline at end of file
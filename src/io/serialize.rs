@@ -1,4 +1,5 @@
 use std::io::{Read, Write};
+use crate::types::time::{Date, DateTime};
 pub trait NativeByte {}
 
 
@@ -13,12 +14,16 @@ make_native_byte!(i8);
 make_native_byte!(i16);
 make_native_byte!(i32);
 make_native_byte!(i64);
+make_native_byte!(i128);
 make_native_byte!(u8);
 make_native_byte!(u16);
 make_native_byte!(u32);
 make_native_byte!(u64);
 make_native_byte!(f32);
 make_native_byte!(f64);
+//Transparent wrappers of i32 and i64
+make_native_byte!(Date);
+make_native_byte!(DateTime);
 
 pub trait ByteSerialize
 {
@@ -79,6 +84,49 @@ impl<T:NativeByte> ByteSerialize for T
     }
 }
 
+//Bool is stored as one byte, any non zero byte is read as true
+impl ByteSerialize for Vec<bool>
+{
+    fn size_in_bytes(&self) -> usize
+    {
+        self.len()
+    }
+    fn to_byte(&self, dest:&mut impl Write) -> std::io::Result<()>
+    {
+        let bytes:Vec<u8> = self.iter().map(|v| *v as u8).collect();
+        bytes.to_byte(dest)
+    }
+    fn from_byte(&mut self, src: &mut (impl Read + ?Sized)) -> std::io::Result<()>
+    {
+        let mut bytes = Vec::<u8>::new();
+        bytes.resize(self.len(), 0);
+        bytes.from_byte(src)?;
+        for (b, v) in bytes.into_iter().zip(self.iter_mut())
+        {
+            *v = b != 0;
+        }
+        Ok(())
+    }
+}
+impl ByteSerialize for bool
+{
+    fn size_in_bytes(&self) -> usize
+    {
+        1
+    }
+    fn to_byte(&self, dest:&mut impl Write) -> std::io::Result<()>
+    {
+        (*self as u8).to_byte(dest)
+    }
+    fn from_byte(&mut self, src: &mut (impl Read + ?Sized)) -> std::io::Result<()>
+    {
+        let mut byte:u8 = 0;
+        byte.from_byte(src)?;
+        *self = byte != 0;
+        Ok(())
+    }
+}
+
 /*
 FIXME: This is quite inefficient due to the large number of allocations in the process.
 The best solution would be to write a type that stores an array of strings in a flat form,
@@ -199,4 +247,20 @@ mod test {
         r.from_byte(&mut buff.as_slice()).unwrap();
         assert_eq!(v, r);
     }
+    #[test]
+    fn bool_io()
+    {
+        let v = Vec::<bool>::from([true, false, false, true]);
+        let mut buff = Vec::<u8>::with_capacity(4);
+        v.to_byte(&mut buff).unwrap();
+        assert_eq!(buff, Vec::<u8>::from([1, 0, 0, 1]));
+        let mut r = Vec::<bool>::new();
+        r.resize(v.len(), false);
+        r.from_byte(&mut [2u8, 0, 1, 0].as_slice()).unwrap();
+        assert_eq!(r, vec![true, false, true, false]);
+
+        let mut single = false;
+        single.from_byte(&mut [1u8].as_slice()).unwrap();
+        assert!(single);
+    }
 }
\ No newline at end of file
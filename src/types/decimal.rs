@@ -0,0 +1,84 @@
+//Values of Decimal(P, S) are integers scaled by 10^S, the scale is kept in the type of the column
+
+pub const MAX_PRECISION :u8 = 38;
+//Decimals up to the precision are stored as i64, the wider ones as i128
+pub const MAX_PRECISION_64 :u8 = 18;
+
+pub fn pow10(n:u8) -> i128
+{
+    10i128.pow(n as u32)
+}
+
+//The value fits P digits
+pub fn fits_precision(v:i128, precision:u8) -> bool
+{
+    v.unsigned_abs() < pow10(precision) as u128
+}
+
+//Changes the scale of the value, the extra digits are truncated
+pub fn rescale(v:i128, from:u8, to:u8) -> Option<i128>
+{
+    if to >= from
+    {
+        v.checked_mul(pow10(to - from))
+    }
+    else
+    {
+        Some(v / pow10(from - to))
+    }
+}
+
+//[-]digits[.digits], the extra fraction digits are truncated
+pub fn parse_decimal(s:&str, scale:u8) -> Option<i128>
+{
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s))
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty() && frac_part.is_empty() || !(int_part.chars().chain(frac_part.chars())).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut v:i128 = 0;
+    for c in int_part.chars().chain(frac_part.chars().chain(std::iter::repeat('0')).take(scale as usize))
+    {
+        v = v.checked_mul(10)?.checked_add(c.to_digit(10).unwrap() as i128)?;
+    }
+    Some(if negative {-v} else {v})
+}
+
+pub fn format_decimal(v:i128, scale:u8) -> String
+{
+    if scale == 0
+    {
+        return v.to_string();
+    }
+    let digits = format!("{:0width$}", v.unsigned_abs(), width = scale as usize + 1);
+    let (int_part, frac_part) = digits.split_at(digits.len() - scale as usize);
+    format!("{}{}.{}", if v < 0 {"-"} else {""}, int_part, frac_part)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_format()
+    {
+        assert_eq!(parse_decimal("12.5", 2), Some(1250));
+        assert_eq!(parse_decimal("-0.019", 2), Some(-1));
+        assert_eq!(parse_decimal(" 7 ", 1), Some(70));
+        assert_eq!(parse_decimal(".5", 1), Some(5));
+        assert_eq!(parse_decimal("1.2.3", 1), None);
+        assert_eq!(parse_decimal("-", 1), None);
+        assert_eq!(format_decimal(1250, 2), "12.50");
+        assert_eq!(format_decimal(-5, 3), "-0.005");
+        assert_eq!(format_decimal(42, 0), "42");
+        assert_eq!(rescale(1250, 2, 0), Some(12));
+        assert_eq!(rescale(-125, 2, 4), Some(-12500));
+        assert!(fits_precision(999, 3));
+        assert!(!fits_precision(-1000, 3));
+    }
+}
@@ -1,36 +1,158 @@
 pub mod types;
+pub mod time;
+pub mod decimal;
 use std::fmt::{Debug, Display};
 use std::fmt;
 
 
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TypeName
 {
     DBInt,
+    DBInt8,
+    DBInt16,
+    DBInt32,
+    DBUInt8,
+    DBUInt16,
+    DBUInt32,
+    DBUInt64,
     DBFloat,
-    DBString
+    DBString,
+    DBBool,
+    DBDate,
+    DBDateTime,
+    //Decimal(precision, scale)
+    DBDecimal(u8, u8),
+    //LowCardinality(String), dictionary encoded strings
+    DBLowCardinality,
+    //Type of NULL literal
+    DBNothing,
+    DBNullable(Box<TypeName>),
+    //Array(T), T may be Nullable or another Array
+    DBArray(Box<TypeName>)
+}
+
+impl TypeName
+{
+    //Nullable(T) of the type, Nullable isn't nested
+    pub fn to_nullable(&self) -> TypeName
+    {
+        match self {
+            TypeName::DBNullable(_) => self.clone(),
+            other => TypeName::DBNullable(Box::new(other.clone()))
+        }
+    }
+    pub fn is_nullable(&self) -> bool
+    {
+        matches!(self, TypeName::DBNullable(_))
+    }
+    //Int and the narrower integers
+    pub fn is_integer(&self) -> bool
+    {
+        matches!(self,
+            TypeName::DBInt | TypeName::DBInt8 | TypeName::DBInt16 | TypeName::DBInt32
+            | TypeName::DBUInt8 | TypeName::DBUInt16 | TypeName::DBUInt32 | TypeName::DBUInt64
+        )
+    }
+    //Decimal(P, S) with 1 <= P <= 38 and S <= P
+    pub fn decimal(precision:u8, scale:u8) -> Result<TypeName, String>
+    {
+        if precision == 0 || precision > decimal::MAX_PRECISION || scale > precision
+        {
+            return Err(format!("Wrong Decimal({}, {}): precision must be from 1 to {}, scale up to the precision", precision, scale, decimal::MAX_PRECISION));
+        }
+        Ok(TypeName::DBDecimal(precision, scale))
+    }
+    //T of Array(T)
+    pub fn element(&self) -> Option<&TypeName>
+    {
+        match self {
+            TypeName::DBArray(elem) => Some(elem),
+            _ => None
+        }
+    }
+    //T of Nullable(T), the type itself otherwise
+    pub fn base(&self) -> &TypeName
+    {
+        match self {
+            TypeName::DBNullable(inner) => inner,
+            other => other
+        }
+    }
 }
 
 impl TryFrom<String> for TypeName {
     type Error = &'static str;
     fn try_from(s: String) -> Result<Self, Self::Error> {
+        if let Some(inner) = s.strip_prefix("Nullable(").and_then(|rest| rest.strip_suffix(')'))
+        {
+            return Ok(TypeName::try_from(inner.to_string())?.to_nullable());
+        }
+        if let Some(elem) = s.strip_prefix("Array(").and_then(|rest| rest.strip_suffix(')'))
+        {
+            return Ok(TypeName::DBArray(Box::new(TypeName::try_from(elem.to_string())?)));
+        }
+        if let Some(args) = s.strip_prefix("Decimal(").and_then(|rest| rest.strip_suffix(')'))
+        {
+            let (precision, scale) = args.split_once(',').ok_or("undefined type")?;
+            let precision = precision.trim().parse::<u8>().map_err(|_| "undefined type")?;
+            let scale = scale.trim().parse::<u8>().map_err(|_| "undefined type")?;
+            return TypeName::decimal(precision, scale).map_err(|_| "wrong decimal precision or scale");
+        }
         match s.as_ref() {
-            "Int" => Ok(TypeName::DBInt),
+            "Int" | "Int64" => Ok(TypeName::DBInt),
+            "Int8" => Ok(TypeName::DBInt8),
+            "Int16" => Ok(TypeName::DBInt16),
+            "Int32" => Ok(TypeName::DBInt32),
+            "UInt8" => Ok(TypeName::DBUInt8),
+            "UInt16" => Ok(TypeName::DBUInt16),
+            "UInt32" => Ok(TypeName::DBUInt32),
+            "UInt64" => Ok(TypeName::DBUInt64),
             "Float" => Ok(TypeName::DBFloat),
             "String" => Ok(TypeName::DBString),
+            "Bool" => Ok(TypeName::DBBool),
+            "Date" => Ok(TypeName::DBDate),
+            "DateTime" => Ok(TypeName::DBDateTime),
+            "Nothing" => Ok(TypeName::DBNothing),
+            "LowCardinality(String)" => Ok(TypeName::DBLowCardinality),
             _ => Err("undefined type")
         }
     }
 }
 impl Display for TypeName {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let TypeName::DBNullable(inner) = self
+        {
+            return write!(f, "Nullable({})", inner);
+        }
+        if let TypeName::DBDecimal(precision, scale) = self
+        {
+            return write!(f, "Decimal({}, {})", precision, scale);
+        }
+        if let TypeName::DBArray(elem) = self
+        {
+            return write!(f, "Array({})", elem);
+        }
         write!(f,
              "{}",
              match self {
                  TypeName::DBInt => "Int",
+                 TypeName::DBInt8 => "Int8",
+                 TypeName::DBInt16 => "Int16",
+                 TypeName::DBInt32 => "Int32",
+                 TypeName::DBUInt8 => "UInt8",
+                 TypeName::DBUInt16 => "UInt16",
+                 TypeName::DBUInt32 => "UInt32",
+                 TypeName::DBUInt64 => "UInt64",
                  TypeName::DBFloat => "Float",
                  TypeName::DBString => "String",
+                 TypeName::DBBool => "Bool",
+                 TypeName::DBDate => "Date",
+                 TypeName::DBDateTime => "DateTime",
+                 TypeName::DBNothing => "Nothing",
+                 TypeName::DBLowCardinality => "LowCardinality(String)",
+                 TypeName::DBDecimal(..) | TypeName::DBNullable(_) | TypeName::DBArray(_) => unreachable!(),
              }
         )
     }
@@ -55,4 +177,46 @@ mod test
         assert_eq!(TypeName::DBFloat.to_string(), "Float")
     }
 
+    #[test]
+    fn nullable()
+    {
+        let t = TypeName::try_from("Nullable(Int)".to_string()).unwrap();
+        assert_eq!(t, TypeName::DBInt.to_nullable());
+        assert_eq!(t.to_string(), "Nullable(Int)");
+        assert_eq!(t.to_nullable(), t);
+        assert_eq!(*t.base(), TypeName::DBInt);
+        assert!(TypeName::try_from("Nullable(Int".to_string()).is_err());
+        assert_eq!(TypeName::try_from("Nullable(UInt8)".to_string()).unwrap().to_string(), "Nullable(UInt8)");
+    }
+
+    #[test]
+    fn decimal()
+    {
+        let t = TypeName::try_from("Decimal(10,2)".to_string()).unwrap();
+        assert_eq!(t, TypeName::DBDecimal(10, 2));
+        assert_eq!(t.to_string(), "Decimal(10, 2)");
+        assert_eq!(TypeName::try_from("Nullable(Decimal(38, 0))".to_string()).unwrap(), TypeName::DBDecimal(38, 0).to_nullable());
+        assert!(TypeName::try_from("Decimal(39, 2)".to_string()).is_err());
+        assert!(TypeName::try_from("Decimal(2, 3)".to_string()).is_err());
+    }
+
+    #[test]
+    fn low_cardinality()
+    {
+        let t = TypeName::try_from("Nullable(LowCardinality(String))".to_string()).unwrap();
+        assert_eq!(t, TypeName::DBLowCardinality.to_nullable());
+        assert_eq!(t.to_string(), "Nullable(LowCardinality(String))");
+        assert!(TypeName::try_from("LowCardinality(Int)".to_string()).is_err());
+    }
+
+    #[test]
+    fn array()
+    {
+        let t = TypeName::try_from("Array(Array(Nullable(Decimal(10, 2))))".to_string()).unwrap();
+        assert_eq!(t, TypeName::DBArray(Box::new(TypeName::DBArray(Box::new(TypeName::DBDecimal(10, 2).to_nullable())))));
+        assert_eq!(t.to_string(), "Array(Array(Nullable(Decimal(10, 2))))");
+        assert_eq!(t.element().and_then(|e| e.element()), Some(&TypeName::DBDecimal(10, 2).to_nullable()));
+        assert!(TypeName::try_from("Array(Int".to_string()).is_err());
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,182 @@
+use std::fmt;
+use std::str::FromStr;
+
+//Calendar of the types is proleptic Gregorian, time is UTC
+
+//Days since 1970-01-01
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Date(pub i32);
+
+//Seconds since 1970-01-01 00:00:00
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct DateTime(pub i64);
+
+const SECONDS_PER_DAY :i64 = 86400;
+
+//Days since epoch of the civil date, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year:i64, month:u32, day:u32) -> i64
+{
+    let y = if month <= 2 {year - 1} else {year};
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days:i64) -> (i64, u32, u32)
+{
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 {mp + 3} else {mp - 9} as u32;
+    let year = yoe + era * 400 + if month <= 2 {1} else {0};
+    (year, month, day)
+}
+
+fn days_in_month(year:i64, month:u32) -> u32
+{
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+impl Date
+{
+    pub fn from_ymd(year:i64, month:u32, day:u32) -> Option<Date>
+    {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month)
+        {
+            return None;
+        }
+        i32::try_from(days_from_civil(year, month, day)).ok().map(Date)
+    }
+    pub fn ymd(&self) -> (i64, u32, u32)
+    {
+        civil_from_days(self.0 as i64)
+    }
+    pub fn start_of_month(&self) -> Date
+    {
+        let (year, month, _) = self.ymd();
+        Date(days_from_civil(year, month, 1) as i32)
+    }
+}
+
+impl DateTime
+{
+    pub fn from_date(date:Date) -> DateTime
+    {
+        DateTime(date.0 as i64 * SECONDS_PER_DAY)
+    }
+    pub fn date(&self) -> Date
+    {
+        Date(self.0.div_euclid(SECONDS_PER_DAY) as i32)
+    }
+    pub fn hms(&self) -> (u32, u32, u32)
+    {
+        let secs = self.0.rem_euclid(SECONDS_PER_DAY) as u32;
+        (secs / 3600, secs / 60 % 60, secs % 60)
+    }
+    pub fn start_of_hour(&self) -> DateTime
+    {
+        DateTime(self.0 - self.0.rem_euclid(3600))
+    }
+}
+
+impl fmt::Display for Date
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let (year, month, day) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl fmt::Display for DateTime
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let (hour, minute, second) = self.hms();
+        write!(f, "{} {:02}:{:02}:{:02}", self.date(), hour, minute, second)
+    }
+}
+
+//Fixed width numbers of the format, e.g. YYYY-MM-DD
+fn parse_fields(s:&str, sep:char, widths:&[usize]) -> Option<Vec<u32>>
+{
+    let parts:Vec<&str> = s.split(sep).collect();
+    if parts.len() != widths.len()
+    {
+        return None;
+    }
+    parts.iter().zip(widths.iter()).map(
+        |(p, w)| if p.len() == *w && p.chars().all(|c| c.is_ascii_digit()) {p.parse::<u32>().ok()} else {None}
+    ).collect()
+}
+
+//YYYY-MM-DD
+impl FromStr for Date
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        parse_fields(s, '-', &[4, 2, 2]).and_then(
+            |f| Date::from_ymd(f[0] as i64, f[1], f[2])
+        ).ok_or(format!("Wrong date '{}'", s))
+    }
+}
+
+//YYYY-MM-DD hh:mm:ss, the time may be omitted
+impl FromStr for DateTime
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let err = || format!("Wrong date and time '{}'", s);
+        let (date, time) = match s.split_once(' ') {
+            Some((date, time)) => (date, Some(time)),
+            None => (s, None)
+        };
+        let date = Date::from_str(date).map_err(|_| err())?;
+        let secs = match time.map(|t| parse_fields(t, ':', &[2, 2, 2])) {
+            None => 0,
+            Some(Some(f)) if f[0] < 24 && f[1] < 60 && f[2] < 60 => f[0] * 3600 + f[1] * 60 + f[2],
+            Some(_) => return Err(err())
+        };
+        Ok(DateTime(DateTime::from_date(date).0 + secs as i64))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dates()
+    {
+        assert_eq!(Date::from_str("1970-01-01"), Ok(Date(0)));
+        assert_eq!(Date::from_str("2024-03-01").unwrap().0, 19783);
+        assert_eq!(Date(19783).to_string(), "2024-03-01");
+        assert_eq!(Date(-1).to_string(), "1969-12-31");
+        assert_eq!(Date::from_str("2024-02-29").unwrap().start_of_month().to_string(), "2024-02-01");
+        assert!(Date::from_str("2023-02-29").is_err());
+        assert!(Date::from_str("2024-1-01").is_err());
+
+        let t = DateTime::from_str("2024-03-01 13:05:09").unwrap();
+        assert_eq!(t.to_string(), "2024-03-01 13:05:09");
+        assert_eq!(t.start_of_hour().to_string(), "2024-03-01 13:00:00");
+        assert_eq!(t.date(), Date(19783));
+        assert_eq!(DateTime::from_str("2024-03-01").unwrap().to_string(), "2024-03-01 00:00:00");
+        assert!(DateTime::from_str("2024-03-01 24:00:00").is_err());
+    }
+}
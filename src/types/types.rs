@@ -1,4 +1,5 @@
 use super::{DBType, TypeName};
+use super::time::{Date, DateTime};
 
 macro_rules! make_type {
     ($name:ident, $display_name:expr, $inner_ty:ty) => {
@@ -18,8 +19,42 @@ macro_rules! make_type {
 }
 
 make_type!(DBInt, "Int", i64);
+make_type!(DBInt8, "Int8", i8);
+make_type!(DBInt16, "Int16", i16);
+make_type!(DBInt32, "Int32", i32);
+make_type!(DBUInt8, "UInt8", u8);
+make_type!(DBUInt16, "UInt16", u16);
+make_type!(DBUInt32, "UInt32", u32);
+make_type!(DBUInt64, "UInt64", u64);
 make_type!(DBFloat, "Float", f64);
 make_type!(DBString, "String", String);
+make_type!(DBBool, "Bool", bool);
+make_type!(DBDate, "Date", Date);
+make_type!(DBDateTime, "DateTime", DateTime);
+//Values of NULL literal are never read
+make_type!(DBNothing, "Nothing", u8);
+
+//Storage types of Decimal(P, S). Precision and scale are the part of the column type,
+//NAME is the widest decimal of the storage with scale 0
+macro_rules! make_decimal_type {
+    ($name:ident, $display_name:expr, $inner_ty:ty, $precision:expr) => {
+        #[derive(Debug, Clone)]
+        pub struct $name {}
+
+        impl DBType for $name {
+            type InnerType = $inner_ty;
+            const NAME: TypeName = TypeName::DBDecimal($precision, 0);
+            const STR_NAME:&'static str = $display_name;
+            fn to_type_string(&self) -> &str
+            {
+                $display_name
+            }
+        }
+    };
+}
+
+make_decimal_type!(DBDecimal64, "Decimal64", i64, 18);
+make_decimal_type!(DBDecimal128, "Decimal128", i128, 38);
 
 
 #[cfg(test)]